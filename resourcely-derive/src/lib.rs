@@ -0,0 +1,147 @@
+//! `#[derive(Resource)]` for resourcely payload types.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput, Error, LitInt, LitStr};
+
+/// Settings collected from the `#[resource(...)]` attributes.
+#[derive(Default)]
+struct ResourceAttributes {
+    url: Option<LitStr>,
+    file_name: Option<LitStr>,
+    ttl: Option<LitInt>,
+    format: Option<LitStr>,
+    cache_dir: Option<LitStr>,
+    namespace: Option<LitStr>,
+}
+
+impl ResourceAttributes {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut attributes = Self::default();
+
+        for attr in input.attrs.iter().filter(|a| a.path().is_ident("resource")) {
+            attr.parse_nested_meta(|meta| {
+                let slot = if meta.path.is_ident("url") {
+                    &mut attributes.url
+                } else if meta.path.is_ident("file_name") {
+                    &mut attributes.file_name
+                } else if meta.path.is_ident("format") {
+                    &mut attributes.format
+                } else if meta.path.is_ident("cache_dir") {
+                    &mut attributes.cache_dir
+                } else if meta.path.is_ident("namespace") {
+                    &mut attributes.namespace
+                } else if meta.path.is_ident("ttl") {
+                    attributes.ttl = Some(meta.value()?.parse()?);
+                    return Ok(());
+                } else {
+                    return Err(meta.error(
+                        "expected one of `url`, `file_name`, `ttl`, `format`, `cache_dir`, `namespace`",
+                    ));
+                };
+
+                *slot = Some(meta.value()?.parse()?);
+                Ok(())
+            })?;
+        }
+
+        Ok(attributes)
+    }
+}
+
+/// `FeatureFlags` -> `feature_flags`
+fn snake_case(name: &str) -> String {
+    let mut result = String::new();
+
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if index > 0 {
+                result.push('_');
+            }
+            result.extend(c.to_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+/// Implement `resourcely::Resource` for the payload type.
+///
+/// ```ignore
+/// #[derive(Resource, Serialize, Deserialize, Default)]
+/// #[resource(url = "https://example.com/flags.json", ttl = 300, cache_dir = "/var/cache/app")]
+/// struct FeatureFlags {
+///     enabled: bool,
+/// }
+///
+/// let reader = FeatureFlags::remote()?;
+/// ```
+///
+/// Attributes (all optional):
+/// - `url`: source of remote resources, or the file URL of local ones
+/// - `file_name`: defaults to the type name in snake case
+/// - `ttl`: cache timeout in seconds
+/// - `format`: `"json"` (default), `"yaml"`, `"toml"` or `"text"`
+/// - `cache_dir`, `namespace`: disk cache location
+#[proc_macro_derive(Resource, attributes(resource))]
+pub fn derive_resource(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let attributes = ResourceAttributes::parse(input)?;
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    let file_name = attributes
+        .file_name
+        .unwrap_or_else(|| LitStr::new(&snake_case(&name.to_string()), Span::call_site()));
+
+    let file_type = match &attributes.format {
+        None => quote!(Json),
+        Some(format) => match format.value().as_str() {
+            "json" => quote!(Json),
+            "yaml" | "yml" => quote!(Yaml),
+            "toml" => quote!(Toml),
+            "text" => quote!(Text),
+            _ => {
+                return Err(Error::new(
+                    format.span(),
+                    "expected `json`, `yaml`, `toml` or `text`",
+                ))
+            }
+        },
+    };
+
+    let url = attributes.url.map(|url| quote!(.url(#url)));
+    let ttl = attributes
+        .ttl
+        .map(|ttl| quote!(.timeout(::std::time::Duration::from_secs(#ttl))));
+    let cache_dir = attributes
+        .cache_dir
+        .map(|dir| quote!(.cache_directory(#dir)));
+    let namespace = attributes
+        .namespace
+        .map(|namespace| quote!(.namespace(#namespace)));
+
+    Ok(quote! {
+        impl #impl_generics ::resourcely::Resource for #name #type_generics #where_clause {
+            fn builder() -> ::resourcely::ResourceBuilder<Self> {
+                ::resourcely::ResourceBuilder::new()
+                    .file_name(#file_name)
+                    .file_type(::resourcely::ResourceFileType::#file_type)
+                    #url
+                    #ttl
+                    #cache_dir
+                    #namespace
+            }
+        }
+    })
+}
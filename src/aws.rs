@@ -0,0 +1,224 @@
+use std::sync::Arc;
+
+use aws_config::{BehaviorVersion, SdkConfig};
+use aws_sdk_ssm::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::OnceCell;
+
+use crate::{
+    base::{CacheScope, ResourceState},
+    error::ResourceError,
+    traits::{DataResult, ResourceReader},
+};
+
+/// Where the payload is stored in AWS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AwsSecretSource {
+    /// An SSM Parameter Store parameter; `SecureString` values are decrypted.
+    Parameter(String),
+    /// A Secrets Manager secret (its `SecretString`).
+    Secret(String),
+}
+
+/// Reader fetching an SSM parameter or a Secrets Manager secret through the AWS SDK.
+///
+/// Region and credentials come from the default provider chain of `aws-config`
+/// (environment, shared profile, SSO, web identity, ECS or instance role) unless a
+/// configuration is given with [`with_sdk_config`](Self::with_sdk_config). An
+/// `http(s)://` resource URL overrides the service endpoint (VPC or local endpoints);
+/// any other URL, e.g. `aws://ssm/app/flags`, only names the resource. The payload is
+/// parsed by the resource file type and cached in memory and on disk like a remote
+/// resource, or in memory only for secrets.
+pub struct AwsResourceReader<T> {
+    state: Arc<ResourceState<T>>,
+    config: OnceCell<SdkConfig>,
+    source: AwsSecretSource,
+    cache_scope: CacheScope,
+}
+
+impl<T> AwsResourceReader<T> {
+    pub fn new(state: impl Into<Arc<ResourceState<T>>>, source: AwsSecretSource) -> Self {
+        Self {
+            state: state.into(),
+            config: OnceCell::new(),
+            source,
+            cache_scope: CacheScope::MemoryAndDisk,
+        }
+    }
+
+    /// Keep the payload in memory only and never write it to the disk cache.
+    pub fn with_memory_only(mut self, memory_only: bool) -> Self {
+        self.cache_scope = match memory_only {
+            true => CacheScope::MemoryOnly,
+            false => CacheScope::MemoryAndDisk,
+        };
+        self
+    }
+
+    /// Use the given SDK configuration instead of loading the default one on the
+    /// first fetch.
+    pub fn with_sdk_config(mut self, config: SdkConfig) -> Self {
+        self.config = OnceCell::new_with(Some(config));
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ResourceReader<T> for AwsResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
+        &self.state
+    }
+
+    async fn get_data_or_error(
+        &self,
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        let fetch = async { self.fetch_value().await.map(|data| (data, None)) };
+
+        self.get_state()
+            .load_or_refresh(allow_stale, self.cache_scope, fetch)
+            .await
+    }
+}
+
+impl<T> AwsResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    async fn sdk_config(&self) -> &SdkConfig {
+        self.config
+            .get_or_init(|| aws_config::load_defaults(BehaviorVersion::latest()))
+            .await
+    }
+
+    fn endpoint_url(&self) -> Option<String> {
+        let url = self.get_state().get_url();
+        matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+    }
+
+    async fn fetch_value(&self) -> Result<Arc<T>, ResourceError> {
+        let config = self.sdk_config().await;
+
+        let value = match &self.source {
+            AwsSecretSource::Parameter(name) => {
+                let mut builder = aws_sdk_ssm::config::Builder::from(config);
+                if let Some(endpoint_url) = self.endpoint_url() {
+                    builder = builder.endpoint_url(endpoint_url);
+                }
+
+                aws_sdk_ssm::Client::from_conf(builder.build())
+                    .get_parameter()
+                    .name(name)
+                    .with_decryption(true)
+                    .send()
+                    .await
+                    .map_err(|e| sdk_error("GetParameter", e))?
+                    .parameter
+                    .and_then(|parameter| parameter.value)
+            }
+            AwsSecretSource::Secret(name) => {
+                let mut builder = aws_sdk_secretsmanager::config::Builder::from(config);
+                if let Some(endpoint_url) = self.endpoint_url() {
+                    builder = builder.endpoint_url(endpoint_url);
+                }
+
+                aws_sdk_secretsmanager::Client::from_conf(builder.build())
+                    .get_secret_value()
+                    .secret_id(name)
+                    .send()
+                    .await
+                    .map_err(|e| sdk_error("GetSecretValue", e))?
+                    .secret_string
+            }
+        }
+        .ok_or_else(|| ResourceError::Aws("the response holds no value".to_string()))?;
+
+        self.get_state().parse(&value).map(Arc::new)
+    }
+}
+
+/// Describe a failed SDK call, including the service error code when AWS returned one.
+fn sdk_error<E, R>(operation: &str, error: SdkError<E, R>) -> ResourceError
+where
+    E: ProvideErrorMetadata + std::error::Error + 'static,
+    R: std::fmt::Debug,
+{
+    let reason = match error.code() {
+        Some(code) => format!("{} failed: {}", operation, code),
+        None => format!("{} failed: {}", operation, DisplayErrorContext(&error)),
+    };
+    ResourceError::Aws(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::ResourceProps;
+    use crate::test_support::block_on;
+    use crate::testing::StubServer;
+    use crate::traits::ResourceFileType;
+    use aws_sdk_ssm::config::{Credentials, Region, SharedCredentialsProvider};
+    use std::collections::HashMap;
+
+    fn reader(
+        server: &StubServer,
+        source: AwsSecretSource,
+    ) -> AwsResourceReader<HashMap<String, u32>> {
+        let config = SdkConfig::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("eu-west-1"))
+            .credentials_provider(SharedCredentialsProvider::new(Credentials::for_tests()))
+            .build();
+        let state = ResourceState::new(ResourceProps::new(
+            "flags",
+            ResourceFileType::Yaml,
+            server.url("/"),
+            std::env::temp_dir(),
+            None,
+        ));
+
+        AwsResourceReader::new(state, source)
+            .with_memory_only(true)
+            .with_sdk_config(config)
+    }
+
+    #[test]
+    fn test_reads_parameter_through_the_sdk() {
+        let server = StubServer::start().unwrap();
+        server.fixture(
+            "/",
+            r#"{"Parameter":{"Name":"/app/flags","Type":"SecureString","Value":"a: 1"}}"#,
+        );
+
+        let reader = reader(
+            &server,
+            AwsSecretSource::Parameter("/app/flags".to_string()),
+        );
+        match block_on(reader.get_data_or_error(false)) {
+            Ok(DataResult::Fresh(data)) => assert_eq!(data.get("a"), Some(&1)),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+        assert_eq!(server.requests("POST", "/"), 1);
+    }
+
+    #[test]
+    fn test_reports_the_service_error_code() {
+        let server = StubServer::start().unwrap();
+        server.fixture_with_status(
+            "/",
+            400,
+            r#"{"__type":"ResourceNotFoundException","message":"Secrets Manager can't find the specified secret."}"#,
+        );
+
+        let reader = reader(&server, AwsSecretSource::Secret("app/db".to_string()));
+        match block_on(reader.get_data_or_error(false)) {
+            Err(ResourceError::Aws(reason)) => {
+                assert_eq!(reason, "GetSecretValue failed: ResourceNotFoundException")
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+}
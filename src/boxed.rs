@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    base::ResourceState,
+    error::ResourceError,
+    traits::{DataResult, ResourceReader, StalePolicy},
+};
+
+/// Type-erased, cloneable handle to any reader of `T`.
+///
+/// Lets readers of different kinds (remote, local, composite, ...) be kept in one
+/// collection or passed across an API boundary without naming their types. Calls are
+/// forwarded to the wrapped reader, including its own overrides of provided methods.
+pub struct BoxedReader<T> {
+    reader: Arc<dyn ResourceReader<T> + Send + Sync>,
+}
+
+impl<T> BoxedReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    pub fn new<R>(reader: R) -> Self
+    where
+        R: ResourceReader<T> + Send + Sync + 'static,
+    {
+        Self {
+            reader: Arc::new(reader),
+        }
+    }
+
+    /// Wrap a reader that is already shared.
+    pub fn from_arc<R>(reader: Arc<R>) -> Self
+    where
+        R: ResourceReader<T> + Send + Sync + 'static,
+    {
+        Self { reader }
+    }
+}
+
+impl<T> Clone for BoxedReader<T> {
+    fn clone(&self) -> Self {
+        Self {
+            reader: Arc::clone(&self.reader),
+        }
+    }
+}
+
+impl<T> From<Arc<dyn ResourceReader<T> + Send + Sync>> for BoxedReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    fn from(reader: Arc<dyn ResourceReader<T> + Send + Sync>) -> Self {
+        Self { reader }
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ResourceReader<T> for BoxedReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
+        self.reader.get_state()
+    }
+
+    fn mark_as_stale(&self) -> Result<(), ResourceError> {
+        self.reader.mark_as_stale()
+    }
+
+    fn is_marked_stale(&self) -> Result<bool, ResourceError> {
+        self.reader.is_marked_stale()
+    }
+
+    fn is_fresh(&self) -> Result<bool, ResourceError> {
+        self.reader.is_fresh()
+    }
+
+    async fn get_data_or_error(
+        &self,
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        self.reader.get_data_or_error(allow_stale).await
+    }
+
+    async fn get_data(&self, policy: StalePolicy) -> Result<DataResult<Arc<T>>, ResourceError> {
+        self.reader.get_data(policy).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::ResourceProps;
+    use crate::env::EnvResourceReader;
+    use crate::test_support::block_on;
+    use crate::testing::MockResourceReader;
+    use crate::traits::ResourceFileType;
+    use reqwest::Url;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    #[test]
+    fn test_heterogeneous_readers_in_one_collection() {
+        let variable = format!("RESOURCELY_BOXED_TEST_{}", std::process::id());
+        std::env::set_var(&variable, "{\"enabled\": 2}");
+
+        let mock = Arc::new(MockResourceReader::<HashMap<String, u32>>::new("mock"));
+        mock.push_fresh(HashMap::from([("enabled".to_string(), 1)]));
+        let env = EnvResourceReader::new(
+            ResourceState::new(ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                Url::parse(&format!("env:{}", variable)).unwrap(),
+                std::env::temp_dir().join("resourcely-boxed-missing"),
+                Some(Duration::from_secs(60)),
+            )),
+            &variable,
+        );
+
+        let readers = [BoxedReader::from_arc(mock.clone()), BoxedReader::new(env)];
+        let values: Vec<u32> = readers
+            .iter()
+            .map(|reader| block_on(reader.get_data_or_default_with(StalePolicy::Never))["enabled"])
+            .collect();
+
+        assert_eq!(values, vec![1, 2]);
+        assert_eq!(mock.calls(), 1);
+        assert_eq!(readers[1].clone().stats().unwrap().misses, 1);
+        std::env::remove_var(&variable);
+    }
+}
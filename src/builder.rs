@@ -1,28 +1,182 @@
 // src/builder.rs
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
-use crate::traits::{LocalResource, RemoteResource};
+use reqwest::cookie::Jar;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::redirect::Policy;
+use reqwest::{Certificate, Identity, Url};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::base::{
+    CacheLayout, CacheLocation, Durability, FileNameFormat, Redaction, RefreshPriority,
+    ResourceProps, ResourceState, SchemaMode, SerializationOptions, Transform, Weigher,
+};
+use crate::clock::Clock;
+use crate::command::CommandResourceReader;
+use crate::config::global_config;
+use crate::env::EnvResourceReader;
+use crate::events::EventListener;
+#[cfg(feature = "danger-insecure-tls")]
+use crate::events::ResourceEvent;
+use crate::file_system::FileSystem;
+use crate::local::DefaultLocalResourceReader;
+use crate::remote::{
+    AutoOfflinePolicy, DefaultRemoteResourceReader, LookupPolicy, ParseBackoff, RequestSigner,
+    DEFAULT_USER_AGENT,
+};
+use crate::schedule::CronSchedule;
+use crate::template::TemplatedResourceReader;
 use crate::ResourceFileType;
 
+/// Client certificate presented to origins requiring mutual TLS
+enum ClientIdentity {
+    Pkcs12 { der: Vec<u8>, password: String },
+    Pem { certificate: Vec<u8>, key: Vec<u8> },
+}
+
+/// Additional trusted root certificates, next to the system roots
+enum RootCertificates {
+    Pem(Vec<u8>),
+    Der(Vec<u8>),
+    PemBundle(Vec<u8>),
+    PemBundleFile(PathBuf),
+}
+
+impl RootCertificates {
+    fn load(&self) -> Result<Vec<Certificate>, String> {
+        let certificates = match self {
+            RootCertificates::Pem(pem) => Certificate::from_pem(pem).map(|c| vec![c]),
+            RootCertificates::Der(der) => Certificate::from_der(der).map(|c| vec![c]),
+            RootCertificates::PemBundle(bundle) => Certificate::from_pem_bundle(bundle),
+            RootCertificates::PemBundleFile(path) => {
+                let bundle = std::fs::read(path).map_err(|e| {
+                    format!("Unable to read the CA bundle '{}': {}", path.display(), e)
+                })?;
+                Certificate::from_pem_bundle(&bundle)
+            }
+        };
+
+        certificates.map_err(|e| format!("Invalid root certificate: {}", e))
+    }
+}
+
 /// Builder for creating resource instances with a fluent interface
 pub struct ResourceBuilder<T> {
     file_name: Option<String>,
     url: Option<String>,
-    cache_directory: Option<PathBuf>,
+    cache_location: Option<CacheLocation>,
     timeout: Option<Duration>,
     file_type: Option<ResourceFileType>,
+    serialization: SerializationOptions,
+    file_name_format: FileNameFormat,
+    layout: CacheLayout,
+    durability: Option<Durability>,
+    namespace: Option<String>,
+    tenant: Option<String>,
+    user_agent: Option<String>,
+    max_redirects: Option<usize>,
+    connect_timeout: Option<Duration>,
+    request_timeout: Option<Duration>,
+    default_headers: Vec<(String, String)>,
+    cookie_jar: Option<Arc<Jar>>,
+    request_signer: Option<Arc<dyn RequestSigner>>,
+    identity: Option<ClientIdentity>,
+    root_certificates: Vec<RootCertificates>,
+    event_listener: Option<EventListener>,
+    clock: Option<Arc<dyn Clock>>,
+    file_system: Option<Arc<dyn FileSystem>>,
+    write_behind: Option<bool>,
+    transforms: Vec<Arc<Transform<T>>>,
+    redaction: Option<Arc<Redaction>>,
+    weigher: Option<Arc<Weigher<T>>>,
+    memory_budget: Option<usize>,
+    weak_memory_cache: bool,
+    file_mode: Option<u32>,
+    strict_permissions: bool,
+    quarantine: bool,
+    strict_parse: bool,
+    metadata_sidecar: bool,
+    cache_index: bool,
+    schema_mode: SchemaMode,
+    lookup_policy: Option<LookupPolicy>,
+    max_stale: Option<Duration>,
+    auto_offline: Option<AutoOfflinePolicy>,
+    parse_backoff: Option<ParseBackoff>,
+    priority: RefreshPriority,
+    refresh_schedule: Option<CronSchedule>,
+    #[cfg(feature = "danger-insecure-tls")]
+    accept_invalid_certs: bool,
     _phantom: std::marker::PhantomData<T>,
 }
 
+/// Preset of caching options for [`ResourceBuilder::profile`], setting the cache
+/// timeout, the bound on stale data, the lookup order, how failing origins are retried
+/// and how the disk cache is written together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    /// Serve the cache as long as possible: an hour-long timeout, stale data of any
+    /// age when the origin fails, no requests for five minutes after three failed
+    /// connections, a parse backoff up to ten minutes, and disk writes off the fetch
+    /// path.
+    Aggressive,
+    /// Keep data close to the origin: a one-minute timeout, stale data at most 15
+    /// minutes old, no requests for 30 seconds after five failed connections, a parse
+    /// backoff up to a minute, and atomic disk writes.
+    Conservative,
+    /// Go to the origin on every read and never serve stale data, retrying on every
+    /// read; the disk cache is still written, for inspection, but not served.
+    NoCache,
+}
+
 impl<T> Default for ResourceBuilder<T> {
     fn default() -> Self {
         Self {
             file_name: None,
             url: None,
-            cache_directory: None,
+            cache_location: None,
             timeout: None,
             file_type: None,
+            serialization: SerializationOptions::default(),
+            file_name_format: FileNameFormat::default(),
+            layout: CacheLayout::default(),
+            durability: None,
+            namespace: None,
+            tenant: None,
+            user_agent: None,
+            max_redirects: None,
+            connect_timeout: None,
+            request_timeout: None,
+            default_headers: Vec::new(),
+            cookie_jar: None,
+            request_signer: None,
+            identity: None,
+            root_certificates: Vec::new(),
+            event_listener: None,
+            clock: None,
+            file_system: None,
+            write_behind: None,
+            transforms: Vec::new(),
+            redaction: None,
+            weigher: None,
+            memory_budget: None,
+            weak_memory_cache: false,
+            file_mode: Some(0o600),
+            strict_permissions: false,
+            quarantine: false,
+            strict_parse: false,
+            metadata_sidecar: false,
+            cache_index: false,
+            schema_mode: SchemaMode::default(),
+            lookup_policy: None,
+            max_stale: None,
+            auto_offline: None,
+            parse_backoff: None,
+            priority: RefreshPriority::default(),
+            refresh_schedule: None,
+            #[cfg(feature = "danger-insecure-tls")]
+            accept_invalid_certs: false,
             _phantom: std::marker::PhantomData,
         }
     }
@@ -30,7 +184,7 @@ impl<T> Default for ResourceBuilder<T> {
 
 impl<T> ResourceBuilder<T>
 where
-    T: Send + Sync + 'static,
+    T: Send + Sync + Serialize + DeserializeOwned + 'static,
 {
     /// Create a new builder with default values
     pub fn new() -> Self {
@@ -51,7 +205,15 @@ where
 
     /// Set the cache directory
     pub fn cache_directory(mut self, dir: impl Into<PathBuf>) -> Self {
-        self.cache_directory = Some(dir.into());
+        self.cache_location = Some(CacheLocation::Path(dir.into()));
+        self
+    }
+
+    /// Set where the cache directory is; unless set here or in the
+    /// [global config](crate::ResourcelyConfig), the platform's per-user cache
+    /// directory ([`CacheLocation::Default`])
+    pub fn cache_location(mut self, location: CacheLocation) -> Self {
+        self.cache_location = Some(location);
         self
     }
 
@@ -67,24 +229,877 @@ where
         self
     }
 
+    /// Set how the payload is serialized into the disk cache
+    pub fn serialization(mut self, options: SerializationOptions) -> Self {
+        self.serialization = options;
+        self
+    }
+
+    /// Set the naming scheme of the timestamped disk cache files
+    pub fn file_name_format(mut self, format: FileNameFormat) -> Self {
+        self.file_name_format = format;
+        self
+    }
+
+    /// Set the directory layout of the disk cache
+    pub fn layout(mut self, layout: CacheLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Set how durable disk cache saves are, e.g. [`Durability::Synced`] for resources
+    /// that must survive power loss
+    pub fn durability(mut self, durability: Durability) -> Self {
+        self.durability = Some(durability);
+        self
+    }
+
+    /// Keep the disk cache of this tenant apart, see [`ResourceProps::with_tenant`]
+    pub fn tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    /// Nest the disk cache into a namespace subdirectory (e.g. per environment)
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Set the `User-Agent` of remote requests (defaults to `resourcely/<version>`)
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Follow at most `limit` redirects (`0` disables them, so a redirect fails with
+    /// its status); the final URL is reported in the fetch metadata
+    pub fn max_redirects(mut self, limit: usize) -> Self {
+        self.max_redirects = Some(limit);
+        self
+    }
+
+    /// Fail remote requests that cannot connect within `timeout` with
+    /// [`ResourceError::ConnectTimeout`](crate::ResourceError::ConnectTimeout)
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Fail remote requests not completed within `timeout`, connecting included, with
+    /// [`ResourceError::RequestTimeout`](crate::ResourceError::RequestTimeout)
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Add a header sent with every remote request
+    pub fn default_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.default_headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Share a cookie jar with the remote reader, e.g. for session-authenticated origins;
+    /// cookies set during redirects are stored too
+    pub fn cookie_jar(mut self, jar: Arc<Jar>) -> Self {
+        self.cookie_jar = Some(jar);
+        self
+    }
+
+    /// Sign every remote request, e.g. for HMAC-authenticated internal APIs
+    pub fn request_signer(mut self, signer: Arc<dyn RequestSigner>) -> Self {
+        self.request_signer = Some(signer);
+        self
+    }
+
+    /// Authenticate with a PKCS#12 client certificate archive (mutual TLS)
+    pub fn client_identity_pkcs12(mut self, der: Vec<u8>, password: impl Into<String>) -> Self {
+        self.identity = Some(ClientIdentity::Pkcs12 {
+            der,
+            password: password.into(),
+        });
+        self
+    }
+
+    /// Authenticate with a PEM client certificate chain and PKCS#8 PEM private key (mutual TLS)
+    pub fn client_identity_pem(mut self, certificate: Vec<u8>, key: Vec<u8>) -> Self {
+        self.identity = Some(ClientIdentity::Pem { certificate, key });
+        self
+    }
+
+    /// Trust an additional PEM root certificate when verifying the origin
+    pub fn root_certificate_pem(mut self, pem: Vec<u8>) -> Self {
+        self.root_certificates.push(RootCertificates::Pem(pem));
+        self
+    }
+
+    /// Trust an additional DER root certificate when verifying the origin
+    pub fn root_certificate_der(mut self, der: Vec<u8>) -> Self {
+        self.root_certificates.push(RootCertificates::Der(der));
+        self
+    }
+
+    /// Trust every certificate of a PEM bundle (e.g. a corporate CA) for this resource only
+    pub fn ca_bundle_pem(mut self, bundle: Vec<u8>) -> Self {
+        self.root_certificates
+            .push(RootCertificates::PemBundle(bundle));
+        self
+    }
+
+    /// Trust every certificate of a PEM bundle file, read when the resource is built
+    pub fn ca_bundle_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.root_certificates
+            .push(RootCertificates::PemBundleFile(path.into()));
+        self
+    }
+
+    /// Receive the resource's events, e.g. offline transitions or TLS warnings
+    pub fn event_listener(mut self, listener: EventListener) -> Self {
+        self.event_listener = Some(listener);
+        self
+    }
+
+    /// Use the given clock for freshness decisions, e.g. a
+    /// [`ManualClock`](crate::state_manager::ManualClock) in tests.
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Keep the disk cache on the given file system, e.g. a
+    /// [`MemoryFileSystem`](crate::state_manager::MemoryFileSystem) in tests.
+    pub fn file_system(mut self, file_system: Arc<dyn FileSystem>) -> Self {
+        self.file_system = Some(file_system);
+        self
+    }
+
+    /// Write the disk cache on a background thread instead of the fetch path.
+    pub fn write_behind(mut self, enabled: bool) -> Self {
+        self.write_behind = Some(enabled);
+        self
+    }
+
+    /// Normalize freshly fetched data before it is cached, see
+    /// [`ResourceProps::with_transform`].
+    pub fn transform(mut self, transform: impl Fn(&mut T) + Send + Sync + 'static) -> Self {
+        self.transforms.push(Arc::new(transform));
+        self
+    }
+
+    /// Unix permission bits of saved cache files, `0o600` by default; `None` keeps
+    /// the process umask.
+    pub fn file_mode(mut self, mode: Option<u32>) -> Self {
+        self.file_mode = mode;
+        self
+    }
+
+    /// Refuse storage directories writable by group or others, see
+    /// [`ResourceProps::with_strict_permissions`].
+    pub fn strict_permissions(mut self, strict: bool) -> Self {
+        self.strict_permissions = strict;
+        self
+    }
+
+    /// Move unparsable cache files into a `quarantine/` subdirectory when the resource
+    /// is built, see [`ResourceProps::with_quarantine`].
+    pub fn quarantine(mut self, enabled: bool) -> Self {
+        self.quarantine = enabled;
+        self
+    }
+
+    /// Set the order in which memory, disk and network are consulted by remote
+    /// resources
+    pub fn lookup_policy(mut self, policy: LookupPolicy) -> Self {
+        self.lookup_policy = Some(policy);
+        self
+    }
+
+    /// Never serve stale data older than this, see [`ResourceProps::with_max_stale`]
+    pub fn max_stale(mut self, max_stale: Duration) -> Self {
+        self.max_stale = Some(max_stale);
+        self
+    }
+
+    /// Switch into offline mode after repeated connection failures, see
+    /// [`AutoOfflinePolicy`]
+    pub fn auto_offline(mut self, policy: AutoOfflinePolicy) -> Self {
+        self.auto_offline = Some(policy);
+        self
+    }
+
+    /// Back off from the origin while its content fails to parse, see [`ParseBackoff`]
+    pub fn parse_backoff(mut self, backoff: ParseBackoff) -> Self {
+        self.parse_backoff = Some(backoff);
+        self
+    }
+
+    /// Apply a preset of caching options. Only options not set yet are filled in, so
+    /// options set before or after it win over the preset
+    pub fn profile(mut self, profile: Profile) -> Self {
+        let (timeout, max_stale, lookup_policy, write_behind, durability) = match profile {
+            Profile::Aggressive => (
+                Duration::from_secs(3600),
+                None,
+                LookupPolicy::CacheFirst,
+                true,
+                Durability::Direct,
+            ),
+            Profile::Conservative => (
+                Duration::from_secs(60),
+                Some(Duration::from_secs(15 * 60)),
+                LookupPolicy::CacheFirst,
+                false,
+                Durability::Atomic,
+            ),
+            Profile::NoCache => (
+                Duration::ZERO,
+                Some(Duration::ZERO),
+                LookupPolicy::NetworkFirst,
+                false,
+                Durability::Direct,
+            ),
+        };
+        let (auto_offline, parse_backoff) = match profile {
+            Profile::Aggressive => (
+                Some(AutoOfflinePolicy {
+                    failure_threshold: 3,
+                    cooldown: Duration::from_secs(300),
+                }),
+                Some(ParseBackoff {
+                    initial: Duration::from_secs(1),
+                    max: Duration::from_secs(600),
+                }),
+            ),
+            Profile::Conservative => (
+                Some(AutoOfflinePolicy {
+                    failure_threshold: 5,
+                    cooldown: Duration::from_secs(30),
+                }),
+                Some(ParseBackoff {
+                    initial: Duration::from_secs(1),
+                    max: Duration::from_secs(60),
+                }),
+            ),
+            Profile::NoCache => (None, None),
+        };
+
+        self.timeout = self.timeout.or(Some(timeout));
+        self.max_stale = self.max_stale.or(max_stale);
+        self.lookup_policy = self.lookup_policy.or(Some(lookup_policy));
+        self.write_behind = self.write_behind.or(Some(write_behind));
+        self.durability = self.durability.or(Some(durability));
+        self.auto_offline = self.auto_offline.or(auto_offline);
+        self.parse_backoff = self.parse_backoff.or(parse_backoff);
+        self
+    }
+
+    /// Set the refresh priority, see [`RefreshPriority`]
+    pub fn priority(mut self, priority: RefreshPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Refresh on a cron schedule, see [`ResourceProps::with_refresh_schedule`]
+    pub fn refresh_schedule(mut self, schedule: CronSchedule) -> Self {
+        self.refresh_schedule = Some(schedule);
+        self
+    }
+
+    /// Fail on unparsable cache files instead of skipping them, see
+    /// [`ResourceProps::with_strict_parse`].
+    pub fn strict_parse(mut self, strict: bool) -> Self {
+        self.strict_parse = strict;
+        self
+    }
+
+    /// Write a metadata sidecar next to every disk cache file, see
+    /// [`ResourceProps::with_metadata_sidecar`].
+    pub fn metadata_sidecar(mut self, enabled: bool) -> Self {
+        self.metadata_sidecar = enabled;
+        self
+    }
+
+    /// Find the current cache file through an index of the cache directory instead of
+    /// scanning it, see [`ResourceProps::with_cache_index`].
+    pub fn cache_index(mut self, enabled: bool) -> Self {
+        self.cache_index = enabled;
+        self
+    }
+
+    /// Set how fetched documents not matching the payload type are handled, see
+    /// [`SchemaMode`]
+    pub fn schema_mode(mut self, mode: SchemaMode) -> Self {
+        self.schema_mode = mode;
+        self
+    }
+
+    /// Strip or mask sensitive data before it is written to the disk cache, see
+    /// [`ResourceProps::with_redaction`].
+    pub fn redaction(
+        mut self,
+        redaction: impl Fn(&mut serde_json::Value) + Send + Sync + 'static,
+    ) -> Self {
+        self.redaction = Some(Arc::new(redaction));
+        self
+    }
+
+    /// Estimate the in-memory size of the payload, see [`ResourceProps::with_weigher`].
+    pub fn weigher(mut self, weigher: impl Fn(&T) -> usize + Send + Sync + 'static) -> Self {
+        self.weigher = Some(Arc::new(weigher));
+        self
+    }
+
+    /// Keep payloads heavier than `bytes` out of the memory cache, see
+    /// [`ResourceProps::with_memory_budget`].
+    pub fn memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = Some(bytes);
+        self
+    }
+
+    /// Keep the memory cache only while callers hold the data, see
+    /// [`ResourceProps::with_weak_memory_cache`].
+    pub fn weak_memory_cache(mut self, enabled: bool) -> Self {
+        self.weak_memory_cache = enabled;
+        self
+    }
+
+    /// Accept invalid or self-signed origin certificates, for local development only.
+    ///
+    /// Building the remote resource emits [`ResourceEvent::InsecureTls`].
+    #[cfg(feature = "danger-insecure-tls")]
+    pub fn danger_accept_invalid_certs(mut self) -> Self {
+        self.accept_invalid_certs = true;
+        self
+    }
+
     /// Build a remote resource
-    pub fn build_remote(self) -> Result<impl RemoteResource<T>, String> {
-        let file_name = self.file_name.ok_or("File name is required")?;
-        let url = self.url.ok_or("URL is required for remote resources")?;
-        let cache_dir = self.cache_directory.unwrap_or_else(|| PathBuf::from("."));
+    pub fn build_remote(mut self) -> Result<DefaultRemoteResourceReader<T>, String> {
+        let url = self
+            .url
+            .take()
+            .ok_or("URL is required for remote resources")?;
+        let url = Url::parse(&url).map_err(|e| format!("Invalid URL '{}': {}", url, e))?;
+
+        let client = self.http_client()?;
+        let request_signer = self.request_signer.take();
+        let lookup_policy = self.lookup_policy.unwrap_or_default();
+        let auto_offline = self.auto_offline;
+        let parse_backoff = self.parse_backoff;
+
+        #[cfg(feature = "danger-insecure-tls")]
+        let accept_invalid_certs = self.accept_invalid_certs;
+
+        let state = ResourceState::new(self.into_props(url)?);
+
+        #[cfg(feature = "danger-insecure-tls")]
+        if accept_invalid_certs {
+            state.emit(ResourceEvent::InsecureTls {
+                resource: state.get_file_name().to_string(),
+            });
+        }
+
+        let mut reader = DefaultRemoteResourceReader::with_client(state, client)
+            .with_lookup_policy(lookup_policy);
+
+        if let Some(signer) = request_signer {
+            reader = reader.with_request_signer(signer);
+        }
+        if let Some(policy) = auto_offline {
+            reader = reader.with_auto_offline(policy);
+        }
+        if let Some(backoff) = parse_backoff {
+            reader = reader.with_parse_backoff(backoff);
+        }
 
-        // Create and return your Remote<T> here
-        // Example: Remote::new(file_name, url, cache_dir, self.timeout)
-        todo!("Implement remote resource creation")
+        Ok(reader)
+    }
+
+    /// Build a remote resource whose URL contains `{placeholders}` filled in per call;
+    /// the reader of every parameter set gets all options of this builder.
+    pub fn build_templated_remote(mut self) -> Result<TemplatedResourceReader<T>, String> {
+        let template = self
+            .url
+            .take()
+            .ok_or("URL is required for remote resources")?;
+        let client = self.http_client()?;
+        let file_name = self.file_name.clone().ok_or("File name is required")?;
+
+        let mut reader = TemplatedResourceReader::new(
+            template,
+            file_name,
+            self.file_type.clone().unwrap_or(ResourceFileType::Json),
+            self.cache_dir(),
+            self.timeout.or(global_config().timeout()),
+        )
+        .with_client(client)
+        .with_lookup_policy(self.lookup_policy.unwrap_or_default());
+
+        if let Some(signer) = self.request_signer.take() {
+            reader = reader.with_request_signer(signer);
+        }
+        if let Some(policy) = self.auto_offline {
+            reader = reader.with_auto_offline(policy);
+        }
+        if let Some(backoff) = self.parse_backoff {
+            reader = reader.with_parse_backoff(backoff);
+        }
+
+        if let Some(namespace) = self.namespace.take() {
+            reader = reader
+                .with_namespace(namespace)
+                .map_err(|e| e.to_string())?;
+        }
+
+        Ok(reader.with_props(move |props| self.apply_options(props)))
     }
 
     /// Build a local resource
-    pub fn build_local(self) -> Result<impl LocalResource<T>, String> {
-        let file_name = self.file_name.ok_or("File name is required")?;
-        let cache_dir = self.cache_directory.unwrap_or_else(|| PathBuf::from("."));
+    pub fn build_local(mut self) -> Result<DefaultLocalResourceReader<T>, String> {
+        let url = match self.url.take() {
+            Some(url) => Url::parse(&url).map_err(|e| format!("Invalid URL '{}': {}", url, e))?,
+            None => {
+                let file_name = self.file_name.as_deref().ok_or("File name is required")?;
+                // a bare file name is looked up in the given directory, not in the cache
+                let directory = match &self.cache_location {
+                    Some(CacheLocation::Path(directory)) => directory.clone(),
+                    _ => PathBuf::from("."),
+                };
+                let file_path = std::path::absolute(directory.join(file_name))
+                    .map_err(|e| format!("Unable to resolve the file path: {}", e))?;
+
+                Url::from_file_path(&file_path)
+                    .map_err(|_| format!("Invalid file path '{}'", file_path.display()))?
+            }
+        };
+
+        Ok(DefaultLocalResourceReader::new(ResourceState::new(
+            self.into_props(url)?,
+        )))
+    }
+
+    /// Build a resource read from the given environment variable
+    pub fn build_env(self, variable: impl Into<String>) -> Result<EnvResourceReader<T>, String> {
+        let variable = variable.into();
+        let url = Url::parse(&format!("env:{}", variable))
+            .map_err(|e| format!("Invalid environment variable name '{}': {}", variable, e))?;
+
+        Ok(EnvResourceReader::new(
+            ResourceState::new(self.into_props(url)?),
+            variable,
+        ))
+    }
 
-        // Create and return your Local<T> here
-        // Example: Local::new(file_name, cache_dir, self.file_type)
-        todo!("Implement local resource creation")
+    /// Build a resource read from the standard output of the given command
+    pub fn build_command(
+        self,
+        program: impl Into<String>,
+        args: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<CommandResourceReader<T>, String> {
+        let program = program.into();
+        let url = Url::parse(&format!(
+            "command:{}",
+            percent_encoding::utf8_percent_encode(&program, percent_encoding::NON_ALPHANUMERIC)
+        ))
+        .map_err(|e| format!("Invalid command '{}': {}", program, e))?;
+
+        Ok(CommandResourceReader::new(
+            ResourceState::new(self.into_props(url)?),
+            program,
+            args,
+        ))
+    }
+
+    fn http_client(&self) -> Result<reqwest::Client, String> {
+        if !self.configures_client() {
+            if let Some(client) = global_config().client() {
+                return Ok(client.clone());
+            }
+        }
+
+        let mut headers = HeaderMap::new();
+
+        for (name, value) in &self.default_headers {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| format!("Invalid header name '{}': {}", name, e))?;
+            let header_value = HeaderValue::from_str(value)
+                .map_err(|e| format!("Invalid value of header '{}': {}", name, e))?;
+
+            headers.append(header_name, header_value);
+        }
+
+        let mut client = reqwest::Client::builder()
+            .user_agent(self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT))
+            .default_headers(headers);
+
+        if let Some(timeout) = self.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+
+        if let Some(timeout) = self.request_timeout {
+            client = client.timeout(timeout);
+        }
+
+        if let Some(jar) = &self.cookie_jar {
+            client = client.cookie_provider(Arc::clone(jar));
+        }
+
+        if let Some(limit) = self.max_redirects {
+            client = client.redirect(match limit {
+                0 => Policy::none(),
+                limit => Policy::limited(limit),
+            });
+        }
+
+        // added to the system roots of this client only; verification stays on
+        for root_certificates in &self.root_certificates {
+            for certificate in root_certificates.load()? {
+                client = client.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some(identity) = &self.identity {
+            let identity = match identity {
+                ClientIdentity::Pkcs12 { der, password } => {
+                    Identity::from_pkcs12_der(der, password)
+                }
+                ClientIdentity::Pem { certificate, key } => {
+                    Identity::from_pkcs8_pem(certificate, key)
+                }
+            }
+            .map_err(|e| format!("Invalid client identity: {}", e))?;
+            client = client.identity(identity);
+        }
+
+        #[cfg(feature = "danger-insecure-tls")]
+        {
+            client = client.danger_accept_invalid_certs(self.accept_invalid_certs);
+        }
+
+        client
+            .build()
+            .map_err(|e| format!("Unable to create the HTTP client: {}", e))
     }
-}
\ No newline at end of file
+
+    fn cache_dir(&self) -> PathBuf {
+        match &self.cache_location {
+            Some(location) => location.resolve(),
+            None => global_config()
+                .cache_location()
+                .cloned()
+                .unwrap_or_default()
+                .resolve(),
+        }
+    }
+
+    /// Whether any option needs a client of this resource's own.
+    fn configures_client(&self) -> bool {
+        #[cfg(feature = "danger-insecure-tls")]
+        if self.accept_invalid_certs {
+            return true;
+        }
+
+        self.user_agent.is_some()
+            || self.max_redirects.is_some()
+            || self.connect_timeout.is_some()
+            || self.request_timeout.is_some()
+            || !self.default_headers.is_empty()
+            || self.cookie_jar.is_some()
+            || self.identity.is_some()
+            || !self.root_certificates.is_empty()
+    }
+
+    fn into_props(self, url: Url) -> Result<ResourceProps<T>, String> {
+        let file_name = self.file_name.clone().ok_or("File name is required")?;
+        let mut props = self.apply_options(ResourceProps::new(
+            file_name,
+            self.file_type.clone().unwrap_or(ResourceFileType::Json),
+            url,
+            self.cache_dir(),
+            self.timeout.or(global_config().timeout()),
+        ));
+
+        if let Some(namespace) = self.namespace {
+            props = props.with_namespace(namespace).map_err(|e| e.to_string())?;
+        }
+
+        Ok(props)
+    }
+
+    /// Apply every option stored in resource properties except the namespace, which
+    /// is validated by the caller.
+    fn apply_options(&self, props: ResourceProps<T>) -> ResourceProps<T> {
+        let config = global_config();
+
+        let mut props = props
+            .with_serialization(self.serialization)
+            .with_file_name_format(self.file_name_format.clone())
+            .with_layout(self.layout)
+            .with_durability(self.durability.unwrap_or_default())
+            .with_write_behind(self.write_behind.unwrap_or(false))
+            .with_weak_memory_cache(self.weak_memory_cache)
+            .with_file_mode(self.file_mode)
+            .with_strict_permissions(self.strict_permissions)
+            .with_quarantine(self.quarantine)
+            .with_strict_parse(self.strict_parse)
+            .with_metadata_sidecar(self.metadata_sidecar)
+            .with_cache_index(self.cache_index)
+            .with_schema_mode(self.schema_mode)
+            .with_priority(self.priority);
+
+        if let Some(redaction) = self.redaction.clone() {
+            props = props.with_redaction(move |value| redaction(value));
+        }
+
+        for transform in self.transforms.iter().cloned() {
+            props = props.with_transform(move |data: &mut T| transform(data));
+        }
+
+        if let Some(weigher) = self.weigher.clone() {
+            props = props.with_weigher(move |data: &T| weigher(data));
+        }
+
+        if let Some(bytes) = self.memory_budget {
+            props = props.with_memory_budget(bytes);
+        }
+
+        if let Some(listener) = config.event_listener(self.event_listener.clone()) {
+            props = props.with_event_listener(listener);
+        }
+
+        if let Some(max_stale) = self.max_stale {
+            props = props.with_max_stale(max_stale);
+        }
+
+        if let Some(schedule) = self.refresh_schedule.clone() {
+            props = props.with_refresh_schedule(schedule);
+        }
+
+        if let Some(clock) = self.clock.clone() {
+            props = props.with_clock(clock);
+        }
+
+        if let Some(file_system) = self.file_system.clone() {
+            props = props.with_file_system(file_system);
+        }
+
+        if let Some(tenant) = self.tenant.clone() {
+            props = props.with_tenant(tenant);
+        }
+
+        props
+    }
+}
+
+/// Payload type declaring its resource wiring (URL, file name, timeout, format, cache
+/// location) once, usually through `#[derive(Resource)]` with the `derive` feature.
+pub trait Resource: Send + Sync + Serialize + DeserializeOwned + 'static {
+    /// Builder preconfigured with the declared settings, open for further options.
+    fn builder() -> ResourceBuilder<Self>;
+
+    /// Build the remote reader of the declared resource.
+    fn remote() -> Result<DefaultRemoteResourceReader<Self>, String> {
+        Self::builder().build_remote()
+    }
+
+    /// Build the local reader of the declared resource.
+    fn local() -> Result<DefaultLocalResourceReader<Self>, String> {
+        Self::builder().build_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{block_on, test_directory};
+    use crate::testing::StubServer;
+    use crate::traits::{DataResult, ResourceReader};
+    #[cfg(feature = "derive")]
+    use serde::Deserialize;
+
+    #[cfg(feature = "derive")]
+    #[derive(crate::Resource, Serialize, Deserialize, Default)]
+    #[resource(url = "data:application/json,{\"enabled\":true}", ttl = 300)]
+    struct FeatureFlags {
+        enabled: bool,
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derived_resource_wiring() {
+        let directory = test_directory("builder-derive");
+        let reader = FeatureFlags::builder()
+            .cache_directory(&directory)
+            .build_remote()
+            .unwrap();
+
+        assert_eq!(reader.get_state().get_file_name(), "feature_flags");
+        let data = block_on(reader.get_data_or_error(false));
+        assert!(matches!(data, Ok(DataResult::Fresh(flags)) if flags.enabled));
+        // inline data is never copied to the disk cache
+        assert!(!directory.exists());
+    }
+
+    #[test]
+    fn test_cookie_jar_stores_response_cookies() {
+        use reqwest::cookie::CookieStore;
+
+        let directory = test_directory("builder-cookies");
+        let server = StubServer::start().unwrap();
+        server
+            .fixture("/session.json", r#"{"user": "admin"}"#)
+            .fixture_header("/session.json", "Set-Cookie", "session=abc; Path=/");
+
+        let jar = Arc::new(Jar::default());
+        let reader = ResourceBuilder::<serde_json::Value>::new()
+            .url(server.url("/session.json").as_str())
+            .file_name("session")
+            .cache_directory(&directory)
+            .cookie_jar(Arc::clone(&jar))
+            .build_remote()
+            .unwrap();
+
+        assert!(matches!(
+            block_on(reader.get_data_or_error(false)),
+            Ok(DataResult::Fresh(_))
+        ));
+        assert_eq!(
+            jar.cookies(&server.url("/other.json")).unwrap(),
+            "session=abc"
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+    #[test]
+    fn test_configured_headers_reach_the_origin() {
+        let directory = test_directory("builder-headers");
+        let server = StubServer::start().unwrap();
+        server.fixture("/flags.json", r#"{"enabled": true}"#);
+
+        let reader = ResourceBuilder::<serde_json::Value>::new()
+            .url(server.url("/flags.json").as_str())
+            .file_name("flags")
+            .cache_directory(&directory)
+            .user_agent("flags-client/1.0")
+            .default_header("X-Team", "platform")
+            .build_remote()
+            .unwrap();
+
+        assert!(matches!(
+            block_on(reader.get_data_or_error(false)),
+            Ok(DataResult::Fresh(_))
+        ));
+        assert_eq!(
+            server
+                .request_header("/flags.json", "User-Agent")
+                .as_deref(),
+            Some("flags-client/1.0")
+        );
+        assert_eq!(
+            server.request_header("/flags.json", "X-Team").as_deref(),
+            Some("platform")
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    /// Error of building a remote resource with the TLS options set on `builder`.
+    fn build_error(builder: ResourceBuilder<serde_json::Value>) -> String {
+        builder
+            .url("https://config.example.com/flags.json")
+            .file_name("flags")
+            .cache_directory(std::env::temp_dir())
+            .build_remote()
+            .err()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_invalid_client_identity_fails_the_build() {
+        let error = build_error(
+            ResourceBuilder::new()
+                .client_identity_pem(b"not a certificate".to_vec(), b"not a key".to_vec()),
+        );
+        assert!(error.starts_with("Invalid client identity"), "{}", error);
+
+        let error = build_error(
+            ResourceBuilder::new().client_identity_pkcs12(b"not an archive".to_vec(), "secret"),
+        );
+        assert!(error.starts_with("Invalid client identity"), "{}", error);
+    }
+
+    #[test]
+    fn test_invalid_ca_bundles_fail_the_build() {
+        let error =
+            build_error(ResourceBuilder::new().root_certificate_pem(b"not a certificate".to_vec()));
+        assert!(error.starts_with("Invalid root certificate"), "{}", error);
+
+        let missing = std::env::temp_dir().join("resourcely-builder-missing-bundle.pem");
+        let error = build_error(ResourceBuilder::new().ca_bundle_file(&missing));
+        assert!(
+            error.starts_with("Unable to read the CA bundle"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_templated_readers_get_the_builder_options() {
+        use crate::file_system::{FileSystem, MemoryFileSystem};
+        use std::path::Path;
+
+        let server = StubServer::start().unwrap();
+        server.fixture(
+            "/tenants/acme/flags.json",
+            r#"{"beta": "on", "token": "secret"}"#,
+        );
+        let file_system = Arc::new(MemoryFileSystem::new());
+        let reader = ResourceBuilder::<std::collections::HashMap<String, String>>::new()
+            .url(format!("{}tenants/{{tenant}}/flags.json", server.url("/")))
+            .file_name("flags")
+            .cache_directory("/cache")
+            .file_system(file_system.clone())
+            .redaction(|value| {
+                if let Some(fields) = value.as_object_mut() {
+                    fields.remove("token");
+                }
+            })
+            .transform(|data| {
+                data.insert("source".to_string(), "origin".to_string());
+            })
+            .build_templated_remote()
+            .unwrap();
+
+        let data = block_on(reader.get_data_with_params(&[("tenant", "acme")], false));
+        assert!(matches!(data, Ok(DataResult::Fresh(data)) if data["source"] == "origin"));
+
+        let files = file_system.list_files(Path::new("/cache")).unwrap();
+        assert_eq!(files.len(), 1);
+        let saved = String::from_utf8(file_system.read(&files[0]).unwrap()).unwrap();
+        assert!(!saved.contains("secret"), "{}", saved);
+        assert!(!Path::new("/cache").exists());
+    }
+
+    #[cfg(feature = "danger-insecure-tls")]
+    #[test]
+    fn test_accepting_invalid_certs_is_reported() {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let listener = {
+            let events = Arc::clone(&events);
+            Arc::new(move |event: &ResourceEvent| events.lock().unwrap().push(event.clone()))
+        };
+
+        ResourceBuilder::<serde_json::Value>::new()
+            .url("https://localhost:8443/flags.json")
+            .file_name("flags")
+            .cache_directory(std::env::temp_dir())
+            .event_listener(listener)
+            .danger_accept_invalid_certs()
+            .build_remote()
+            .unwrap();
+
+        assert!(matches!(
+            events.lock().unwrap().as_slice(),
+            [ResourceEvent::InsecureTls { resource }] if resource == "flags"
+        ));
+    }
+}
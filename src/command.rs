@@ -0,0 +1,162 @@
+use std::{process::Command, sync::Arc};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    base::{CacheScope, ResourceState},
+    error::ResourceError,
+    traits::{DataResult, ResourceReader},
+};
+
+/// Reader running a command (e.g. `kubectl get cm foo -o json`) and parsing its
+/// standard output as the resource.
+///
+/// The output is cached in memory and on disk with the same timeout and stale data
+/// semantics as the remote reader. The command runs on a separate thread so the
+/// async executor is not blocked.
+pub struct CommandResourceReader<T> {
+    state: Arc<ResourceState<T>>,
+    program: String,
+    args: Vec<String>,
+}
+
+impl<T> CommandResourceReader<T> {
+    pub fn new(
+        state: impl Into<Arc<ResourceState<T>>>,
+        program: impl Into<String>,
+        args: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            state: state.into(),
+            program: program.into(),
+            args: args.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for CommandResourceReader<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CommandResourceReader")
+            .field("state", &self.state)
+            .field("program", &self.program)
+            .field("args", &self.args)
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ResourceReader<T> for CommandResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
+        &self.state
+    }
+
+    async fn get_data_or_error(
+        &self,
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        let fetch = async {
+            let output = self.run_command().await?;
+            self.get_state()
+                .parse(&output)
+                .map(|data| (Arc::new(data), None))
+        };
+
+        self.get_state()
+            .load_or_refresh(allow_stale, CacheScope::MemoryAndDisk, fetch)
+            .await
+    }
+}
+
+impl<T> CommandResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    /// Run the command and return its standard output.
+    async fn run_command(&self) -> Result<String, ResourceError> {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+
+        let (sender, receiver) = futures_channel::oneshot::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(command.output());
+        });
+
+        let output = receiver
+            .await
+            .map_err(|_| ResourceError::Command("the command thread panicked".to_string()))?
+            .map_err(ResourceError::io)?;
+
+        if !output.status.success() {
+            return Err(ResourceError::Command(format!(
+                "'{}' exited with {}: {}",
+                self.program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        String::from_utf8(output.stdout).map_err(|_| ResourceError::InvalidUnicodeEncoding)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::base::ResourceProps;
+    use crate::test_support::{block_on, test_directory};
+    use crate::traits::ResourceFileType;
+    use reqwest::Url;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn command_reader(
+        name: &str,
+        directory: &std::path::Path,
+        script: &str,
+    ) -> CommandResourceReader<HashMap<String, u32>> {
+        CommandResourceReader::new(
+            ResourceState::new(ResourceProps::new(
+                name,
+                ResourceFileType::Json,
+                Url::parse("command:sh").unwrap(),
+                directory,
+                Some(Duration::from_secs(60)),
+            )),
+            "sh",
+            ["-c", script],
+        )
+    }
+
+    #[test]
+    fn test_command_output_is_parsed_and_persisted() {
+        let directory = test_directory("command-output");
+        let reader = command_reader("flags", &directory, r#"echo '{"enabled": 1}'"#);
+
+        match block_on(reader.get_data_or_error(false)) {
+            Ok(DataResult::Fresh(data)) => assert_eq!(data.get("enabled"), Some(&1)),
+            _ => panic!("Expected fresh data from the command output"),
+        }
+        assert_eq!(reader.get_state().list_cache_files().unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_failing_command_is_reported() {
+        let directory = test_directory("command-failing");
+        let reader = command_reader("flags", &directory, "echo denied >&2; exit 3");
+
+        assert!(matches!(
+            block_on(reader.get_data_or_error(false)),
+            Err(ResourceError::Command(_))
+        ));
+        assert!(reader
+            .stats()
+            .unwrap()
+            .last_error
+            .is_some_and(|error| error.contains("denied")));
+    }
+}
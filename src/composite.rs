@@ -0,0 +1,308 @@
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    marker::PhantomData,
+    sync::Arc,
+    time::SystemTime,
+};
+
+use futures_util::future::join_all;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    base::ResourceState,
+    error::ResourceError,
+    traits::{DataResult, ResourceReader},
+};
+
+type AnyData = Arc<dyn Any + Send + Sync>;
+
+type MergeFn<T> = dyn Fn(&CompositeParts) -> Result<T, ResourceError> + Send + Sync;
+
+/// Type-erased sub-resource of a composite, so parts of different payload types can
+/// be fetched together.
+#[async_trait::async_trait]
+trait CompositePart: Send + Sync {
+    /// The part's data and whether it is fresh, `None` when the part is absent.
+    async fn load(&self, allow_stale: bool) -> Result<Option<(AnyData, bool)>, ResourceError>;
+}
+
+struct Part<U, R> {
+    reader: Arc<R>,
+    _payload: PhantomData<fn() -> U>,
+}
+
+#[async_trait::async_trait]
+impl<U, R> CompositePart for Part<U, R>
+where
+    U: Send + Sync + DeserializeOwned + Serialize + Default + 'static,
+    R: ResourceReader<U> + Send + Sync + 'static,
+{
+    async fn load(&self, allow_stale: bool) -> Result<Option<(AnyData, bool)>, ResourceError> {
+        Ok(match self.reader.get_data_or_error(allow_stale).await? {
+            DataResult::Fresh(data) => Some((data, true)),
+            DataResult::Stale(data) => Some((data, false)),
+            DataResult::Absent => None,
+        })
+    }
+}
+
+/// Data of the sub-resources handed to the merge function of a [`CompositeResource`].
+pub struct CompositeParts {
+    parts: HashMap<String, AnyData>,
+    absent: HashSet<String>,
+    stale: bool,
+}
+
+impl CompositeParts {
+    /// Data of the part registered under `name` with payload type `U`.
+    pub fn get<U: Send + Sync + 'static>(&self, name: &str) -> Result<Arc<U>, ResourceError> {
+        let data = self
+            .parts
+            .get(name)
+            .ok_or_else(|| match self.is_absent(name) {
+                true => ResourceError::Composite(format!("part '{}' is absent", name)),
+                false => ResourceError::Composite(format!("no part named '{}'", name)),
+            })?;
+
+        Arc::clone(data).downcast::<U>().map_err(|_| {
+            ResourceError::Composite(format!(
+                "part '{}' is not of type {}",
+                name,
+                std::any::type_name::<U>()
+            ))
+        })
+    }
+
+    /// Whether the part's source deliberately holds no data ([`DataResult::Absent`]).
+    pub fn is_absent(&self, name: &str) -> bool {
+        self.absent.contains(name)
+    }
+
+    /// Whether any part is stale.
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+}
+
+/// Resource assembled from several sub-resources.
+///
+/// The parts are read concurrently and combined by the merge function into one value,
+/// which is cached (in memory and on disk) like any other resource, so callers see one
+/// coherent value. The result is fresh only when every part is fresh; a value merged
+/// from stale parts is returned as stale and not cached.
+pub struct CompositeResource<T> {
+    state: Arc<ResourceState<T>>,
+    parts: Vec<(String, Arc<dyn CompositePart>)>,
+    merge: Box<MergeFn<T>>,
+}
+
+impl<T> CompositeResource<T> {
+    pub fn new(
+        state: impl Into<Arc<ResourceState<T>>>,
+        merge: impl Fn(&CompositeParts) -> Result<T, ResourceError> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            state: state.into(),
+            parts: Vec::new(),
+            merge: Box::new(merge),
+        }
+    }
+
+    /// Add a sub-resource, available to the merge function under `name`.
+    pub fn with_part<U, R>(mut self, name: impl Into<String>, reader: Arc<R>) -> Self
+    where
+        U: Send + Sync + DeserializeOwned + Serialize + Default + 'static,
+        R: ResourceReader<U> + Send + Sync + 'static,
+    {
+        self.parts.push((
+            name.into(),
+            Arc::new(Part {
+                reader,
+                _payload: PhantomData,
+            }),
+        ));
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ResourceReader<T> for CompositeResource<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
+        &self.state
+    }
+
+    async fn get_data_or_error(
+        &self,
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        let result = self
+            .load_data(allow_stale)
+            .await
+            .and_then(|data| self.get_state().limit_staleness(data));
+
+        if let Err(error) = &result {
+            self.get_state().record_error(error);
+        }
+
+        result
+    }
+}
+
+impl<T> CompositeResource<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    /// Read all parts concurrently and merge them.
+    async fn assemble(&self, allow_stale: bool) -> Result<(Arc<T>, bool), ResourceError> {
+        let loaded = join_all(self.parts.iter().map(|(_, part)| part.load(allow_stale))).await;
+
+        let mut parts = CompositeParts {
+            parts: HashMap::with_capacity(self.parts.len()),
+            absent: HashSet::new(),
+            stale: false,
+        };
+        for ((name, _), result) in self.parts.iter().zip(loaded) {
+            match result? {
+                Some((data, fresh)) => {
+                    parts.stale |= !fresh;
+                    parts.parts.insert(name.clone(), data);
+                }
+                None => {
+                    parts.absent.insert(name.clone());
+                }
+            }
+        }
+
+        let merged = (self.merge)(&parts)?;
+
+        Ok((Arc::new(merged), !parts.stale))
+    }
+
+    async fn load_data(&self, allow_stale: bool) -> Result<DataResult<Arc<T>>, ResourceError> {
+        let mut stale_internal_data: Option<(Arc<T>, SystemTime)> = None;
+        let mut stale_disk_cached_data: Option<(Arc<T>, SystemTime)> = None;
+
+        if !self.get_state().is_marked_stale()? {
+            if let Some((data, fresh, timestamp)) = self.get_state().get_internal_data()? {
+                if fresh {
+                    self.get_state().record_hit();
+                    return Ok(DataResult::Fresh(data));
+                }
+                stale_internal_data = Some((data, timestamp));
+            }
+
+            if let Some((data, fresh, timestamp)) = self.get_state().get_disk_cached_data()? {
+                if fresh {
+                    self.get_state().record_hit();
+                    return Ok(DataResult::Fresh(data));
+                }
+                stale_disk_cached_data = Some((data, timestamp));
+            }
+        }
+
+        // Both stale sources may be available, prefer the newer one
+        let newest_stale_data = match (stale_internal_data, stale_disk_cached_data) {
+            (Some(internal), Some(disk)) if disk.1 > internal.1 => Some(disk.0),
+            (Some(internal), _) => Some(internal.0),
+            (None, disk) => disk.map(|(data, _)| data),
+        };
+
+        self.get_state().record_miss();
+        let generation = self.get_state().generation()?;
+
+        match self.assemble(allow_stale).await {
+            Ok((data, true)) => Ok(DataResult::Fresh(
+                self.get_state().store_refreshed(generation, data)?,
+            )),
+            // stale parts were allowed by the caller
+            Ok((data, false)) => Ok(DataResult::Stale(data)),
+            Err(error) => match newest_stale_data.filter(|_| allow_stale) {
+                Some(data) => {
+                    self.get_state().record_error(&error);
+                    Ok(DataResult::Stale(data))
+                }
+                None => Err(error),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::ResourceProps;
+    use crate::test_support::{block_on, test_directory};
+    use crate::testing::MockResourceReader;
+    use crate::traits::{ResourceFileType, StalePolicy};
+    use reqwest::Url;
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parts_are_merged_and_cached() {
+        let directory = test_directory("composite-merge");
+        let rates = Arc::new(MockResourceReader::<BTreeMap<String, f64>>::new("rates"));
+        rates.push_fresh(BTreeMap::from([("EUR".to_string(), 2.0)]));
+        let prices = Arc::new(MockResourceReader::<Vec<f64>>::new("prices"));
+        prices.push_fresh(vec![1.5, 3.0]).push_stale(vec![1.0]);
+
+        let composite = CompositeResource::new(
+            ResourceState::new(ResourceProps::new(
+                "prices-eur",
+                ResourceFileType::Json,
+                Url::parse("composite:prices-eur").unwrap(),
+                &directory,
+                Some(Duration::from_secs(60)),
+            )),
+            |parts| {
+                let rate = parts.get::<BTreeMap<String, f64>>("rates")?["EUR"];
+                Ok(parts
+                    .get::<Vec<f64>>("prices")?
+                    .iter()
+                    .map(|price| price * rate)
+                    .collect::<Vec<f64>>())
+            },
+        )
+        .with_part("rates", Arc::clone(&rates))
+        .with_part("prices", Arc::clone(&prices));
+
+        assert!(matches!(
+            block_on(composite.get_data_or_error(false)),
+            Ok(DataResult::Fresh(data)) if *data == vec![3.0, 6.0]
+        ));
+        assert!(block_on(composite.get_data_or_none_with(StalePolicy::Never)).is_some());
+        assert_eq!((rates.calls(), prices.calls()), (1, 1));
+
+        // a stale part makes the merged value stale
+        composite.mark_as_stale().unwrap();
+        assert!(matches!(
+            block_on(composite.get_data_or_error(true)),
+            Ok(DataResult::Stale(data)) if *data == vec![2.0]
+        ));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_unknown_part_is_reported() {
+        let composite = CompositeResource::<Vec<u32>>::new(
+            ResourceState::new(ResourceProps::new(
+                "numbers",
+                ResourceFileType::Json,
+                Url::parse("composite:numbers").unwrap(),
+                std::env::temp_dir().join("resourcely-composite-unknown"),
+                None,
+            )),
+            |parts| Ok(parts.get::<Vec<u32>>("missing")?.to_vec()),
+        );
+
+        assert!(matches!(
+            block_on(composite.get_data_or_error(false)),
+            Err(ResourceError::Composite(_))
+        ));
+    }
+}
@@ -0,0 +1,174 @@
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+
+use crate::base::CacheLocation;
+use crate::events::EventListener;
+
+static GLOBAL_CONFIG: RwLock<Option<Arc<ResourcelyConfig>>> = RwLock::new(None);
+
+/// Defaults inherited by every [`ResourceBuilder`](crate::ResourceBuilder) for the
+/// options it does not set itself, so fleet-wide policy lives in one place.
+///
+/// Install it with [`set_global_config`] at startup; builders read it when building.
+///
+/// ```ignore
+/// set_global_config(
+///     ResourcelyConfig::new()
+///         .with_cache_location(CacheLocation::App("billing".to_string()))
+///         .with_timeout(Duration::from_secs(300))
+///         .with_event_listener(metrics_listener),
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct ResourcelyConfig {
+    cache_location: Option<CacheLocation>,
+    client: Option<reqwest::Client>,
+    timeout: Option<Duration>,
+    event_listener: Option<EventListener>,
+    tracing: bool,
+}
+
+impl ResourcelyConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Where resources keep their disk cache.
+    pub fn with_cache_location(mut self, location: CacheLocation) -> Self {
+        self.cache_location = Some(location);
+        self
+    }
+
+    /// HTTP client of remote resources whose builder configures no HTTP options
+    /// (user agent, headers, timeouts, redirects, certificates), so they share its
+    /// connection pool.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Cache timeout of resources.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Listener receiving the events of resources without a listener of their own,
+    /// e.g. to feed metrics.
+    pub fn with_event_listener(mut self, listener: EventListener) -> Self {
+        self.event_listener = Some(listener);
+        self
+    }
+
+    /// Also record the events of every resource built afterwards as `tracing` events
+    /// (target `resourcely`). Requires the `tracing` feature; ignored otherwise.
+    pub fn with_tracing(mut self, enabled: bool) -> Self {
+        self.tracing = enabled;
+        self
+    }
+
+    pub(crate) fn cache_location(&self) -> Option<&CacheLocation> {
+        self.cache_location.as_ref()
+    }
+
+    pub(crate) fn client(&self) -> Option<&reqwest::Client> {
+        self.client.as_ref()
+    }
+
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Listener of a resource: its own or the default one, traced when enabled.
+    pub(crate) fn event_listener(&self, own: Option<EventListener>) -> Option<EventListener> {
+        let listener = own.or_else(|| self.event_listener.clone());
+
+        #[cfg(feature = "tracing")]
+        if self.tracing {
+            return Some(Arc::new(move |event: &crate::events::ResourceEvent| {
+                tracing::info!(target: "resourcely", ?event, "resource event");
+                if let Some(listener) = &listener {
+                    listener(event);
+                }
+            }));
+        }
+
+        listener
+    }
+}
+
+/// Install the defaults inherited by resource builders from now on.
+pub fn set_global_config(config: ResourcelyConfig) {
+    *GLOBAL_CONFIG
+        .write()
+        .unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(config));
+}
+
+/// The installed defaults, empty unless [`set_global_config`] was called.
+pub fn global_config() -> Arc<ResourcelyConfig> {
+    GLOBAL_CONFIG
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::ResourceEvent;
+    use crate::file_system::MemoryFileSystem;
+    use crate::traits::ResourceReader;
+    use crate::ResourceBuilder;
+    use std::collections::HashMap;
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_builders_inherit_global_defaults() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        // other tests build resources meanwhile; keep to the ones built here
+        set_global_config(
+            ResourcelyConfig::new()
+                .with_cache_location(CacheLocation::Path("/global-cache".into()))
+                .with_event_listener(Arc::new(move |event: &ResourceEvent| {
+                    if let ResourceEvent::EvictedFromMemory { resource, .. } = event {
+                        if resource.starts_with("configured-") {
+                            sink.lock().unwrap().push(resource.clone());
+                        }
+                    }
+                })),
+        );
+
+        let build = |name: &str| {
+            ResourceBuilder::<HashMap<String, u32>>::new()
+                .file_name(name)
+                .url("https://example.com/flags.json")
+                .file_system(Arc::new(MemoryFileSystem::new()))
+        };
+        let inherited = build("configured-inherited").build_remote().unwrap();
+        let own = build("configured-own")
+            .cache_directory("/own-cache")
+            .event_listener(Arc::new(|_: &ResourceEvent| {}))
+            .build_remote()
+            .unwrap();
+        set_global_config(ResourcelyConfig::default());
+
+        let inherited = inherited.get_state();
+        let own = own.get_state();
+        assert_eq!(
+            inherited.get_storage_directory(),
+            Path::new("/global-cache")
+        );
+        assert_eq!(own.get_storage_directory(), Path::new("/own-cache"));
+
+        for state in [inherited, own] {
+            state.emit(ResourceEvent::EvictedFromMemory {
+                resource: state.get_file_name().to_string(),
+                weight: 0,
+            });
+        }
+        assert_eq!(*received.lock().unwrap(), vec!["configured-inherited"]);
+    }
+}
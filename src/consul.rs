@@ -0,0 +1,194 @@
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use reqwest::{header::HeaderMap, StatusCode, Url};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    base::{CacheScope, ResourceState},
+    error::ResourceError,
+    traits::{DataResult, ResourceReader},
+};
+
+/// Modify index of the key, reported by Consul in the `X-Consul-Index` header.
+fn consul_index(headers: &HeaderMap) -> Option<u64> {
+    headers.get("X-Consul-Index")?.to_str().ok()?.parse().ok()
+}
+
+/// Reader backed by a Consul KV key.
+///
+/// The resource URL is the Consul address (e.g. `http://consul:8500`). Reads behave
+/// like the remote reader; in addition [`watch`](Self::watch) runs Consul blocking
+/// queries so changes of the key are stored and published to the resource
+/// subscribers (see [`ResourceState::subscribe`]) almost instantly.
+pub struct ConsulResourceReader<T> {
+    state: Arc<ResourceState<T>>,
+    client: reqwest::Client,
+    key: String,
+    token: Option<String>,
+    wait: Duration,
+    /// Index of the last value read, the starting point of the next blocking query.
+    index: Mutex<Option<u64>>,
+}
+
+impl<T> ConsulResourceReader<T> {
+    pub fn new(state: impl Into<Arc<ResourceState<T>>>, key: impl Into<String>) -> Self {
+        Self {
+            state: state.into(),
+            client: reqwest::Client::new(),
+            key: key.into().trim_matches('/').to_string(),
+            token: None,
+            wait: Duration::from_secs(300),
+            index: Mutex::new(None),
+        }
+    }
+
+    /// Send the given ACL token as `X-Consul-Token`.
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Longest time a blocking query waits for a change (defaults to 5 minutes).
+    pub fn with_wait(mut self, wait: Duration) -> Self {
+        self.wait = wait;
+        self
+    }
+
+    /// Perform requests through the given HTTP client.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ResourceReader<T> for ConsulResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
+        &self.state
+    }
+
+    async fn get_data_or_error(
+        &self,
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        let fetch = async {
+            match self.fetch(None).await? {
+                Some(data) => Ok((data, None)),
+                None => Err(ResourceError::UnableToFreshData),
+            }
+        };
+
+        self.get_state()
+            .load_or_refresh(allow_stale, CacheScope::MemoryAndDisk, fetch)
+            .await
+    }
+}
+
+impl<T> ConsulResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    /// Wait until the key changes (or the blocking query times out) and store the new
+    /// value, returning whether it changed.
+    pub async fn wait_for_change(&self) -> Result<bool, ResourceError> {
+        let index = *self.index.lock().unwrap_or_else(PoisonError::into_inner);
+
+        match self.fetch(index).await? {
+            Some(data) => {
+                self.get_state().save_to_disk(&data)?;
+                self.get_state().set_internal_cache(data)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Keep the resource up to date with blocking queries until an error occurs.
+    ///
+    /// Meant to run in a spawned task next to the readers of the resource; restart it
+    /// after an error, possibly with a delay.
+    pub async fn watch(&self) -> Result<(), ResourceError> {
+        loop {
+            if let Err(error) = self.wait_for_change().await {
+                self.get_state().record_error(&error);
+                return Err(error);
+            }
+        }
+    }
+
+    /// Read the key, blocking until its index moves past `index` when one is given.
+    /// `None` means the blocking query timed out without a change.
+    async fn fetch(&self, index: Option<u64>) -> Result<Option<Arc<T>>, ResourceError> {
+        let address = self.get_state().get_url().as_str().trim_end_matches('/');
+        let mut url = Url::parse(&format!("{}/v1/kv/{}", address, self.key))
+            .map_err(|e| ResourceError::Consul(format!("invalid address: {}", e)))?;
+
+        url.query_pairs_mut().append_pair("raw", "true");
+        if let Some(index) = index {
+            url.query_pairs_mut()
+                .append_pair("index", &index.to_string())
+                .append_pair("wait", &format!("{}s", self.wait.as_secs().max(1)));
+        }
+
+        let mut request = self.client.get(url);
+        if let Some(token) = &self.token {
+            request = request.header("X-Consul-Token", token);
+        }
+
+        let response = request.send().await.map_err(ResourceError::network)?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(ResourceError::Consul(format!(
+                "key '{}' not found",
+                self.key
+            )));
+        }
+        if !response.status().is_success() {
+            return Err(ResourceError::Consul(format!(
+                "reading '{}' failed with status {}",
+                self.key,
+                response.status()
+            )));
+        }
+
+        let new_index = consul_index(response.headers());
+        if index.is_some() && new_index == index {
+            return Ok(None);
+        }
+
+        let body = response.text().await.map_err(ResourceError::network)?;
+        let data = self.get_state().parse(&body).map(Arc::new)?;
+
+        // an index going backwards (e.g. a snapshot restore) restarts from scratch
+        let next_index = match (index, new_index) {
+            (Some(previous), Some(new)) if new < previous => None,
+            (_, new) => new,
+        };
+        *self.index.lock().unwrap_or_else(PoisonError::into_inner) = next_index;
+
+        Ok(Some(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consul_index_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(consul_index(&headers), None);
+
+        headers.insert("X-Consul-Index", "4711".parse().unwrap());
+        assert_eq!(consul_index(&headers), Some(4711));
+
+        headers.insert("X-Consul-Index", "not-a-number".parse().unwrap());
+        assert_eq!(consul_index(&headers), None);
+    }
+}
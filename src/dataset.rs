@@ -0,0 +1,330 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, SystemTime};
+
+use arrow_array::RecordBatch;
+use bytes::Bytes;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::file::reader::SerializedFileReader;
+use parquet::record::reader::RowIter;
+use parquet::record::Row;
+use reqwest::Url;
+
+use crate::{
+    error::ResourceError,
+    file_system::{FileSystem, RealFileSystem},
+    remote::DEFAULT_USER_AGENT,
+    traits::DataResult,
+};
+
+/// Columnar dataset backed by a Parquet file, e.g. a nightly export.
+///
+/// The file is fetched from `url` (`http(s)://` or `file://`) and cached as raw bytes,
+/// in memory and on disk (`{name}.parquet` in the storage directory), instead of
+/// going through serde. Read it as Arrow [`RecordBatch`]es with
+/// [`record_batches`](Self::record_batches) or row by row with [`rows`](Self::rows).
+///
+/// The disk cache is fresh while its modification time is within the timeout; without
+/// a timeout cached data never expires. Stale data is served when fetching fails.
+pub struct DatasetResource {
+    name: String,
+    url: Url,
+    storage_directory: PathBuf,
+    timeout: Option<Duration>,
+    client: reqwest::Client,
+    file_system: Arc<dyn FileSystem>,
+    /// Content and fetch time of the dataset held in memory.
+    cache: RwLock<Option<(Bytes, SystemTime)>>,
+    marked_stale: RwLock<bool>,
+}
+
+impl DatasetResource {
+    pub fn new(
+        name: impl Into<String>,
+        url: Url,
+        storage_directory: impl Into<PathBuf>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(DEFAULT_USER_AGENT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            name: name.into(),
+            url,
+            storage_directory: storage_directory.into(),
+            timeout,
+            client,
+            file_system: Arc::new(RealFileSystem),
+            cache: RwLock::new(None),
+            marked_stale: RwLock::new(false),
+        }
+    }
+
+    /// Perform the requests through the given HTTP client.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Keep the disk cache on the given file system.
+    pub fn with_file_system(mut self, file_system: Arc<dyn FileSystem>) -> Self {
+        self.file_system = file_system;
+        self
+    }
+
+    /// Path of the disk cache file.
+    pub fn get_file_path(&self) -> PathBuf {
+        self.storage_directory
+            .join(format!("{}.parquet", self.name))
+    }
+
+    /// Force the next read to fetch the dataset again.
+    pub fn mark_as_stale(&self) -> Result<(), ResourceError> {
+        *self
+            .marked_stale
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = true;
+        Ok(())
+    }
+
+    /// Raw content of the Parquet file, from the cache while fresh and fetched
+    /// otherwise; with `allow_stale`, cached content is served when fetching fails.
+    pub async fn get_bytes(&self, allow_stale: bool) -> Result<DataResult<Bytes>, ResourceError> {
+        let marked_stale = *self
+            .marked_stale
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut stale = None;
+
+        if let Some((content, fetched_at)) = self.memory()? {
+            if !marked_stale && self.is_fresh(fetched_at) {
+                return Ok(DataResult::Fresh(content));
+            }
+            stale = Some((content, fetched_at));
+        }
+
+        let file_path = self.get_file_path();
+        if let Ok(modified) = self.file_system.modified(&file_path) {
+            let newer_than_memory = stale
+                .as_ref()
+                .is_none_or(|(_, fetched_at)| modified > *fetched_at);
+
+            if newer_than_memory {
+                if let Ok(content) = self.file_system.read(&file_path) {
+                    let content = Bytes::from(content);
+                    if !marked_stale && self.is_fresh(modified) {
+                        self.set_memory(content.clone(), modified)?;
+                        return Ok(DataResult::Fresh(content));
+                    }
+                    stale = Some((content, modified));
+                }
+            }
+        }
+
+        match self.fetch().await {
+            Ok(content) => {
+                self.save_to_disk(&file_path, &content)?;
+                self.set_memory(content.clone(), SystemTime::now())?;
+                *self
+                    .marked_stale
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner) = false;
+                Ok(DataResult::Fresh(content))
+            }
+            Err(error) => match stale.filter(|_| allow_stale) {
+                Some((content, _)) => Ok(DataResult::Stale(content)),
+                None => Err(error),
+            },
+        }
+    }
+
+    /// Every record batch of the dataset, decoded through Arrow.
+    pub async fn record_batches(
+        &self,
+        allow_stale: bool,
+    ) -> Result<Vec<RecordBatch>, ResourceError> {
+        let content = self.content(allow_stale).await?;
+
+        ParquetRecordBatchReaderBuilder::try_new(content)
+            .and_then(|builder| builder.build())
+            .map_err(dataset_error)?
+            .map(|batch| batch.map_err(|e| ResourceError::Dataset(e.to_string())))
+            .collect()
+    }
+
+    /// Rows of the dataset, with typed accessors per column (`get_long`, `get_string`, ...).
+    pub async fn rows(
+        &self,
+        allow_stale: bool,
+    ) -> Result<impl Iterator<Item = Result<Row, ResourceError>>, ResourceError> {
+        let content = self.content(allow_stale).await?;
+        let reader = SerializedFileReader::new(content).map_err(dataset_error)?;
+
+        Ok(RowIter::from_file_into(Box::new(reader)).map(|row| row.map_err(dataset_error)))
+    }
+
+    async fn content(&self, allow_stale: bool) -> Result<Bytes, ResourceError> {
+        match self.get_bytes(allow_stale).await? {
+            DataResult::Fresh(content) | DataResult::Stale(content) => Ok(content),
+            DataResult::Absent => Err(ResourceError::UnableToFreshData),
+        }
+    }
+
+    async fn fetch(&self) -> Result<Bytes, ResourceError> {
+        if self.url.scheme() == "file" {
+            let path = self
+                .url
+                .to_file_path()
+                .map_err(|_| ResourceError::InvalidUrl(self.url.to_string()))?;
+            return std::fs::read(path)
+                .map(Bytes::from)
+                .map_err(ResourceError::Io);
+        }
+
+        let response = self
+            .client
+            .get(self.url.clone())
+            .send()
+            .await
+            .map_err(ResourceError::network)?;
+
+        if !response.status().is_success() {
+            return Err(ResourceError::HttpStatus(response.status().as_u16()));
+        }
+
+        response.bytes().await.map_err(ResourceError::network)
+    }
+
+    fn save_to_disk(&self, file_path: &Path, content: &Bytes) -> Result<(), ResourceError> {
+        let temporary_path = self
+            .storage_directory
+            .join(format!(".{}.parquet.tmp", self.name));
+
+        self.file_system
+            .create_dir_all(&self.storage_directory)
+            .and_then(|_| {
+                self.file_system
+                    .write_with_mode(&temporary_path, content, 0o600)
+            })
+            .and_then(|_| self.file_system.rename(&temporary_path, file_path))
+            .map_err(|error| {
+                let _ = self.file_system.remove_file(&temporary_path);
+                ResourceError::Io(error)
+            })
+    }
+
+    fn is_fresh(&self, stored_at: SystemTime) -> bool {
+        self.timeout.is_none_or(|timeout| {
+            SystemTime::now()
+                .duration_since(stored_at)
+                .map(|age| age < timeout)
+                .unwrap_or(true)
+        })
+    }
+
+    fn memory(&self) -> Result<Option<(Bytes, SystemTime)>, ResourceError> {
+        Ok(self
+            .cache
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone())
+    }
+
+    fn set_memory(&self, content: Bytes, stored_at: SystemTime) -> Result<(), ResourceError> {
+        *self.cache.write().unwrap_or_else(PoisonError::into_inner) = Some((content, stored_at));
+        Ok(())
+    }
+}
+
+fn dataset_error(error: parquet::errors::ParquetError) -> ResourceError {
+    ResourceError::Dataset(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{block_on, test_directory};
+    use arrow_array::{Int64Array, StringArray};
+    use parquet::arrow::ArrowWriter;
+    use parquet::record::RowAccessor;
+
+    fn parquet_file(ids: Vec<i64>, names: Vec<&str>) -> Vec<u8> {
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(ids)) as _),
+            ("name", Arc::new(StringArray::from(names)) as _),
+        ])
+        .unwrap();
+
+        let mut content = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut content, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        content
+    }
+
+    #[test]
+    fn test_dataset_is_cached_as_raw_bytes() {
+        let directory = test_directory("dataset");
+        let source = directory.join("source").join("users.parquet");
+        std::fs::create_dir_all(source.parent().unwrap()).unwrap();
+        std::fs::write(&source, parquet_file(vec![1, 2], vec!["ada", "linus"])).unwrap();
+
+        let dataset = DatasetResource::new(
+            "users",
+            Url::from_file_path(&source).unwrap(),
+            directory.join("cache"),
+            Some(Duration::from_secs(60)),
+        );
+
+        let batches = block_on(dataset.record_batches(false)).unwrap();
+        assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), 2);
+        assert_eq!(
+            std::fs::read(dataset.get_file_path()).unwrap(),
+            std::fs::read(&source).unwrap()
+        );
+
+        // served from the cache once the source is gone
+        std::fs::remove_file(&source).unwrap();
+        let rows: Vec<Row> = block_on(dataset.rows(false))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(rows[1].get_long(0).unwrap(), 2);
+        assert_eq!(rows[1].get_string(1).unwrap(), "linus");
+
+        dataset.mark_as_stale().unwrap();
+        assert!(matches!(
+            block_on(dataset.get_bytes(false)),
+            Err(ResourceError::Io(_))
+        ));
+        assert!(matches!(
+            block_on(dataset.get_bytes(true)),
+            Ok(DataResult::Stale(_))
+        ));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_dataset_fails_to_decode() {
+        let directory = test_directory("dataset-invalid");
+        std::fs::create_dir_all(&directory).unwrap();
+        let source = directory.join("broken.parquet");
+        std::fs::write(&source, "not parquet").unwrap();
+
+        let dataset = DatasetResource::new(
+            "dataset",
+            Url::from_file_path(&source).unwrap(),
+            &directory,
+            None,
+        );
+        assert!(matches!(
+            block_on(dataset.record_batches(false)),
+            Err(ResourceError::Dataset(_))
+        ));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
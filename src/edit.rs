@@ -0,0 +1,161 @@
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{base::ResourceState, error::ResourceError};
+
+/// Copy of a resource's data being changed, created with
+/// [`ResourceReader::edit`](crate::ResourceReader::edit).
+///
+/// Mutate the copy through `DerefMut`, then [`commit`](Self::commit) it to the memory
+/// and disk cache at once, or drop it (or [`abort`](Self::abort)) to discard it. The
+/// commit fails with [`ResourceError::EditConflict`] when other data was stored since
+/// the edit began, so concurrent edits or refreshes are never silently overwritten.
+pub struct Edit<'a, T> {
+    state: &'a ResourceState<T>,
+    value: T,
+    generation: u64,
+}
+
+impl<'a, T> Edit<'a, T>
+where
+    T: Clone + Default + Serialize + DeserializeOwned,
+{
+    /// Start editing a copy of the newest cached data, or of the default value when
+    /// nothing is cached.
+    pub fn new(state: &'a ResourceState<T>) -> Result<Self, ResourceError> {
+        let generation = state.generation()?;
+        let value = match state.get_cached_data()? {
+            Some((data, _)) => T::clone(&data),
+            None => T::default(),
+        };
+
+        Ok(Self {
+            state,
+            value,
+            generation,
+        })
+    }
+
+    /// Store the edited data in memory and on disk, notifying subscribers.
+    pub fn commit(self) -> Result<Arc<T>, ResourceError> {
+        let data = Arc::new(self.value);
+        if !self
+            .state
+            .set_internal_cache_if_generation(self.generation, Arc::clone(&data))?
+        {
+            return Err(ResourceError::EditConflict);
+        }
+        self.state.save_to_disk(&data)?;
+
+        Ok(data)
+    }
+
+    /// Write the edited data back to its source with `write_back` (e.g. a `PUT` to the
+    /// origin), then [`commit`](Self::commit) it. Nothing is stored when the write-back
+    /// fails or the resource changed since the edit began.
+    pub async fn commit_with<F, Fut>(self, write_back: F) -> Result<Arc<T>, ResourceError>
+    where
+        F: FnOnce(&T) -> Fut,
+        Fut: Future<Output = Result<(), ResourceError>>,
+    {
+        if !self.state.is_current(self.generation)? {
+            return Err(ResourceError::EditConflict);
+        }
+        write_back(&self.value).await?;
+
+        self.commit()
+    }
+
+    /// Discard the edited data.
+    pub fn abort(self) {}
+}
+
+impl<T> Deref for Edit<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Edit<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::ResourceProps;
+    use crate::file_system::MemoryFileSystem;
+    use crate::local::DefaultLocalResourceReader;
+    use crate::test_support::block_on;
+    use crate::traits::ResourceReader;
+    use crate::ResourceFileType;
+    use reqwest::Url;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_edits_commit_abort_and_detect_conflicts() {
+        let file_system = Arc::new(MemoryFileSystem::new());
+        let reader = DefaultLocalResourceReader::<BTreeMap<String, u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "limits",
+                ResourceFileType::Json,
+                Url::parse("file:///cache/limits.json").unwrap(),
+                "/cache",
+                None,
+            )
+            .with_file_system(file_system.clone()),
+        ));
+        let state = reader.get_state();
+
+        let mut edit = reader.edit().unwrap();
+        edit.insert("requests".to_string(), 10);
+        let committed = edit.commit().unwrap();
+        assert_eq!(committed["requests"], 10);
+        assert_eq!(
+            state.get_internal_data().unwrap().unwrap().0["requests"],
+            10
+        );
+        assert_eq!(state.list_cache_files().unwrap().len(), 1);
+
+        let mut edit = reader.edit().unwrap();
+        edit.insert("requests".to_string(), 20);
+        edit.abort();
+        assert_eq!(
+            state.get_internal_data().unwrap().unwrap().0["requests"],
+            10
+        );
+
+        // a store while editing wins over the edit
+        let mut edit = reader.edit().unwrap();
+        edit.insert("requests".to_string(), 30);
+        state.set_internal_cache(BTreeMap::new()).unwrap();
+        assert!(matches!(edit.commit(), Err(ResourceError::EditConflict)));
+
+        // a failed write-back stores nothing
+        let mut edit = reader.edit().unwrap();
+        edit.insert("requests".to_string(), 40);
+        let result = block_on(
+            edit.commit_with(|_| async { Err(ResourceError::Command("rejected".to_string())) }),
+        );
+        assert!(result.is_err());
+        assert!(state.get_internal_data().unwrap().unwrap().0.is_empty());
+
+        let mut edit = reader.edit().unwrap();
+        edit.insert("requests".to_string(), 50);
+        let written = std::sync::Mutex::new(None);
+        let committed = block_on(edit.commit_with(|value| {
+            *written.lock().unwrap() = Some(value["requests"]);
+            async { Ok(()) }
+        }))
+        .unwrap();
+        assert_eq!(committed["requests"], 50);
+        assert_eq!(*written.lock().unwrap(), Some(50));
+    }
+}
@@ -0,0 +1,319 @@
+use std::io::{self, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::error::ResourceError;
+use crate::file_system::FileSystem;
+
+/// First line of an encrypted cache file, followed by the key id and the payload.
+const HEADER: &str = "resourcely-aes-256-gcm";
+
+/// 256-bit AES key with the id recorded next to the data it encrypts.
+#[derive(Clone)]
+pub struct EncryptionKey {
+    id: String,
+    bytes: [u8; 32],
+}
+
+impl EncryptionKey {
+    /// The id must not contain line breaks.
+    pub fn new(id: impl Into<String>, bytes: [u8; 32]) -> Self {
+        Self {
+            id: id.into(),
+            bytes,
+        }
+    }
+
+    /// Key from its base64 representation, e.g. the output of `openssl rand -base64 32`.
+    pub fn from_base64(id: impl Into<String>, encoded: &str) -> Result<Self, ResourceError> {
+        let bytes = STANDARD
+            .decode(encoded.trim())
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .ok_or_else(|| {
+                ResourceError::Encryption("a key must be 32 base64 encoded bytes".to_string())
+            })?;
+
+        Ok(Self::new(id, bytes))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn cipher(&self) -> Result<LessSafeKey, ResourceError> {
+        UnboundKey::new(&AES_256_GCM, &self.bytes)
+            .map(LessSafeKey::new)
+            .map_err(|_| ResourceError::Encryption("invalid key".to_string()))
+    }
+}
+
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptionKey")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Source of the keys encrypting the disk cache, see
+/// [`ResourceProps::with_encryption`](crate::state_manager::ResourceProps::with_encryption).
+///
+/// Keys are resolved on every write and read, so an implementation backed by a KMS,
+/// the OS keyring or the environment can rotate keys at any time: files are written
+/// with the current key and read with the key whose id they record.
+pub trait KeyProvider: Send + Sync {
+    /// Key encrypting newly written files.
+    fn current_key(&self) -> Result<EncryptionKey, ResourceError>;
+
+    /// Key with the given id, for files written before a rotation.
+    fn key(&self, id: &str) -> Result<EncryptionKey, ResourceError>;
+}
+
+/// Fixed set of keys: the first encrypts, all of them decrypt.
+pub struct StaticKeys {
+    keys: Vec<EncryptionKey>,
+}
+
+impl StaticKeys {
+    /// `current` encrypts new files; `previous` keys still decrypt older ones.
+    pub fn new(current: EncryptionKey, previous: Vec<EncryptionKey>) -> Self {
+        let mut keys = vec![current];
+        keys.extend(previous);
+        Self { keys }
+    }
+}
+
+impl KeyProvider for StaticKeys {
+    fn current_key(&self) -> Result<EncryptionKey, ResourceError> {
+        Ok(self.keys[0].clone())
+    }
+
+    fn key(&self, id: &str) -> Result<EncryptionKey, ResourceError> {
+        self.keys
+            .iter()
+            .find(|key| key.id == id)
+            .cloned()
+            .ok_or_else(|| ResourceError::Encryption(format!("unknown key '{}'", id)))
+    }
+}
+
+/// Keys read from environment variables on every use.
+///
+/// The current key is the base64 value of the `variable`, identified by the
+/// `<variable>_ID` variable (`default` when unset). Keys rotated out stay readable when
+/// kept in `<variable>_<id>`, e.g. `CACHE_KEY_2024Q1`.
+pub struct EnvKeyProvider {
+    variable: String,
+}
+
+impl EnvKeyProvider {
+    pub fn new(variable: impl Into<String>) -> Self {
+        Self {
+            variable: variable.into(),
+        }
+    }
+
+    fn read(variable: &str) -> Result<String, ResourceError> {
+        std::env::var(variable).map_err(|_| {
+            ResourceError::Encryption(format!("environment variable '{}' is not set", variable))
+        })
+    }
+}
+
+impl KeyProvider for EnvKeyProvider {
+    fn current_key(&self) -> Result<EncryptionKey, ResourceError> {
+        let id = std::env::var(format!("{}_ID", self.variable))
+            .unwrap_or_else(|_| "default".to_string());
+        EncryptionKey::from_base64(id, &Self::read(&self.variable)?)
+    }
+
+    fn key(&self, id: &str) -> Result<EncryptionKey, ResourceError> {
+        let current = self.current_key()?;
+        if current.id == id {
+            return Ok(current);
+        }
+        EncryptionKey::from_base64(id, &Self::read(&format!("{}_{}", self.variable, id))?)
+    }
+}
+
+/// Encrypt `content` with the provider's current key (AES-256-GCM, the key id as
+/// associated data).
+pub fn encrypt(content: &[u8], provider: &dyn KeyProvider) -> Result<Vec<u8>, ResourceError> {
+    let key = provider.current_key()?;
+    let mut nonce = [0; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| ResourceError::Encryption("no randomness available".to_string()))?;
+
+    let mut sealed = content.to_vec();
+    key.cipher()?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(key.id.as_bytes()),
+            &mut sealed,
+        )
+        .map_err(|_| ResourceError::Encryption("encryption failed".to_string()))?;
+
+    let mut payload = nonce.to_vec();
+    payload.extend(sealed);
+    Ok(format!("{}\n{}\n{}\n", HEADER, key.id, STANDARD.encode(payload)).into_bytes())
+}
+
+/// Decrypt content written by [`encrypt`]; anything else is returned unchanged.
+pub fn decrypt(content: Vec<u8>, provider: &dyn KeyProvider) -> Result<Vec<u8>, ResourceError> {
+    let Some(rest) = content.strip_prefix(format!("{}\n", HEADER).as_bytes()) else {
+        return Ok(content);
+    };
+    let invalid = || ResourceError::Encryption("malformed encrypted file".to_string());
+
+    let rest = std::str::from_utf8(rest).map_err(|_| invalid())?;
+    let (id, payload) = rest.split_once('\n').ok_or_else(invalid)?;
+    let payload = STANDARD.decode(payload.trim()).map_err(|_| invalid())?;
+    if payload.len() < NONCE_LEN {
+        return Err(invalid());
+    }
+
+    let (nonce, sealed) = payload.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| invalid())?;
+    let key = provider.key(id)?;
+    let mut sealed = sealed.to_vec();
+    let plain = key
+        .cipher()?
+        .open_in_place(nonce, Aad::from(id.as_bytes()), &mut sealed)
+        .map_err(|_| ResourceError::Encryption(format!("unable to decrypt with key '{}'", id)))?;
+
+    Ok(plain.to_vec())
+}
+
+/// File system encrypting written files and decrypting read ones, wrapped around the
+/// resource's file system when encryption is enabled.
+pub struct EncryptedFileSystem {
+    inner: Arc<dyn FileSystem>,
+    provider: Arc<dyn KeyProvider>,
+}
+
+impl EncryptedFileSystem {
+    pub fn new(inner: Arc<dyn FileSystem>, provider: Arc<dyn KeyProvider>) -> Self {
+        Self { inner, provider }
+    }
+}
+
+fn io_error(error: ResourceError) -> Error {
+    Error::new(ErrorKind::InvalidData, error.to_string())
+}
+
+impl FileSystem for EncryptedFileSystem {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        decrypt(self.inner.read(path)?, &*self.provider).map_err(io_error)
+    }
+
+    fn write(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        let sealed = encrypt(content, &*self.provider).map_err(io_error)?;
+        self.inner.write(path, &sealed)
+    }
+
+    fn write_with_mode(&self, path: &Path, content: &[u8], mode: u32) -> io::Result<()> {
+        let sealed = encrypt(content, &*self.provider).map_err(io_error)?;
+        self.inner.write_with_mode(path, &sealed, mode)
+    }
+
+    fn create_new(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        let sealed = encrypt(content, &*self.provider).map_err(io_error)?;
+        self.inner.create_new(path, &sealed)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.inner.rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.inner.remove_file(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.inner.create_dir_all(path)
+    }
+
+    fn list_files(&self, directory: &Path) -> io::Result<Vec<PathBuf>> {
+        self.inner.list_files(directory)
+    }
+
+    fn file_size(&self, path: &Path) -> io::Result<u64> {
+        self.inner.file_size(path)
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        self.inner.modified(path)
+    }
+
+    fn sync_file(&self, path: &Path) -> io::Result<()> {
+        self.inner.sync_file(path)
+    }
+
+    fn sync_directory(&self, path: &Path) -> io::Result<()> {
+        self.inner.sync_directory(path)
+    }
+
+    fn set_mode(&self, path: &Path, mode: u32) -> io::Result<()> {
+        self.inner.set_mode(path, mode)
+    }
+
+    fn mode(&self, path: &Path) -> io::Result<Option<u32>> {
+        self.inner.mode(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_system::MemoryFileSystem;
+
+    #[test]
+    fn test_rotated_keys_still_decrypt() {
+        let old = EncryptionKey::new("2024q1", [1; 32]);
+        let new = EncryptionKey::new("2024q2", [2; 32]);
+        let memory = Arc::new(MemoryFileSystem::new());
+        let path = Path::new("/cache/flags-1.json");
+
+        let before = EncryptedFileSystem::new(
+            memory.clone(),
+            Arc::new(StaticKeys::new(old.clone(), Vec::new())),
+        );
+        before.create_dir_all(Path::new("/cache")).unwrap();
+        before.write(path, b"{\"enabled\": 1}").unwrap();
+
+        let stored = memory.contents(path).unwrap();
+        assert!(stored.starts_with(b"resourcely-aes-256-gcm\n2024q1\n"));
+        assert!(!stored.windows(7).any(|window| window == b"enabled"));
+
+        // rotated: new files use the new key, old ones stay readable
+        let after = EncryptedFileSystem::new(
+            memory.clone(),
+            Arc::new(StaticKeys::new(new.clone(), vec![old])),
+        );
+        assert_eq!(after.read_to_string(path).unwrap(), "{\"enabled\": 1}");
+        after.write(path, b"{}").unwrap();
+        assert!(memory
+            .contents(path)
+            .unwrap()
+            .starts_with(b"resourcely-aes-256-gcm\n2024q2\n"));
+
+        // the old key alone cannot read the new file, nor a tampered one
+        assert!(before.read(path).is_err());
+        let mut tampered = memory.contents(path).unwrap();
+        let middle = tampered.len() - 10;
+        tampered[middle] ^= 1;
+        memory.insert(path, tampered);
+        assert!(after.read(path).is_err());
+
+        // plain files, e.g. maintained by hand, are read as they are
+        memory.insert(path, "{\"plain\": true}");
+        assert_eq!(after.read_to_string(path).unwrap(), "{\"plain\": true}");
+    }
+}
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    base::ResourceState,
+    error::ResourceError,
+    traits::{DataResult, ResourceReader},
+};
+
+/// Reader sourcing the payload from an environment variable holding a JSON or YAML
+/// document, e.g. for container overrides.
+///
+/// The parsed value is cached in memory for the configured timeout; nothing is
+/// written to disk.
+pub struct EnvResourceReader<T> {
+    state: Arc<ResourceState<T>>,
+    variable: String,
+}
+
+impl<T> EnvResourceReader<T> {
+    pub fn new(state: impl Into<Arc<ResourceState<T>>>, variable: impl Into<String>) -> Self {
+        Self {
+            state: state.into(),
+            variable: variable.into(),
+        }
+    }
+
+    /// Name of the environment variable holding the payload.
+    pub fn variable(&self) -> &str {
+        &self.variable
+    }
+}
+
+impl<T> std::fmt::Debug for EnvResourceReader<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EnvResourceReader")
+            .field("state", &self.state)
+            .field("variable", &self.variable)
+            .finish()
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ResourceReader<T> for EnvResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
+        &self.state
+    }
+
+    async fn get_data_or_error(
+        &self,
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        let result = self
+            .load_data(allow_stale)
+            .and_then(|data| self.get_state().limit_staleness(data));
+
+        if let Err(error) = &result {
+            self.get_state().record_error(error);
+        }
+
+        result
+    }
+}
+
+impl<T> EnvResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    fn load_data(&self, allow_stale: bool) -> Result<DataResult<Arc<T>>, ResourceError> {
+        let mut stale_internal_data: Option<Arc<T>> = None;
+
+        if !self.get_state().is_marked_stale()? {
+            if let Some((data, fresh, _)) = self.get_state().get_internal_data()? {
+                if fresh {
+                    self.get_state().record_hit();
+                    return Ok(DataResult::Fresh(data));
+                }
+                stale_internal_data = Some(data);
+            }
+        }
+
+        self.get_state().record_miss();
+
+        let fresh_data_from_env = std::env::var(&self.variable)
+            .ok()
+            .and_then(|content| self.get_state().parse(&content).ok())
+            .map(Arc::new);
+
+        if fresh_data_from_env.is_none() && allow_stale {
+            if let Some(data) = stale_internal_data {
+                return Ok(DataResult::Stale(data));
+            }
+
+            return Err(ResourceError::StaleInternalNone);
+        }
+
+        let fresh_data = fresh_data_from_env.ok_or(ResourceError::UnableToFreshData)?;
+
+        self.get_state().set_internal_cache(fresh_data.clone())?;
+
+        Ok(DataResult::Fresh(fresh_data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::ResourceProps;
+    use crate::test_support::block_on;
+    use crate::traits::{ResourceFileType, StalePolicy};
+    use reqwest::Url;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    #[test]
+    fn test_reads_and_caches_environment_variable() {
+        let variable = format!("RESOURCELY_ENV_TEST_{}", std::process::id());
+        let reader = EnvResourceReader::<HashMap<String, u32>>::new(
+            ResourceState::new(ResourceProps::new(
+                "flags",
+                ResourceFileType::Yaml,
+                Url::parse(&format!("env:{}", variable)).unwrap(),
+                std::env::temp_dir().join("resourcely-env-missing"),
+                Some(Duration::from_secs(60)),
+            )),
+            &variable,
+        );
+
+        assert!(block_on(reader.get_data_or_default_with(StalePolicy::Never)).is_empty());
+
+        std::env::set_var(&variable, "enabled: 1");
+        match block_on(reader.get_data_or_error(false)) {
+            Ok(DataResult::Fresh(data)) => assert_eq!(data.get("enabled"), Some(&1)),
+            _ => panic!("Expected fresh data from the environment variable"),
+        }
+
+        // served from memory until the timeout elapses
+        std::env::remove_var(&variable);
+        assert!(block_on(reader.get_data_or_none_with(StalePolicy::Never)).is_some());
+        assert_eq!(reader.stats().unwrap().hits, 1);
+    }
+}
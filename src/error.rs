@@ -4,12 +4,18 @@ use std::{fmt, io};
 ///
 /// All fallible registry operations return this error type to indicate
 /// what went wrong during the operation.
+///
+/// Some variants only exist with their feature enabled (e.g. `Vault` with `vault`),
+/// so matches need a wildcard arm to keep compiling when another crate in the
+/// dependency graph enables more features.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ResourceError {
     /// Failed to acquire the cache lock.
     ///
-    /// This occurs when the internal cache lock cannot be acquired,
-    /// typically due to lock poisoning or contention.
+    /// No longer returned: a lock poisoned by a panicking thread is recovered with
+    /// [`PoisonError::into_inner`](std::sync::PoisonError::into_inner) instead of
+    /// failing every later call. Kept so existing matches keep compiling.
     CacheLock,
 
     /// No fresh or stale data found in the cache.
@@ -18,6 +24,23 @@ pub enum ResourceError {
     /// the fresh or stale cache state.
     StaleInternalNone,
 
+    /// The only data available is older than the stale policy allows.
+    ///
+    /// The duration is the age of that data.
+    StaleTooOld(std::time::Duration),
+
+    /// Every source of a remote resource came up empty.
+    ///
+    /// This occurs when fetching failed and neither the memory nor the disk cache
+    /// held data to fall back on. Reports what each source did.
+    AllSourcesFailed(Box<SourceFailures>),
+
+    /// A read did not complete within the time limit.
+    ///
+    /// The duration is the limit, see
+    /// [`ResourceReaderExt::with_timeout`](crate::ResourceReaderExt::with_timeout).
+    Timeout(std::time::Duration),
+
     /// Unable to refresh data from the source.
     ///
     /// This occurs when an attempt to fetch fresh data fails,
@@ -41,6 +64,169 @@ pub enum ResourceError {
     /// This wraps standard IO errors that occur during file operations.
     Io(io::Error),
 
+    /// HTTP request failed.
+    ///
+    /// This wraps errors reported by the HTTP client, such as DNS resolution,
+    /// connection or TLS failures.
+    Network(reqwest::Error),
+
+    /// Connecting to the origin took longer than the connect timeout.
+    ///
+    /// The origin is likely unreachable (down, filtered or a wrong address).
+    ConnectTimeout(reqwest::Error),
+
+    /// The origin accepted the connection but did not complete the response within
+    /// the request timeout.
+    RequestTimeout(reqwest::Error),
+
+    /// Offline mode is active and no cached data is available.
+    ///
+    /// This occurs when network access is disabled for a remote resource and
+    /// neither the memory nor the disk cache holds any data.
+    Offline,
+
+    /// A JSON Patch could not be applied.
+    ///
+    /// This occurs when a delta update received from the origin is malformed
+    /// or does not fit the cached document. The string describes the failure.
+    InvalidPatch(String),
+
+    /// External command failed.
+    ///
+    /// This occurs when the command of a command-output resource exits
+    /// unsuccessfully. The string contains the exit status and standard error.
+    Command(String),
+
+    /// Vault rejected a request or returned no secret.
+    ///
+    /// This occurs when authentication fails, the secret is missing or the
+    /// response cannot be interpreted. The string describes the failure.
+    #[cfg(feature = "vault")]
+    Vault(String),
+
+    /// AWS rejected a request or returned no value.
+    ///
+    /// This occurs when the request is refused (e.g. missing permissions
+    /// or an unknown parameter) or the response holds no value.
+    #[cfg(feature = "aws")]
+    Aws(String),
+
+    /// Consul rejected a request or the key does not exist.
+    ///
+    /// The string describes the failure.
+    #[cfg(feature = "consul")]
+    Consul(String),
+
+    /// The MQTT broker refused the connection or subscription, or the topic has no
+    /// retained message.
+    ///
+    /// The string describes the failure.
+    #[cfg(feature = "mqtt")]
+    Mqtt(String),
+
+    /// A disk cache file could not be encrypted or decrypted, e.g. its key is
+    /// unknown to the key provider.
+    ///
+    /// The string describes the failure.
+    #[cfg(feature = "encryption")]
+    Encryption(String),
+
+    /// A Parquet dataset could not be decoded.
+    ///
+    /// The string describes the failure.
+    #[cfg(feature = "parquet")]
+    Dataset(String),
+
+    /// Failure injected by a [`FaultInjector`](crate::testing::FaultInjector).
+    ///
+    /// Stands in for network failures, which cannot be constructed as
+    /// `reqwest` errors. The string names the injected fault.
+    #[cfg(any(test, feature = "testing"))]
+    InjectedFault(String),
+
+    /// A composite resource could not be assembled.
+    ///
+    /// This occurs when the merge function asks for a part that does not exist
+    /// or has another payload type. The string names the part.
+    Composite(String),
+
+    /// Declaring a dependency between registered resources would create a cycle.
+    ///
+    /// The string lists the resources forming the cycle.
+    DependencyCycle(String),
+
+    /// A registry snapshot kept seeing refreshes and gave up.
+    ///
+    /// Contains the names of the resources that changed during the last attempt.
+    SnapshotConflict(Vec<String>),
+
+    /// An edit was not committed because other data was stored since it began.
+    EditConflict,
+
+    /// The origin answered with an unsuccessful HTTP status.
+    ///
+    /// The number is the status code, e.g. `503`.
+    HttpStatus(u16),
+
+    /// The origin asked to retry later (`Retry-After` on a 429 or 503 response).
+    ///
+    /// No request is sent until the contained duration has elapsed.
+    RetryLater(std::time::Duration),
+
+    /// Requests are paused because the origin's content failed to parse repeatedly.
+    ///
+    /// No request is sent until the contained duration has elapsed, see
+    /// [`ParseBackoff`](crate::state_manager::ParseBackoff).
+    ParseBackoff(std::time::Duration),
+
+    /// A resource URL could not be built.
+    ///
+    /// This occurs when a URL template has a placeholder without a parameter
+    /// or the substituted URL does not parse. The string describes the failure.
+    InvalidUrl(String),
+
+    /// A document has fields the payload type does not know.
+    ///
+    /// Only reported in [`SchemaMode::Strict`](crate::state_manager::SchemaMode::Strict).
+    /// Contains the paths of the fields, e.g. `limits.brust`.
+    UnknownFields(Vec<String>),
+
+    /// A document does not deserialize into the payload type.
+    ///
+    /// Reported in [`SchemaMode::Warn`](crate::state_manager::SchemaMode::Warn) and
+    /// [`SchemaMode::Strict`](crate::state_manager::SchemaMode::Strict). Contains the
+    /// path of the failing value, e.g. `rules[2].name` (`.` for the document itself),
+    /// and the parser's message.
+    SchemaMismatch(String, String),
+
+    /// A refresh schedule could not be parsed.
+    ///
+    /// The string describes the malformed part of the cron expression.
+    InvalidSchedule(String),
+
+    /// A cache namespace is not a nested relative path.
+    ///
+    /// Contains the rejected namespace, e.g. `../shared` or `/tmp`.
+    InvalidNamespace(String),
+
+    /// A disk cache file does not deserialize into the resource type.
+    ///
+    /// Only reported in strict parse mode; otherwise such files are skipped.
+    /// Contains the file path and the parser's message.
+    InvalidCacheFile(std::path::PathBuf, String),
+
+    /// The storage directory is writable by group or others.
+    ///
+    /// This occurs in strict permission mode, where such a directory is refused
+    /// because others could read or plant cache files. The string is the path.
+    InsecureDirectory(String),
+
+    /// Malformed `data:` URL.
+    ///
+    /// This occurs when an inline resource URL lacks the `,` separating the
+    /// media type from the payload, or its base64 payload cannot be decoded.
+    InvalidDataUrl,
+
     /// Unsupported file type encountered.
     ///
     /// This occurs when attempting to process a file with an unsupported format.
@@ -75,6 +261,35 @@ pub enum ResourceError {
     TimestampParseError,
 }
 
+/// What each source of a remote resource did in a read that served nothing.
+#[derive(Debug)]
+pub struct SourceFailures {
+    /// The error of the fetch from the origin.
+    pub network: ResourceError,
+    /// The outcome of the on disk cache lookup.
+    pub disk: CacheLookup,
+    /// The outcome of the in memory cache lookup.
+    pub memory: CacheLookup,
+}
+
+/// Outcome of looking up a cache layer that provided no data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheLookup {
+    /// Not consulted, e.g. because the resource was marked stale.
+    Skipped,
+    /// Consulted and found empty.
+    Empty,
+}
+
+impl fmt::Display for CacheLookup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheLookup::Skipped => write!(f, "skipped"),
+            CacheLookup::Empty => write!(f, "empty"),
+        }
+    }
+}
+
 /// Helper constructors for common error patterns.
 impl ResourceError {
     /// Creates a deserialization error for the specified format.
@@ -112,6 +327,22 @@ impl ResourceError {
     pub fn io(error: io::Error) -> ResourceError {
         ResourceError::Io(error)
     }
+
+    /// Creates a network error from an HTTP client error.
+    ///
+    /// Timeouts are reported as [`ConnectTimeout`](Self::ConnectTimeout) or
+    /// [`RequestTimeout`](Self::RequestTimeout).
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - The underlying HTTP client error
+    pub fn network(error: reqwest::Error) -> ResourceError {
+        match (error.is_timeout(), error.is_connect()) {
+            (true, true) => ResourceError::ConnectTimeout(error),
+            (true, false) => ResourceError::RequestTimeout(error),
+            _ => ResourceError::Network(error),
+        }
+    }
 }
 
 impl fmt::Display for ResourceError {
@@ -121,6 +352,17 @@ impl fmt::Display for ResourceError {
             ResourceError::StaleInternalNone => {
                 write!(f, "No fresh or stale data found")
             }
+            ResourceError::StaleTooOld(age) => {
+                write!(f, "Stale data is too old ({}s)", age.as_secs())
+            }
+            ResourceError::AllSourcesFailed(failures) => write!(
+                f,
+                "All sources failed (network: {}; disk cache: {}; memory cache: {})",
+                failures.network, failures.disk, failures.memory
+            ),
+            ResourceError::Timeout(limit) => {
+                write!(f, "Read timed out after {}ms", limit.as_millis())
+            }
             ResourceError::UnableToFreshData => {
                 write!(f, "Unable to refresh data")
             }
@@ -131,6 +373,81 @@ impl fmt::Display for ResourceError {
                 write!(f, "Failed to serialize {} data", format)
             }
             ResourceError::Io(e) => write!(f, "IO error: {}", e),
+            ResourceError::Network(e) => write!(f, "Network error: {}", e),
+            ResourceError::ConnectTimeout(e) => write!(f, "Connect timeout: {}", e),
+            ResourceError::RequestTimeout(e) => write!(f, "Request timeout: {}", e),
+            ResourceError::Offline => {
+                write!(f, "Offline mode is active and no cached data is available")
+            }
+            ResourceError::InvalidPatch(reason) => {
+                write!(f, "Unable to apply JSON Patch: {}", reason)
+            }
+            ResourceError::Command(reason) => write!(f, "Command failed: {}", reason),
+            #[cfg(feature = "vault")]
+            ResourceError::Vault(reason) => write!(f, "Vault error: {}", reason),
+            #[cfg(feature = "aws")]
+            ResourceError::Aws(reason) => write!(f, "AWS error: {}", reason),
+            #[cfg(feature = "consul")]
+            ResourceError::Consul(reason) => write!(f, "Consul error: {}", reason),
+            #[cfg(feature = "mqtt")]
+            ResourceError::Mqtt(reason) => write!(f, "MQTT error: {}", reason),
+            #[cfg(feature = "encryption")]
+            ResourceError::Encryption(reason) => write!(f, "Encryption error: {}", reason),
+            #[cfg(feature = "parquet")]
+            ResourceError::Dataset(reason) => write!(f, "Dataset error: {}", reason),
+            #[cfg(any(test, feature = "testing"))]
+            ResourceError::InjectedFault(fault) => write!(f, "Injected fault: {}", fault),
+            ResourceError::Composite(reason) => write!(f, "Composite resource error: {}", reason),
+            ResourceError::DependencyCycle(cycle) => {
+                write!(f, "Resource dependency cycle: {}", cycle)
+            }
+            ResourceError::EditConflict => {
+                write!(f, "The resource changed while it was edited")
+            }
+            ResourceError::SnapshotConflict(names) => {
+                write!(
+                    f,
+                    "Resources changed during the snapshot: {}",
+                    names.join(", ")
+                )
+            }
+            ResourceError::HttpStatus(status) => {
+                write!(f, "Origin answered with HTTP status {}", status)
+            }
+            ResourceError::RetryLater(remaining) => {
+                write!(f, "Origin asked to retry in {}s", remaining.as_secs())
+            }
+            ResourceError::ParseBackoff(remaining) => write!(
+                f,
+                "Origin content failed to parse; retrying in {}s",
+                remaining.as_secs()
+            ),
+            ResourceError::InvalidUrl(reason) => write!(f, "Invalid URL: {}", reason),
+            ResourceError::UnknownFields(fields) => {
+                write!(f, "Unknown fields: {}", fields.join(", "))
+            }
+            ResourceError::SchemaMismatch(path, reason) => {
+                write!(f, "Field `{}` does not match the schema: {}", path, reason)
+            }
+            ResourceError::InvalidSchedule(reason) => {
+                write!(f, "Invalid refresh schedule: {}", reason)
+            }
+            ResourceError::InvalidNamespace(namespace) => write!(
+                f,
+                "Namespace '{}' must be a relative path without '..'",
+                namespace
+            ),
+            ResourceError::InvalidCacheFile(path, reason) => {
+                write!(f, "Invalid cache file {}: {}", path.display(), reason)
+            }
+            ResourceError::InsecureDirectory(path) => {
+                write!(
+                    f,
+                    "Storage directory is writable by group or others: {}",
+                    path
+                )
+            }
+            ResourceError::InvalidDataUrl => write!(f, "Invalid data URL"),
             ResourceError::UnsupportedFileType(file_type) => {
                 write!(f, "Unsupported file type: {}", file_type)
             }
@@ -153,7 +470,14 @@ impl fmt::Display for ResourceError {
     }
 }
 
-impl std::error::Error for ResourceError {}
+impl std::error::Error for ResourceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ResourceError::AllSourcesFailed(failures) => Some(&failures.network),
+            _ => None,
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -192,4 +516,21 @@ mod tests {
         let err_trait: &dyn std::error::Error = &ResourceError::UnableToFreshData;
         assert_eq!(err_trait.to_string(), err_concrete.to_string());
     }
+
+    #[test]
+    fn test_all_sources_failed_reports_each_source() {
+        let err = ResourceError::AllSourcesFailed(Box::new(SourceFailures {
+            network: ResourceError::HttpStatus(503),
+            disk: CacheLookup::Empty,
+            memory: CacheLookup::Skipped,
+        }));
+
+        assert_eq!(
+            err.to_string(),
+            "All sources failed (network: Origin answered with HTTP status 503; \
+             disk cache: empty; memory cache: skipped)"
+        );
+        let source = std::error::Error::source(&err).unwrap();
+        assert_eq!(source.to_string(), "Origin answered with HTTP status 503");
+    }
 }
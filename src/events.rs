@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::base::SchemaWarning;
+
+/// Notable state transitions of a resource, delivered to its [`EventListener`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResourceEvent {
+    /// Repeated connection failures switched the resource into offline-serving mode.
+    WentOffline {
+        resource: String,
+        consecutive_failures: u32,
+        cooldown: Duration,
+    },
+    /// The origin was reachable again after an automatic offline period.
+    BackOnline { resource: String },
+    /// Certificate verification is disabled for the resource (development only).
+    InsecureTls { resource: String },
+    /// A mirror (or the resource URL) was probed; `latency` is `None` when the probe
+    /// failed or timed out.
+    MirrorProbed {
+        resource: String,
+        url: String,
+        latency: Option<Duration>,
+        healthy: bool,
+    },
+    /// Mirror probing switched requests to a faster healthy URL.
+    MirrorSelected {
+        resource: String,
+        url: String,
+        latency: Duration,
+    },
+    /// A write-behind save of the disk cache failed; the memory cache is unaffected.
+    DiskWriteFailed { resource: String, error: String },
+    /// A disk cache file failed to parse and was moved into the quarantine directory.
+    CacheFileQuarantined {
+        resource: String,
+        path: PathBuf,
+        error: String,
+    },
+    /// A fetched document differs from the payload type, see
+    /// [`SchemaMode::Warn`](crate::state_manager::SchemaMode::Warn).
+    SchemaDrift {
+        resource: String,
+        warnings: Vec<SchemaWarning>,
+    },
+    /// Aged disk cache files were deleted, see [`Registry::spawn_janitor`](crate::Registry::spawn_janitor).
+    CacheFilesRemoved {
+        resource: String,
+        files: usize,
+        bytes: u64,
+    },
+    /// Data was dropped from the memory cache to stay within a memory budget; reads
+    /// fall back to the disk cache.
+    EvictedFromMemory { resource: String, weight: usize },
+}
+
+/// Callback receiving [`ResourceEvent`]s, e.g. to show a degraded-mode banner.
+pub type EventListener = Arc<dyn Fn(&ResourceEvent) + Send + Sync>;
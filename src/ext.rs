@@ -0,0 +1,315 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    base::ResourceState,
+    error::ResourceError,
+    traits::{DataResult, ResourceReader, StalePolicy},
+    view::View,
+};
+
+/// Combinators adding behavior to any reader, implemented for every [`ResourceReader`].
+///
+/// The returned readers share the state (cache, statistics, events) of the reader
+/// they wrap, so they can be combined further or registered like any other reader.
+pub trait ResourceReaderExt<T>: ResourceReader<T> + Sized
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    /// Read `fallback` when this reader returns an error.
+    fn with_fallback<R>(self, fallback: R) -> WithFallback<Self, R>
+    where
+        R: ResourceReader<T>,
+    {
+        WithFallback {
+            primary: self,
+            fallback,
+        }
+    }
+
+    /// Fail with [`ResourceError::Timeout`] when a read takes longer than `timeout`.
+    fn with_timeout(self, timeout: Duration) -> WithTimeout<Self> {
+        WithTimeout {
+            reader: self,
+            timeout,
+        }
+    }
+
+    /// Derived value recomputed only when the data changes, owning the reader; see
+    /// [`View`] and [`ResourceReader::view`] for a view borrowing it.
+    fn cached_view<U, F>(self, project: F) -> View<'static, T, U>
+    where
+        Self: Send + Sync + 'static,
+        T: 'static,
+        F: Fn(&T) -> U + Send + Sync + 'static,
+    {
+        View::owned(self, project)
+    }
+
+    /// Convert errors into an application error type at the reader boundary, e.g. with
+    /// the resource name attached; the mapper receives the reader's state for context.
+    fn map_err<E, F>(self, mapper: F) -> MapErr<Self, F>
+    where
+        F: Fn(ResourceError, &ResourceState<T>) -> E,
+    {
+        MapErr {
+            reader: self,
+            mapper,
+        }
+    }
+
+    /// Serve `value` as stale data when the reader has no data or fails and stale data
+    /// is allowed.
+    fn or_default_value(self, value: T) -> OrDefaultValue<Self, T> {
+        OrDefaultValue {
+            reader: self,
+            value: Arc::new(value),
+        }
+    }
+}
+
+impl<T, R> ResourceReaderExt<T> for R
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+    R: ResourceReader<T>,
+{
+}
+
+/// Reader falling back to a second reader, see [`ResourceReaderExt::with_fallback`].
+pub struct WithFallback<P, F> {
+    primary: P,
+    fallback: F,
+}
+
+#[async_trait::async_trait]
+impl<T, P, F> ResourceReader<T> for WithFallback<P, F>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+    P: ResourceReader<T> + Send + Sync,
+    F: ResourceReader<T> + Send + Sync,
+{
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
+        self.primary.get_state()
+    }
+
+    /// The primary reader's data, or the fallback's; the primary's error when both fail.
+    async fn get_data_or_error(
+        &self,
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        match self.primary.get_data_or_error(allow_stale).await {
+            Ok(result) => Ok(result),
+            Err(error) => self
+                .fallback
+                .get_data_or_error(allow_stale)
+                .await
+                .map_err(|_| error),
+        }
+    }
+}
+
+/// Reader bounding the duration of reads, see [`ResourceReaderExt::with_timeout`].
+pub struct WithTimeout<R> {
+    reader: R,
+    timeout: Duration,
+}
+
+#[async_trait::async_trait]
+impl<T, R> ResourceReader<T> for WithTimeout<R>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+    R: ResourceReader<T> + Send + Sync,
+{
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
+        self.reader.get_state()
+    }
+
+    async fn get_data_or_error(
+        &self,
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        tokio::time::timeout(self.timeout, self.reader.get_data_or_error(allow_stale))
+            .await
+            .unwrap_or(Err(ResourceError::Timeout(self.timeout)))
+    }
+}
+
+/// Reader with a last resort value, see [`ResourceReaderExt::or_default_value`].
+pub struct OrDefaultValue<R, T> {
+    reader: R,
+    value: Arc<T>,
+}
+
+#[async_trait::async_trait]
+impl<T, R> ResourceReader<T> for OrDefaultValue<R, T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+    R: ResourceReader<T> + Send + Sync,
+{
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
+        self.reader.get_state()
+    }
+
+    async fn get_data_or_error(
+        &self,
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        match self.reader.get_data_or_error(allow_stale).await {
+            Ok(DataResult::Absent) | Err(_) if allow_stale => {
+                Ok(DataResult::Stale(Arc::clone(&self.value)))
+            }
+            result => result,
+        }
+    }
+}
+
+/// Reader returning an application error type, see [`ResourceReaderExt::map_err`].
+///
+/// It is not a [`ResourceReader`] itself, as those fail with [`ResourceError`]; the
+/// wrapped reader is available through [`reader`](Self::reader).
+pub struct MapErr<R, F> {
+    reader: R,
+    mapper: F,
+}
+
+impl<R, F> MapErr<R, F> {
+    /// The wrapped reader.
+    pub fn reader(&self) -> &R {
+        &self.reader
+    }
+
+    /// See [`ResourceReader::get_data_or_error`].
+    pub async fn get_data_or_error<T, E>(&self, allow_stale: bool) -> Result<DataResult<Arc<T>>, E>
+    where
+        T: Send + Sync + DeserializeOwned + Serialize + Default,
+        R: ResourceReader<T> + Send + Sync,
+        F: Fn(ResourceError, &ResourceState<T>) -> E,
+    {
+        self.reader
+            .get_data_or_error(allow_stale)
+            .await
+            .map_err(|error| (self.mapper)(error, self.reader.get_state()))
+    }
+
+    /// See [`ResourceReader::get_data`].
+    pub async fn get_data<T, E>(&self, policy: StalePolicy) -> Result<DataResult<Arc<T>>, E>
+    where
+        T: Send + Sync + DeserializeOwned + Serialize + Default,
+        R: ResourceReader<T> + Send + Sync,
+        F: Fn(ResourceError, &ResourceState<T>) -> E,
+    {
+        self.reader
+            .get_data(policy)
+            .await
+            .map_err(|error| (self.mapper)(error, self.reader.get_state()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::block_on;
+    use crate::testing::MockResourceReader;
+    use std::collections::HashMap;
+
+    /// Reader whose reads never complete.
+    struct Hanging(MockResourceReader<u32>);
+
+    #[async_trait::async_trait]
+    impl ResourceReader<u32> for Hanging {
+        fn get_state(&self) -> &Arc<ResourceState<u32>> {
+            self.0.get_state()
+        }
+
+        async fn get_data_or_error(
+            &self,
+            _allow_stale: bool,
+        ) -> Result<DataResult<Arc<u32>>, ResourceError> {
+            std::future::pending().await
+        }
+    }
+
+    #[test]
+    fn test_fallback_and_default_value() {
+        let primary = MockResourceReader::<u32>::new("primary");
+        let fallback = MockResourceReader::<u32>::new("fallback");
+        fallback.push_fresh(7);
+        for _ in 0..3 {
+            primary.push_error(ResourceError::HttpStatus(503));
+        }
+        for _ in 0..2 {
+            fallback.push_error(ResourceError::UnableToFreshData);
+        }
+
+        let reader = primary.with_fallback(fallback).or_default_value(42);
+        assert_eq!(
+            block_on(reader.get_data_or_none_with(StalePolicy::Never)),
+            Some(Arc::new(7))
+        );
+
+        // both fail, only the default value is left
+        assert!(matches!(
+            block_on(reader.get_data_or_error(false)),
+            Err(ResourceError::HttpStatus(503))
+        ));
+        assert!(matches!(
+            block_on(reader.get_data_or_error(true)),
+            Ok(DataResult::Stale(value)) if *value == 42
+        ));
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct AppError {
+        resource: String,
+        request_id: u32,
+        message: String,
+    }
+
+    #[test]
+    fn test_map_err_attaches_context() {
+        let reader = MockResourceReader::<u32>::new("pricing");
+        reader.push_error(ResourceError::HttpStatus(503));
+        reader.push_fresh(1);
+
+        let request_id = 42;
+        let reader = reader.map_err(|error, state| AppError {
+            resource: state.get_file_name().to_string(),
+            request_id,
+            message: error.to_string(),
+        });
+
+        let Err(error) = block_on(reader.get_data_or_error(true)) else {
+            panic!("expected the mapped error");
+        };
+        assert_eq!(
+            error,
+            AppError {
+                resource: "pricing".to_string(),
+                request_id: 42,
+                message: "Origin answered with HTTP status 503".to_string(),
+            }
+        );
+        assert!(matches!(
+            block_on(reader.get_data(StalePolicy::Never)),
+            Ok(DataResult::Fresh(value)) if *value == 1
+        ));
+        assert_eq!(reader.reader().calls(), 2);
+    }
+
+    #[test]
+    fn test_timeout_and_cached_view() {
+        let reader =
+            Hanging(MockResourceReader::new("slow")).with_timeout(Duration::from_millis(20));
+        assert!(matches!(
+            block_on(reader.get_data_or_error(true)),
+            Err(ResourceError::Timeout(timeout)) if timeout == Duration::from_millis(20)
+        ));
+
+        let users = MockResourceReader::<Vec<(u32, String)>>::new("users");
+        users.push_fresh(vec![(1, "ada".to_string())]);
+        let by_id = users.cached_view(|users| users.iter().cloned().collect::<HashMap<_, _>>());
+        assert_eq!(block_on(by_id.get_or_none(false)).unwrap()[&1], "ada");
+    }
+}
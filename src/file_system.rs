@@ -0,0 +1,490 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+/// File operations used by the disk cache.
+///
+/// [`RealFileSystem`] is used unless a resource is given another implementation, e.g.
+/// a [`MemoryFileSystem`] simulating corrupted files or permission errors in tests.
+pub trait FileSystem: Send + Sync {
+    fn read(&self, path: &Path) -> Result<Vec<u8>>;
+
+    fn write(&self, path: &Path, content: &[u8]) -> Result<()>;
+
+    /// Write a file that has the Unix permission bits `mode` before any content is
+    /// written, so it is never readable by others in between.
+    ///
+    /// Unless implemented, the file is written and then given the mode.
+    fn write_with_mode(&self, path: &Path, content: &[u8], mode: u32) -> Result<()> {
+        self.write(path, content)?;
+        self.set_mode(path, mode)
+    }
+
+    /// Create a file that must not exist yet, failing with `AlreadyExists` otherwise;
+    /// used as a lock shared with other processes.
+    ///
+    /// Unless implemented, the check and the write are not atomic.
+    fn create_new(&self, path: &Path, content: &[u8]) -> Result<()> {
+        if self.read(path).is_ok() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists", path.display()),
+            ));
+        }
+        self.write(path, content)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()>;
+
+    fn remove_file(&self, path: &Path) -> Result<()>;
+
+    fn create_dir_all(&self, path: &Path) -> Result<()>;
+
+    /// Regular files directly inside `directory`.
+    fn list_files(&self, directory: &Path) -> Result<Vec<PathBuf>>;
+
+    /// Size of the file in bytes.
+    fn file_size(&self, path: &Path) -> Result<u64>;
+
+    /// Last modification time of the file (`Unsupported` unless implemented).
+    fn modified(&self, _path: &Path) -> Result<SystemTime> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "modification times are not available",
+        ))
+    }
+
+    /// Flush the file's content to stable storage (no-op unless implemented).
+    fn sync_file(&self, _path: &Path) -> Result<()> {
+        Ok(())
+    }
+
+    /// Flush the directory's entries (e.g. a rename) to stable storage (no-op unless
+    /// implemented).
+    fn sync_directory(&self, _path: &Path) -> Result<()> {
+        Ok(())
+    }
+
+    /// Set the Unix permission bits of a file (no-op unless implemented).
+    fn set_mode(&self, _path: &Path, _mode: u32) -> Result<()> {
+        Ok(())
+    }
+
+    /// Unix permission bits of a file or directory, `None` when not supported.
+    fn mode(&self, _path: &Path) -> Result<Option<u32>> {
+        Ok(None)
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        String::from_utf8(self.read(path)?).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+/// The operating system's file system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealFileSystem;
+
+impl FileSystem for RealFileSystem {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn write(&self, path: &Path, content: &[u8]) -> Result<()> {
+        std::fs::write(path, content)
+    }
+
+    #[cfg(unix)]
+    fn write_with_mode(&self, path: &Path, content: &[u8], mode: u32) -> Result<()> {
+        use std::io::Write;
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(mode)
+            .open(path)?;
+        // the mode only applies to new files; an existing one is restricted first
+        file.set_permissions(std::fs::Permissions::from_mode(mode))?;
+        file.write_all(content)
+    }
+
+    fn create_new(&self, path: &Path, content: &[u8]) -> Result<()> {
+        use std::io::Write;
+
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?
+            .write_all(content)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        std::fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        std::fs::remove_file(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        std::fs::create_dir_all(path)
+    }
+
+    fn list_files(&self, directory: &Path) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+
+        for entry in std::fs::read_dir(directory)? {
+            let file_path = entry?.path();
+            if file_path.is_file() {
+                files.push(file_path);
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn file_size(&self, path: &Path) -> Result<u64> {
+        std::fs::metadata(path).map(|metadata| metadata.len())
+    }
+
+    fn modified(&self, path: &Path) -> Result<SystemTime> {
+        std::fs::metadata(path)?.modified()
+    }
+
+    fn sync_file(&self, path: &Path) -> Result<()> {
+        std::fs::File::open(path)?.sync_all()
+    }
+
+    #[cfg(unix)]
+    fn sync_directory(&self, path: &Path) -> Result<()> {
+        std::fs::File::open(path)?.sync_all()
+    }
+
+    #[cfg(unix)]
+    fn set_mode(&self, path: &Path, mode: u32) -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+    }
+
+    #[cfg(unix)]
+    fn mode(&self, path: &Path) -> Result<Option<u32>> {
+        use std::os::unix::fs::PermissionsExt;
+        Ok(Some(std::fs::metadata(path)?.permissions().mode() & 0o7777))
+    }
+}
+
+/// File system kept in memory, for tests.
+///
+/// Directories exist once created (explicitly or by inserting a file into them).
+/// Paths below a [denied](Self::deny) prefix fail with `PermissionDenied`.
+#[derive(Debug, Default)]
+pub struct MemoryFileSystem {
+    files: RwLock<BTreeMap<PathBuf, Vec<u8>>>,
+    directories: RwLock<BTreeSet<PathBuf>>,
+    modes: RwLock<BTreeMap<PathBuf, u32>>,
+    modified: RwLock<BTreeMap<PathBuf, SystemTime>>,
+    denied: RwLock<Vec<PathBuf>>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Put a file (e.g. a corrupted cache entry) in place, creating its directory.
+    pub fn insert(&self, path: impl Into<PathBuf>, content: impl Into<Vec<u8>>) {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            self.add_directories(parent);
+        }
+        self.touch(&path);
+        if let Ok(mut files) = self.files.write() {
+            files.insert(path, content.into());
+        }
+    }
+
+    /// Set the modification time of an existing file, e.g. to age it.
+    pub fn set_modified(&self, path: &Path, time: SystemTime) {
+        if let Ok(mut modified) = self.modified.write() {
+            modified.insert(path.to_path_buf(), time);
+        }
+    }
+
+    fn touch(&self, path: &Path) {
+        self.set_modified(path, SystemTime::now());
+    }
+
+    /// Content of the file, if it exists.
+    pub fn contents(&self, path: &Path) -> Option<Vec<u8>> {
+        self.files.read().ok()?.get(path).cloned()
+    }
+
+    /// Every file path, sorted.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.files
+            .read()
+            .map(|files| files.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Make every operation on `path` and below fail with `PermissionDenied`.
+    pub fn deny(&self, path: impl Into<PathBuf>) {
+        if let Ok(mut denied) = self.denied.write() {
+            denied.push(path.into());
+        }
+    }
+
+    /// Lift all restrictions set by [`deny`](Self::deny).
+    pub fn allow_all(&self) {
+        if let Ok(mut denied) = self.denied.write() {
+            denied.clear();
+        }
+    }
+
+    fn check(&self, path: &Path) -> Result<()> {
+        let denied = self.denied.read().map_err(|_| poisoned())?;
+
+        if denied.iter().any(|prefix| path.starts_with(prefix)) {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                format!("access to {} denied", path.display()),
+            ));
+        }
+        Ok(())
+    }
+
+    fn has_directory(&self, path: &Path) -> Result<bool> {
+        Ok(path.as_os_str().is_empty()
+            || self
+                .directories
+                .read()
+                .map_err(|_| poisoned())?
+                .contains(path))
+    }
+
+    fn add_directories(&self, path: &Path) {
+        if let Ok(mut directories) = self.directories.write() {
+            for ancestor in path.ancestors() {
+                directories.insert(ancestor.to_path_buf());
+            }
+        }
+    }
+}
+
+fn poisoned() -> Error {
+    Error::other("memory file system lock poisoned")
+}
+
+fn not_found(path: &Path) -> Error {
+    Error::new(
+        ErrorKind::NotFound,
+        format!("{} does not exist", path.display()),
+    )
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        self.check(path)?;
+        self.contents(path).ok_or_else(|| not_found(path))
+    }
+
+    fn write(&self, path: &Path, content: &[u8]) -> Result<()> {
+        self.check(path)?;
+        if !self.has_directory(path.parent().unwrap_or(Path::new("")))? {
+            return Err(not_found(path));
+        }
+
+        self.files
+            .write()
+            .map_err(|_| poisoned())?
+            .insert(path.to_path_buf(), content.to_vec());
+        self.touch(path);
+        Ok(())
+    }
+
+    fn create_new(&self, path: &Path, content: &[u8]) -> Result<()> {
+        self.check(path)?;
+        if !self.has_directory(path.parent().unwrap_or(Path::new("")))? {
+            return Err(not_found(path));
+        }
+
+        let mut files = self.files.write().map_err(|_| poisoned())?;
+        if files.contains_key(path) {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists", path.display()),
+            ));
+        }
+        files.insert(path.to_path_buf(), content.to_vec());
+        drop(files);
+        self.touch(path);
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.check(from)?;
+        self.check(to)?;
+
+        let mut files = self.files.write().map_err(|_| poisoned())?;
+        let content = files.remove(from).ok_or_else(|| not_found(from))?;
+        files.insert(to.to_path_buf(), content);
+
+        let mut modes = self.modes.write().map_err(|_| poisoned())?;
+        match modes.remove(from) {
+            Some(mode) => modes.insert(to.to_path_buf(), mode),
+            None => modes.remove(to),
+        };
+
+        let mut modified = self.modified.write().map_err(|_| poisoned())?;
+        if let Some(time) = modified.remove(from) {
+            modified.insert(to.to_path_buf(), time);
+        }
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        self.check(path)?;
+        self.files
+            .write()
+            .map_err(|_| poisoned())?
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| not_found(path))
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        self.check(path)?;
+        self.add_directories(path);
+        Ok(())
+    }
+
+    fn list_files(&self, directory: &Path) -> Result<Vec<PathBuf>> {
+        self.check(directory)?;
+        if !self.has_directory(directory)? {
+            return Err(not_found(directory));
+        }
+
+        Ok(self
+            .files
+            .read()
+            .map_err(|_| poisoned())?
+            .keys()
+            .filter(|path| path.parent() == Some(directory))
+            .cloned()
+            .collect())
+    }
+
+    fn file_size(&self, path: &Path) -> Result<u64> {
+        self.read(path).map(|content| content.len() as u64)
+    }
+
+    fn modified(&self, path: &Path) -> Result<SystemTime> {
+        self.check(path)?;
+        self.contents(path).ok_or_else(|| not_found(path))?;
+        Ok(self
+            .modified
+            .read()
+            .map_err(|_| poisoned())?
+            .get(path)
+            .copied()
+            .unwrap_or(SystemTime::UNIX_EPOCH))
+    }
+
+    fn set_mode(&self, path: &Path, mode: u32) -> Result<()> {
+        self.check(path)?;
+        if !self.has_directory(path)? && self.contents(path).is_none() {
+            return Err(not_found(path));
+        }
+        self.modes
+            .write()
+            .map_err(|_| poisoned())?
+            .insert(path.to_path_buf(), mode);
+        Ok(())
+    }
+
+    fn mode(&self, path: &Path) -> Result<Option<u32>> {
+        self.check(path)?;
+        Ok(self
+            .modes
+            .read()
+            .map_err(|_| poisoned())?
+            .get(path)
+            .copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::test_directory;
+
+    #[test]
+    fn test_memory_file_system_behaves_like_a_disk() {
+        let fs = MemoryFileSystem::new();
+        let directory = Path::new("/cache");
+
+        assert_eq!(
+            fs.list_files(directory).unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+        assert_eq!(
+            fs.write(&directory.join("a.json"), b"{}")
+                .unwrap_err()
+                .kind(),
+            ErrorKind::NotFound
+        );
+
+        fs.create_dir_all(directory).unwrap();
+        fs.write(&directory.join("a.json"), b"{}").unwrap();
+        fs.rename(&directory.join("a.json"), &directory.join("b.json"))
+            .unwrap();
+        fs.insert("/cache/nested/c.json", "[]");
+
+        assert_eq!(
+            fs.list_files(directory).unwrap(),
+            vec![directory.join("b.json")]
+        );
+        assert_eq!(fs.read_to_string(&directory.join("b.json")).unwrap(), "{}");
+        assert_eq!(fs.file_size(Path::new("/cache/nested/c.json")).unwrap(), 2);
+
+        fs.deny("/cache/nested");
+        assert_eq!(
+            fs.read(Path::new("/cache/nested/c.json"))
+                .unwrap_err()
+                .kind(),
+            ErrorKind::PermissionDenied
+        );
+        fs.allow_all();
+        fs.remove_file(Path::new("/cache/nested/c.json")).unwrap();
+        assert_eq!(fs.paths(), vec![directory.join("b.json")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_files_are_created_with_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = test_directory("file-mode");
+        std::fs::create_dir_all(&directory).unwrap();
+        let created = directory.join("created.json");
+        let existing = directory.join("existing.json");
+        std::fs::write(&existing, "{}").unwrap();
+        std::fs::set_permissions(&existing, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        RealFileSystem
+            .write_with_mode(&created, b"{}", 0o600)
+            .unwrap();
+        RealFileSystem
+            .write_with_mode(&existing, b"[]", 0o600)
+            .unwrap();
+
+        assert_eq!(RealFileSystem.mode(&created).unwrap(), Some(0o600));
+        assert_eq!(RealFileSystem.mode(&existing).unwrap(), Some(0o600));
+        assert_eq!(std::fs::read(&existing).unwrap(), b"[]");
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
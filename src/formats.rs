@@ -0,0 +1,662 @@
+//! Line based configuration formats (INI, `.properties`, dotenv).
+//!
+//! Documents are read into a [`serde_json::Value`] of strings and deserialized with
+//! [`deserialize_strings`], which parses numbers and booleans out of those strings
+//! where the target type asks for them.
+
+use serde::de::{
+    self, value::MapDeserializer, value::SeqDeserializer, Deserialize, Deserializer,
+    IntoDeserializer, Visitor,
+};
+use serde_json::{Map, Value};
+
+use crate::error::ResourceError;
+
+/// Parse an INI document: `key = value` pairs (or `key: value`), grouped by `[section]`
+/// headers into nested objects. Pairs before the first header are top level keys;
+/// lines starting with `;` or `#` are comments and quotes around values are removed.
+pub fn parse_ini(content: &str) -> Result<Value, ResourceError> {
+    let invalid = || ResourceError::deserialization("INI");
+    let mut root = Map::new();
+    let mut section: Option<String> = None;
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name.strip_suffix(']').ok_or_else(invalid)?.trim();
+            match root.entry(name) {
+                serde_json::map::Entry::Vacant(entry) => {
+                    entry.insert(Value::Object(Map::new()));
+                }
+                serde_json::map::Entry::Occupied(entry) if !entry.get().is_object() => {
+                    return Err(invalid())
+                }
+                serde_json::map::Entry::Occupied(_) => {}
+            }
+            section = Some(name.to_string());
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .or_else(|| line.split_once(':'))
+            .ok_or_else(invalid)?;
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+            .unwrap_or(value);
+
+        let target = match &section {
+            Some(name) => root
+                .get_mut(name)
+                .and_then(Value::as_object_mut)
+                .ok_or_else(invalid)?,
+            None => &mut root,
+        };
+        target.insert(key.trim().to_string(), Value::String(value.to_string()));
+    }
+
+    Ok(Value::Object(root))
+}
+
+/// Write a document as INI; objects become sections, so it may nest one level only.
+pub fn render_ini(document: &Value) -> Result<String, ResourceError> {
+    let invalid = || ResourceError::serialization("INI");
+    let root = document.as_object().ok_or_else(invalid)?;
+    let mut output = String::new();
+
+    for (key, value) in root.iter().filter(|(_, value)| !value.is_object()) {
+        output.push_str(&format!(
+            "{} = {}\n",
+            key,
+            scalar(value).ok_or_else(invalid)?
+        ));
+    }
+
+    for (name, section) in root
+        .iter()
+        .filter_map(|(name, value)| Some((name, value.as_object()?)))
+    {
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output.push_str(&format!("[{}]\n", name));
+        for (key, value) in section {
+            output.push_str(&format!(
+                "{} = {}\n",
+                key,
+                scalar(value).ok_or_else(invalid)?
+            ));
+        }
+    }
+
+    Ok(output)
+}
+
+/// Parse a Java `.properties` document into a flat object. Keys are separated from
+/// values by `=`, `:` or whitespace, lines ending in `\` continue on the next line,
+/// `#` and `!` start comments, and `\t`, `\n`, `\uXXXX` and similar escapes are decoded.
+pub fn parse_properties(content: &str) -> Result<Value, ResourceError> {
+    let mut root = Map::new();
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        let mut logical = line.trim_start().to_string();
+        if logical.is_empty() || logical.starts_with('#') || logical.starts_with('!') {
+            continue;
+        }
+        while ends_with_continuation(&logical) {
+            logical.pop();
+            match lines.next() {
+                Some(next) => logical.push_str(next.trim_start()),
+                None => break,
+            }
+        }
+
+        let chars: Vec<char> = logical.chars().collect();
+        let mut end = 0;
+        while end < chars.len() && !matches!(chars[end], '=' | ':' | ' ' | '\t' | '\x0c') {
+            end += if chars[end] == '\\' { 2 } else { 1 };
+        }
+        let end = end.min(chars.len());
+
+        let mut start = end;
+        while start < chars.len() && matches!(chars[start], ' ' | '\t' | '\x0c') {
+            start += 1;
+        }
+        if start < chars.len() && matches!(chars[start], '=' | ':') {
+            start += 1;
+            while start < chars.len() && matches!(chars[start], ' ' | '\t' | '\x0c') {
+                start += 1;
+            }
+        }
+
+        let key: String = chars[..end].iter().collect();
+        let value: String = chars[start..].iter().collect();
+        root.insert(unescape(&key)?, Value::String(unescape(&value)?));
+    }
+
+    Ok(Value::Object(root))
+}
+
+/// Write a flat document as `.properties`.
+pub fn render_properties(document: &Value) -> Result<String, ResourceError> {
+    let invalid = || ResourceError::serialization("properties");
+    let root = document.as_object().ok_or_else(invalid)?;
+    let mut output = String::new();
+
+    for (key, value) in root {
+        let value = scalar(value).ok_or_else(invalid)?;
+        output.push_str(&format!(
+            "{}={}\n",
+            escape(key, true),
+            escape(&value, false)
+        ));
+    }
+
+    Ok(output)
+}
+
+/// Parse a dotenv document (`KEY=VALUE` lines) into a flat object.
+///
+/// Lines may start with `export `, `#` starts a comment line or, after whitespace, a
+/// trailing comment of an unquoted value. Single quoted values are literal; double
+/// quoted values may span lines and decode `\n`, `\t`, `\"` and `\\`.
+pub fn parse_dotenv(content: &str) -> Result<Value, ResourceError> {
+    let invalid = || ResourceError::deserialization("dotenv");
+    let mut root = Map::new();
+    let mut lines = content.lines();
+
+    while let Some(line) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(invalid());
+        }
+        let value = value.trim_start();
+
+        let value = if let Some(quoted) = value.strip_prefix('\'') {
+            let (value, _) = quoted.split_once('\'').ok_or_else(invalid)?;
+            value.to_string()
+        } else if let Some(quoted) = value.strip_prefix('"') {
+            let mut quoted = quoted.to_string();
+            loop {
+                if let Some(value) = closing_quote(&quoted).map(|end| &quoted[..end]) {
+                    break unescape_dotenv(value);
+                }
+                quoted.push('\n');
+                quoted.push_str(lines.next().ok_or_else(invalid)?);
+            }
+        } else {
+            let end = value.find(" #").or_else(|| value.find("\t#"));
+            value[..end.unwrap_or(value.len())].trim_end().to_string()
+        };
+
+        root.insert(key.to_string(), Value::String(value));
+    }
+
+    Ok(Value::Object(root))
+}
+
+/// Write a document as dotenv; nested objects are flattened with `__`.
+pub fn render_dotenv(document: &Value) -> Result<String, ResourceError> {
+    fn render(
+        object: &Map<String, Value>,
+        prefix: &str,
+        output: &mut String,
+    ) -> Result<(), ResourceError> {
+        for (key, value) in object {
+            let key = format!("{}{}", prefix, key);
+            match value {
+                Value::Object(nested) => render(nested, &format!("{}__", key), output)?,
+                value => {
+                    let value =
+                        scalar(value).ok_or_else(|| ResourceError::serialization("dotenv"))?;
+                    let value = value
+                        .replace('\\', "\\\\")
+                        .replace('"', "\\\"")
+                        .replace('\n', "\\n");
+                    output.push_str(&format!("{}=\"{}\"\n", key, value));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    let root = document
+        .as_object()
+        .ok_or_else(|| ResourceError::serialization("dotenv"))?;
+    let mut output = String::new();
+    render(root, "", &mut output)?;
+    Ok(output)
+}
+
+/// Byte index of the first `"` not escaped by a backslash.
+fn closing_quote(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in text.char_indices() {
+        match c {
+            '"' if !escaped => return Some(index),
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn unescape_dotenv(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => output.push('\n'),
+            Some('t') => output.push('\t'),
+            Some(c) => output.push(c),
+            None => output.push('\\'),
+        }
+    }
+
+    output
+}
+
+/// Textual form of a scalar, `None` for objects and arrays.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Null => Some(String::new()),
+        Value::Bool(_) | Value::Number(_) => Some(value.to_string()),
+        Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+fn ends_with_continuation(line: &str) -> bool {
+    line.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1
+}
+
+fn unescape(text: &str) -> Result<String, ResourceError> {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => output.push('\t'),
+            Some('n') => output.push('\n'),
+            Some('r') => output.push('\r'),
+            Some('f') => output.push('\x0c'),
+            Some('u') => {
+                let code: String = chars.by_ref().take(4).collect();
+                let c = u32::from_str_radix(&code, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| ResourceError::deserialization("properties"))?;
+                output.push(c);
+            }
+            Some(c) => output.push(c),
+            None => {}
+        }
+    }
+
+    Ok(output)
+}
+
+fn escape(text: &str, key: bool) -> String {
+    let mut output = String::with_capacity(text.len());
+
+    for (index, c) in text.chars().enumerate() {
+        match c {
+            '\\' => output.push_str("\\\\"),
+            '\t' => output.push_str("\\t"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\x0c' => output.push_str("\\f"),
+            ' ' if key || index == 0 => output.push_str("\\ "),
+            '=' | ':' | '#' | '!' if key || index == 0 => {
+                output.push('\\');
+                output.push(c);
+            }
+            c => output.push(c),
+        }
+    }
+
+    output
+}
+
+/// Deserialize `T` from a document whose scalars are strings, see the module docs.
+pub fn deserialize_strings<T: for<'a> Deserialize<'a>>(
+    document: Value,
+    format: &str,
+) -> Result<T, ResourceError> {
+    T::deserialize(Strings::new(document, false))
+        .map_err(|_| ResourceError::deserialization(format))
+}
+
+/// Deserialize `T` from a dotenv document, see [`parse_dotenv`].
+///
+/// Maps keep the variable names as written. Struct fields match variables regardless
+/// of case (`DATABASE_URL` fills `database_url`), and `__` separates nested structs:
+/// `SERVER__PORT` fills `port` of the `server` field.
+pub fn deserialize_env<T: for<'a> Deserialize<'a>>(document: Value) -> Result<T, ResourceError> {
+    T::deserialize(Strings::new(document, true))
+        .map_err(|_| ResourceError::deserialization("dotenv"))
+}
+
+/// Deserializer over a [`Value`] reading numbers and booleans from strings.
+pub(crate) struct Strings {
+    value: Value,
+    /// Whether struct fields are matched against environment style keys.
+    env_keys: bool,
+}
+
+impl Strings {
+    pub(crate) fn new(value: Value, env_keys: bool) -> Self {
+        Self { value, env_keys }
+    }
+
+    /// Regroup environment style keys by the struct's fields, see [`deserialize_env`].
+    fn env_fields(map: Map<String, Value>, fields: &[&str]) -> Map<String, Value> {
+        let field = |key: &str| {
+            fields
+                .iter()
+                .find(|field| field.eq_ignore_ascii_case(key))
+                .map(|field| field.to_string())
+        };
+        let mut grouped = Map::new();
+
+        for (key, value) in map {
+            if let Some(name) = field(&key) {
+                grouped.insert(name, value);
+                continue;
+            }
+            match key
+                .split_once("__")
+                .and_then(|(prefix, rest)| Some((field(prefix)?, rest)))
+            {
+                Some((name, rest)) => {
+                    let nested = grouped
+                        .entry(name)
+                        .or_insert_with(|| Value::Object(Map::new()));
+                    if let Value::Object(nested) = nested {
+                        nested.insert(rest.to_string(), value);
+                    }
+                }
+                None => {
+                    grouped.insert(key, value);
+                }
+            }
+        }
+
+        grouped
+    }
+}
+
+macro_rules! parse_from_string {
+    ($($method:ident => $visit:ident: $type:ty),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                match &self.value {
+                    Value::String(text) => match text.trim().parse::<$type>() {
+                        Ok(value) => visitor.$visit(value),
+                        Err(_) => Err(de::Error::invalid_type(
+                            de::Unexpected::Str(text),
+                            &stringify!($type),
+                        )),
+                    },
+                    _ => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Strings {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let env_keys = self.env_keys;
+        match self.value {
+            Value::Object(map) => {
+                let mut entries = MapDeserializer::new(
+                    map.into_iter().map(|(k, v)| (k, Strings::new(v, env_keys))),
+                );
+                let value = visitor.visit_map(&mut entries)?;
+                entries.end()?;
+                Ok(value)
+            }
+            Value::Array(items) => {
+                let mut items = SeqDeserializer::new(
+                    items.into_iter().map(|item| Strings::new(item, env_keys)),
+                );
+                let value = visitor.visit_seq(&mut items)?;
+                items.end()?;
+                Ok(value)
+            }
+            Value::String(text) => visitor.visit_string(text),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    parse_from_string! {
+        deserialize_bool => visit_bool: bool,
+        deserialize_i8 => visit_i8: i8,
+        deserialize_i16 => visit_i16: i16,
+        deserialize_i32 => visit_i32: i32,
+        deserialize_i64 => visit_i64: i64,
+        deserialize_u8 => visit_u8: u8,
+        deserialize_u16 => visit_u16: u16,
+        deserialize_u32 => visit_u32: u32,
+        deserialize_u64 => visit_u64: u64,
+        deserialize_f32 => visit_f32: f32,
+        deserialize_f64 => visit_f64: f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            // `key =` leaves an optional value unset
+            Value::Null => visitor.visit_none(),
+            Value::String(text) if text.is_empty() => visitor.visit_none(),
+            value => visitor.visit_some(Strings::new(value, self.env_keys)),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::String(text) => visitor.visit_enum(text.into_deserializer()),
+            value => value.deserialize_enum(name, variants, visitor),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Object(map) if self.env_keys => {
+                Strings::new(Value::Object(Self::env_fields(map, fields)), true)
+                    .deserialize_any(visitor)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct newtype_struct seq
+        tuple tuple_struct map identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, serde_json::Error> for Strings {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+    enum Level {
+        #[serde(rename = "debug")]
+        Debug,
+        #[serde(rename = "info")]
+        Info,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+    struct Server {
+        host: String,
+        port: u16,
+        tls: bool,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+    struct Settings {
+        level: Level,
+        server: Server,
+        retries: Option<u32>,
+    }
+
+    #[test]
+    fn test_ini_sections_into_structs() {
+        let content = "; legacy billing service\n\
+                       level = info\n\
+                       \n\
+                       [server]\n\
+                       host = \"billing.internal\"\n\
+                       port: 8443\n\
+                       # overridden below\n\
+                       tls = false\n\
+                       tls = true\n";
+
+        let document = parse_ini(content).unwrap();
+        let settings: Settings = deserialize_strings(document.clone(), "INI").unwrap();
+        assert_eq!(
+            settings,
+            Settings {
+                level: Level::Info,
+                server: Server {
+                    host: "billing.internal".to_string(),
+                    port: 8443,
+                    tls: true,
+                },
+                retries: None,
+            }
+        );
+
+        let rendered = render_ini(&serde_json::to_value(&settings).unwrap()).unwrap();
+        assert_eq!(parse_ini(&rendered).unwrap()["server"], document["server"]);
+        assert!(parse_ini("[server\nport = 1").is_err());
+        assert!(parse_ini("port").is_err());
+    }
+
+    #[test]
+    fn test_properties_escapes_and_continuations() {
+        let content = "# generated\n\
+                       ! also a comment\n\
+                       db.url = jdbc:postgresql://db:5432/app\n\
+                       db.pool:10\n\
+                       greeting Hello\\tWorld \\u00e9\n\
+                       path\\ with\\ spaces=a\\\\b\n\
+                       list = one, \\\n\
+                       \x20      two\n\
+                       empty\n";
+
+        let properties: HashMap<String, String> =
+            deserialize_strings(parse_properties(content).unwrap(), "properties").unwrap();
+        assert_eq!(properties["db.url"], "jdbc:postgresql://db:5432/app");
+        assert_eq!(properties["db.pool"], "10");
+        assert_eq!(properties["greeting"], "Hello\tWorld \u{e9}");
+        assert_eq!(properties["path with spaces"], "a\\b");
+        assert_eq!(properties["list"], "one, two");
+        assert_eq!(properties["empty"], "");
+
+        let pool: HashMap<String, u32> =
+            deserialize_strings(parse_properties("db.pool = 10").unwrap(), "properties").unwrap();
+        assert_eq!(pool["db.pool"], 10);
+
+        let document = serde_json::to_value(&properties).unwrap();
+        let rendered = render_properties(&document).unwrap();
+        assert_eq!(parse_properties(&rendered).unwrap(), document);
+    }
+
+    #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+    struct Database {
+        url: String,
+        pool: u32,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+    struct Env {
+        database: Database,
+        debug: bool,
+        motd: String,
+        api_key: Option<String>,
+    }
+
+    #[test]
+    fn test_dotenv_into_maps_and_structs() {
+        let content = "# local overrides\n\
+                       export DATABASE__URL=postgres://localhost/app # dev only\n\
+                       DATABASE__POOL = 4\n\
+                       DEBUG=true\n\
+                       MOTD=\"line one\n\
+                       line \\\"two\\\"\"\n\
+                       API_KEY='s3cr#t \\n'\n";
+
+        let document = parse_dotenv(content).unwrap();
+        let variables: HashMap<String, String> = deserialize_env(document.clone()).unwrap();
+        assert_eq!(variables["DATABASE__URL"], "postgres://localhost/app");
+        assert_eq!(variables["API_KEY"], "s3cr#t \\n");
+
+        let env: Env = deserialize_env(document).unwrap();
+        assert_eq!(
+            env,
+            Env {
+                database: Database {
+                    url: "postgres://localhost/app".to_string(),
+                    pool: 4,
+                },
+                debug: true,
+                motd: "line one\nline \"two\"".to_string(),
+                api_key: Some("s3cr#t \\n".to_string()),
+            }
+        );
+
+        let rendered = render_dotenv(&serde_json::to_value(&env).unwrap()).unwrap();
+        assert_eq!(
+            deserialize_env::<Env>(parse_dotenv(&rendered).unwrap()).unwrap(),
+            env
+        );
+        assert!(parse_dotenv("NOT AN ASSIGNMENT").is_err());
+        assert!(parse_dotenv("KEY=\"unterminated").is_err());
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::base::Durability;
+use crate::error::ResourceError;
+use crate::file_system::FileSystem;
+use crate::utilities::save_content_to_disk;
+use crate::write_behind;
+
+/// Name of the index file in a cache directory; the leading dot keeps it out of the
+/// file listings of every resource.
+pub(crate) const INDEX_FILE_NAME: &str = ".resourcely-index.json";
+
+/// Lock file held while an index file is rewritten, by this or another process.
+const LOCK_FILE_NAME: &str = ".resourcely-index.json.lock";
+
+/// How long a deferred update waits for the lock of another writer.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Age after which a lock file is considered left behind by a crashed writer.
+const ABANDONED_LOCK_AGE: Duration = Duration::from_secs(30);
+
+/// Serializes the read-modify-write cycles of index files within the process, so its
+/// writers do not poll the lock file of each other.
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// Index files parsed by one resource, by path, with the modification time and size
+/// they were read at. Kept by the resource's state, so it is dropped along with it.
+#[derive(Default)]
+pub(crate) struct ParsedIndexes(Mutex<BTreeMap<PathBuf, ParsedIndex>>);
+
+struct ParsedIndex {
+    modified: SystemTime,
+    size: u64,
+    index: Arc<Index>,
+}
+
+/// Current disk cache file of a resource, as recorded in the index of its cache
+/// directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct IndexEntry {
+    /// Name of the file within the cache directory.
+    pub file: String,
+    /// Timestamp embedded in the file name.
+    pub timestamp: SystemTime,
+    /// FNV-1a hash of the file content.
+    pub content_hash: u64,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+struct Index {
+    entries: BTreeMap<String, IndexEntry>,
+}
+
+/// Index of `directory`; empty when it is missing or unreadable, so lookups fall back
+/// to scanning the directory.
+///
+/// The parsed index is reused while the file keeps its modification time and size.
+fn read(fs: &dyn FileSystem, parsed: &ParsedIndexes, directory: &Path) -> Arc<Index> {
+    let path = directory.join(INDEX_FILE_NAME);
+    let (Ok(modified), Ok(size)) = (fs.modified(&path), fs.file_size(&path)) else {
+        return fs
+            .read(&path)
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .map(Arc::new)
+            .unwrap_or_default();
+    };
+
+    let mut parsed = parsed.0.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(cached) = parsed.get(&path) {
+        if cached.modified == modified && cached.size == size {
+            return Arc::clone(&cached.index);
+        }
+    }
+
+    let index: Arc<Index> = fs
+        .read(&path)
+        .ok()
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .map(Arc::new)
+        .unwrap_or_default();
+    parsed.insert(
+        path,
+        ParsedIndex {
+            modified,
+            size,
+            index: Arc::clone(&index),
+        },
+    );
+    index
+}
+
+/// Entry of the resource whose cache files are named `name` in the index of
+/// `directory`.
+pub(crate) fn lookup(
+    fs: &dyn FileSystem,
+    parsed: &ParsedIndexes,
+    directory: &Path,
+    name: &str,
+) -> Option<IndexEntry> {
+    read(fs, parsed, directory).entries.get(name).cloned()
+}
+
+/// Lock file of a cache directory, removed when dropped.
+struct IndexLock<'a> {
+    fs: &'a dyn FileSystem,
+    path: PathBuf,
+}
+
+impl<'a> IndexLock<'a> {
+    /// Create the lock file, `None` while another writer holds it; a lock file older
+    /// than [`ABANDONED_LOCK_AGE`] is taken over.
+    fn try_acquire(
+        fs: &'a dyn FileSystem,
+        directory: &Path,
+    ) -> Result<Option<Self>, ResourceError> {
+        let path = directory.join(LOCK_FILE_NAME);
+        fs.create_dir_all(directory).map_err(ResourceError::Io)?;
+
+        for _ in 0..2 {
+            match fs.create_new(&path, std::process::id().to_string().as_bytes()) {
+                Ok(()) => return Ok(Some(Self { fs, path })),
+                Err(error) if error.kind() == ErrorKind::AlreadyExists => {}
+                Err(error) => return Err(ResourceError::Io(error)),
+            }
+
+            let abandoned = fs
+                .modified(&path)
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .is_some_and(|age| age > ABANDONED_LOCK_AGE);
+            if !abandoned {
+                break;
+            }
+            let _ = fs.remove_file(&path);
+        }
+
+        Ok(None)
+    }
+
+    /// Like [`try_acquire`](Self::try_acquire), waiting up to [`LOCK_TIMEOUT`] for the
+    /// other writer. Blocks the thread, so it only runs on the writer thread.
+    fn acquire(fs: &'a dyn FileSystem, directory: &Path) -> Result<Self, ResourceError> {
+        let started = Instant::now();
+
+        loop {
+            if let Some(lock) = Self::try_acquire(fs, directory)? {
+                return Ok(lock);
+            }
+
+            if started.elapsed() > LOCK_TIMEOUT {
+                return Err(ResourceError::Io(std::io::Error::new(
+                    ErrorKind::TimedOut,
+                    format!(
+                        "{} is held by another writer",
+                        directory.join(LOCK_FILE_NAME).display()
+                    ),
+                )));
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+}
+
+impl Drop for IndexLock<'_> {
+    fn drop(&mut self) {
+        let _ = self.fs.remove_file(&self.path);
+    }
+}
+
+/// Record `entry` for `name`, or remove the entry with `None`. The index file is
+/// replaced through a temporary file, so readers never see it half written, while
+/// holding the lock file of the directory, so concurrent writers in other processes
+/// do not lose each other's entries.
+///
+/// While another process holds the lock, the update is handed to the disk writer
+/// thread, which waits for it, instead of blocking the caller (possibly an async
+/// task). An entry never replaces a newer one, so a late update cannot roll the
+/// index back; until it lands, lookups may find the previous file.
+pub(crate) fn update(
+    fs: &Arc<dyn FileSystem>,
+    parsed: &Arc<ParsedIndexes>,
+    directory: &Path,
+    name: &str,
+    entry: Option<IndexEntry>,
+    durability: Durability,
+    mode: Option<u32>,
+) -> Result<(), ResourceError> {
+    {
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(_lock) = IndexLock::try_acquire(&**fs, directory)? {
+            return write(&**fs, parsed, directory, name, entry, durability, mode);
+        }
+    }
+
+    let fs = Arc::clone(fs);
+    let parsed = Arc::clone(parsed);
+    let directory = directory.to_path_buf();
+    let name = name.to_string();
+    write_behind::submit(move || {
+        // an index that cannot be updated is left as it is
+        if let Ok(_lock) = IndexLock::acquire(&*fs, &directory) {
+            let _guard = INDEX_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+            let _ = write(&*fs, &parsed, &directory, &name, entry, durability, mode);
+        }
+    });
+
+    Ok(())
+}
+
+/// Rewrite the index of `directory` with the lock file held.
+fn write(
+    fs: &dyn FileSystem,
+    parsed: &ParsedIndexes,
+    directory: &Path,
+    name: &str,
+    entry: Option<IndexEntry>,
+    durability: Durability,
+    mode: Option<u32>,
+) -> Result<(), ResourceError> {
+    let mut index = Index::clone(&read(fs, parsed, directory));
+    match entry {
+        Some(entry) => {
+            let newer = index
+                .entries
+                .get(name)
+                .is_some_and(|current| current.timestamp > entry.timestamp);
+            if !newer {
+                index.entries.insert(name.to_string(), entry);
+            }
+        }
+        None => {
+            index.entries.remove(name);
+        }
+    };
+
+    let content =
+        serde_json::to_string(&index).map_err(|_| ResourceError::serialization("JSON"))?;
+    let durability = match durability {
+        Durability::Synced => Durability::Synced,
+        _ => Durability::Atomic,
+    };
+    save_content_to_disk(
+        fs,
+        &content,
+        &directory.join(INDEX_FILE_NAME),
+        None,
+        None,
+        durability,
+        mode,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_system::MemoryFileSystem;
+    use std::time::Duration;
+
+    #[test]
+    fn test_entries_are_updated_independently() {
+        let memory = Arc::new(MemoryFileSystem::new());
+        let fs: Arc<dyn FileSystem> = memory.clone();
+        let parsed = Arc::new(ParsedIndexes::default());
+        let directory = Path::new("/cache");
+        let entry = |file: &str, secs: u64| IndexEntry {
+            file: file.to_string(),
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+            content_hash: secs,
+        };
+        let update = |name: &str, entry: Option<IndexEntry>| {
+            update(
+                &fs,
+                &parsed,
+                directory,
+                name,
+                entry,
+                Durability::Direct,
+                None,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(lookup(&*fs, &parsed, directory, "flags"), None);
+        update("flags", Some(entry("flags-1.json", 1)));
+        update("limits", Some(entry("limits-1.json", 1)));
+        update("flags", Some(entry("flags-2.json", 2)));
+        assert_eq!(
+            lookup(&*fs, &parsed, directory, "flags"),
+            Some(entry("flags-2.json", 2))
+        );
+
+        update("flags", None);
+        assert_eq!(lookup(&*fs, &parsed, directory, "flags"), None);
+        assert_eq!(
+            lookup(&*fs, &parsed, directory, "limits"),
+            Some(entry("limits-1.json", 1))
+        );
+
+        // an unreadable index is treated as empty
+        memory.insert(directory.join(INDEX_FILE_NAME), "{");
+        assert_eq!(lookup(&*fs, &parsed, directory, "limits"), None);
+    }
+
+    #[test]
+    fn test_updates_take_the_directory_lock() {
+        let memory = Arc::new(MemoryFileSystem::new());
+        let fs: Arc<dyn FileSystem> = memory.clone();
+        let parsed = Arc::new(ParsedIndexes::default());
+        let directory = Path::new("/cache");
+        let lock = directory.join(LOCK_FILE_NAME);
+        let entry = IndexEntry {
+            file: "flags-1.json".to_string(),
+            timestamp: SystemTime::UNIX_EPOCH,
+            content_hash: 1,
+        };
+
+        // left behind by a writer that crashed
+        memory.insert(&lock, "4242");
+        memory.set_modified(&lock, SystemTime::now() - ABANDONED_LOCK_AGE * 2);
+
+        update(
+            &fs,
+            &parsed,
+            directory,
+            "flags",
+            Some(entry.clone()),
+            Durability::Direct,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            lookup(&*fs, &parsed, directory, "flags"),
+            Some(entry.clone())
+        );
+        assert_eq!(memory.contents(&lock), None);
+
+        // another process rewrote the index since it was parsed
+        memory.insert(directory.join(INDEX_FILE_NAME), r#"{"entries":{}}"#);
+        assert_eq!(lookup(&*fs, &parsed, directory, "flags"), None);
+
+        // held by a live writer: the writer thread waits for it instead of the caller
+        let newer = IndexEntry {
+            file: "flags-2.json".to_string(),
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(2),
+            content_hash: 2,
+        };
+        let update = |entry: &IndexEntry| {
+            update(
+                &fs,
+                &parsed,
+                directory,
+                "flags",
+                Some(entry.clone()),
+                Durability::Direct,
+                None,
+            )
+            .unwrap()
+        };
+        memory.insert(&lock, "4242");
+        update(&newer);
+        assert_eq!(lookup(&*fs, &parsed, directory, "flags"), None);
+        memory.remove_file(&lock).unwrap();
+        write_behind::flush();
+        assert_eq!(
+            lookup(&*fs, &parsed, directory, "flags"),
+            Some(newer.clone())
+        );
+
+        // a late update does not roll the index back
+        update(&entry);
+        assert_eq!(lookup(&*fs, &parsed, directory, "flags"), Some(newer));
+    }
+}
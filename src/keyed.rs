@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use futures_util::future::join_all;
+use percent_encoding::utf8_percent_encode;
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    base::{ResourceProps, ResourceState, FILE_NAME_PART},
+    error::ResourceError,
+    remote::{DefaultRemoteResourceReader, DEFAULT_USER_AGENT},
+    traits::{DataResult, ResourceFileType, ResourceReader},
+};
+
+type TtlFn<K> = dyn Fn(&K) -> Option<Duration> + Send + Sync;
+
+type PropsFn<V> = dyn Fn(ResourceProps<V>) -> ResourceProps<V> + Send + Sync;
+
+struct KeyedEntry<V> {
+    reader: Arc<DefaultRemoteResourceReader<V>>,
+    /// Tick of the last access, for evicting the least recently used key.
+    used: u64,
+}
+
+struct Entries<K, V> {
+    readers: HashMap<K, KeyedEntry<V>>,
+    tick: u64,
+}
+
+/// Read-through cache of a family of documents addressed by key, e.g. `users/{id}`.
+///
+/// [`get`](Self::get) fetches `{base_url}/{key}` on first use and caches every key
+/// apart, in memory and on disk (`{name}.key-{key}-<timestamp>.json`), like a
+/// [remote resource](crate::state_manager::Remote) of its own. Keys are dropped from
+/// memory least recently used first beyond [`with_max_entries`](Self::with_max_entries);
+/// their disk cache stays and is read again when the key is asked for.
+pub struct KeyedResource<K, V> {
+    name: String,
+    file_type: ResourceFileType,
+    base_url: Url,
+    storage_directory: PathBuf,
+    ttl: Option<Duration>,
+    ttl_for: Option<Box<TtlFn<K>>>,
+    max_entries: Option<usize>,
+    client: reqwest::Client,
+    configure: Option<Box<PropsFn<V>>>,
+    entries: Mutex<Entries<K, V>>,
+}
+
+impl<K, V> KeyedResource<K, V>
+where
+    K: Eq + Hash + Clone + Display,
+    V: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    pub fn new(
+        name: impl Into<String>,
+        file_type: ResourceFileType,
+        base_url: Url,
+        storage_directory: impl Into<PathBuf>,
+    ) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(DEFAULT_USER_AGENT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            name: name.into(),
+            file_type,
+            base_url,
+            storage_directory: storage_directory.into(),
+            ttl: None,
+            ttl_for: None,
+            max_entries: None,
+            client,
+            configure: None,
+            entries: Mutex::new(Entries {
+                readers: HashMap::new(),
+                tick: 0,
+            }),
+        }
+    }
+
+    /// Keep every key fresh for `ttl`; without one cached keys never expire.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Decide the TTL per key, e.g. shorter for volatile entries; `None` falls back to
+    /// [`with_ttl`](Self::with_ttl).
+    pub fn with_ttl_for(
+        mut self,
+        ttl_for: impl Fn(&K) -> Option<Duration> + Send + Sync + 'static,
+    ) -> Self {
+        self.ttl_for = Some(Box::new(ttl_for));
+        self
+    }
+
+    /// Keep at most `max_entries` keys in memory.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries.max(1));
+        self
+    }
+
+    /// Perform the requests through the given HTTP client.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Adjust the properties of every key's resource, e.g. its file system or
+    /// namespace.
+    pub fn with_props(
+        mut self,
+        configure: impl Fn(ResourceProps<V>) -> ResourceProps<V> + Send + Sync + 'static,
+    ) -> Self {
+        self.configure = Some(Box::new(configure));
+        self
+    }
+
+    /// URL of the document of `key`: the key as last path segment of the base URL.
+    pub fn url_for(&self, key: &K) -> Result<Url, ResourceError> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .map_err(|_| ResourceError::InvalidUrl(self.base_url.to_string()))?
+            .pop_if_empty()
+            .push(&key.to_string());
+
+        Ok(url)
+    }
+
+    /// Data of `key`, from the cache while fresh and fetched otherwise; stale data is
+    /// served when fetching fails.
+    pub async fn get(&self, key: &K) -> Result<DataResult<Arc<V>>, ResourceError> {
+        self.reader(key)?.get_data_or_error(true).await
+    }
+
+    /// Data of several keys, fetched concurrently.
+    pub async fn get_many(
+        &self,
+        keys: &[K],
+    ) -> HashMap<K, Result<DataResult<Arc<V>>, ResourceError>> {
+        join_all(
+            keys.iter()
+                .map(|key| async move { (key.clone(), self.get(key).await) }),
+        )
+        .await
+        .into_iter()
+        .collect()
+    }
+
+    /// Mark the cached data of `key` stale, so the next read fetches it again.
+    pub fn invalidate(&self, key: &K) -> Result<(), ResourceError> {
+        self.reader(key)?.mark_as_stale()
+    }
+
+    /// Drop `key` from memory, returning whether it was held; its disk cache stays.
+    pub fn evict(&self, key: &K) -> Result<bool, ResourceError> {
+        Ok(self
+            .entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .readers
+            .remove(key)
+            .is_some())
+    }
+
+    /// Number of keys held in memory.
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .map(|entries| entries.readers.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reader of `key`, created on first use; evicts the least recently used key
+    /// beyond the entry limit.
+    fn reader(&self, key: &K) -> Result<Arc<DefaultRemoteResourceReader<V>>, ResourceError> {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries.tick += 1;
+        let tick = entries.tick;
+
+        if let Some(entry) = entries.readers.get_mut(key) {
+            entry.used = tick;
+            return Ok(Arc::clone(&entry.reader));
+        }
+
+        let reader = Arc::new(DefaultRemoteResourceReader::with_client(
+            ResourceState::new(self.props_for(key)?),
+            self.client.clone(),
+        ));
+        entries.readers.insert(
+            key.clone(),
+            KeyedEntry {
+                reader: Arc::clone(&reader),
+                used: tick,
+            },
+        );
+
+        if let Some(max_entries) = self.max_entries {
+            while entries.readers.len() > max_entries {
+                let oldest = entries
+                    .readers
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.used)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    entries.readers.remove(&oldest);
+                }
+            }
+        }
+
+        Ok(reader)
+    }
+
+    fn props_for(&self, key: &K) -> Result<ResourceProps<V>, ResourceError> {
+        let ttl = self
+            .ttl_for
+            .as_ref()
+            .and_then(|ttl_for| ttl_for(key))
+            .or(self.ttl);
+        let props = ResourceProps::new(
+            format!(
+                "{}.key-{}",
+                self.name,
+                utf8_percent_encode(&key.to_string(), FILE_NAME_PART)
+            ),
+            self.file_type.clone(),
+            self.url_for(key)?,
+            &self.storage_directory,
+            ttl,
+        );
+
+        Ok(match &self.configure {
+            Some(configure) => configure(props),
+            None => props,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_system::{FileSystem, MemoryFileSystem};
+    use crate::test_support::block_on;
+    use crate::testing::StubServer;
+    use std::path::Path;
+
+    #[test]
+    fn test_keys_are_cached_apart_with_their_own_ttl() {
+        let server = StubServer::start().unwrap();
+        server.fixture("/users/1", "{\"name\": \"ada\"}");
+        server.fixture("/users/2", "{\"name\": \"grace\"}");
+        server.fixture("/users/a%20b", "{\"name\": \"spaced\"}");
+
+        let file_system = Arc::new(MemoryFileSystem::new());
+        let disk = file_system.clone();
+        let users = KeyedResource::<String, HashMap<String, String>>::new(
+            "users",
+            ResourceFileType::Json,
+            server.url("/users/"),
+            "/cache",
+        )
+        .with_ttl(Duration::from_secs(60))
+        .with_ttl_for(|key| (key == "2").then_some(Duration::ZERO))
+        .with_max_entries(2)
+        .with_props(move |props| props.with_file_system(disk.clone()));
+
+        let key = |key: &str| key.to_string();
+        let name = |result: Result<DataResult<Arc<HashMap<String, String>>>, ResourceError>| {
+            match result {
+                Ok(DataResult::Fresh(data)) => data["name"].clone(),
+                _ => panic!("expected fresh data"),
+            }
+        };
+
+        assert_eq!(name(block_on(users.get(&key("1")))), "ada");
+        assert_eq!(name(block_on(users.get(&key("1")))), "ada");
+        server.assert_fetches("/users/1", 1);
+
+        let many = block_on(users.get_many(&[key("2"), key("a b")]));
+        assert_eq!(
+            name(many.into_iter().find(|(k, _)| k == "2").unwrap().1),
+            "grace"
+        );
+        assert_eq!(users.len(), 2);
+        let files = file_system.list_files(Path::new("/cache")).unwrap();
+        assert!(files.iter().any(|file| file
+            .to_string_lossy()
+            .starts_with("/cache/users.key-a%20b-")));
+
+        // key 1 was dropped from memory and comes back from disk
+        assert_eq!(name(block_on(users.get(&key("1")))), "ada");
+        server.assert_fetches("/users/1", 1);
+        server.assert_fetches("/users/a%20b", 1);
+
+        // key 2 expires at once
+        block_on(users.get(&key("2"))).unwrap();
+        server.assert_fetches("/users/2", 2);
+
+        users.invalidate(&key("1")).unwrap();
+        block_on(users.get(&key("1"))).unwrap();
+        server.assert_fetches("/users/1", 2);
+        assert!(users.evict(&key("1")).unwrap());
+    }
+}
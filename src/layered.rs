@@ -0,0 +1,259 @@
+use std::sync::Arc;
+
+use futures_util::future::join_all;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{
+    base::ResourceState,
+    error::ResourceError,
+    traits::{DataResult, ResourceReader},
+};
+
+/// How arrays are combined when a layer overrides another.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// The upper layer's array replaces the lower one.
+    #[default]
+    Replace,
+    /// The upper layer's items are appended to the lower array.
+    Append,
+    /// Items are deep-merged position by position; extra items are kept.
+    ByIndex,
+}
+
+/// Deep-merge `overlay` into `base`: objects are merged key by key, arrays according
+/// to `arrays`, and any other value of the overlay replaces the base.
+fn deep_merge(base: &mut Value, overlay: Value, arrays: ArrayMerge) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => deep_merge(existing, value, arrays),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(base), Value::Array(overlay)) if arrays != ArrayMerge::Replace => {
+            if arrays == ArrayMerge::Append {
+                base.extend(overlay);
+                return;
+            }
+
+            for (index, value) in overlay.into_iter().enumerate() {
+                match base.get_mut(index) {
+                    Some(existing) => deep_merge(existing, value, arrays),
+                    None => base.push(value),
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Reader producing one effective configuration from layered documents, e.g. a
+/// remote document over a local file over embedded defaults.
+///
+/// Layers are read concurrently (stale data allowed) and deep-merged in the order
+/// they were added, later layers winning. A layer that cannot be read is left out;
+/// the result is then stale, like a result containing stale layers, and is not cached.
+pub struct LayeredResourceReader<T> {
+    state: Arc<ResourceState<T>>,
+    defaults: Value,
+    layers: Vec<Arc<dyn ResourceReader<Value> + Send + Sync>>,
+    arrays: ArrayMerge,
+}
+
+impl<T> LayeredResourceReader<T> {
+    pub fn new(state: impl Into<Arc<ResourceState<T>>>) -> Self {
+        Self {
+            state: state.into(),
+            defaults: Value::Null,
+            layers: Vec::new(),
+            arrays: ArrayMerge::default(),
+        }
+    }
+
+    /// Embedded defaults, the bottom layer.
+    pub fn with_defaults(mut self, defaults: &impl Serialize) -> Self {
+        self.defaults = serde_json::to_value(defaults).unwrap_or(Value::Null);
+        self
+    }
+
+    /// Add a layer above the previously added ones.
+    pub fn with_layer<R>(mut self, reader: Arc<R>) -> Self
+    where
+        R: ResourceReader<Value> + Send + Sync + 'static,
+    {
+        self.layers.push(reader);
+        self
+    }
+
+    /// Set how arrays are merged (they are replaced by default).
+    pub fn with_array_merge(mut self, arrays: ArrayMerge) -> Self {
+        self.arrays = arrays;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ResourceReader<T> for LayeredResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
+        &self.state
+    }
+
+    async fn get_data_or_error(
+        &self,
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        let result = self
+            .load_data(allow_stale)
+            .await
+            .and_then(|data| self.get_state().limit_staleness(data));
+
+        if let Err(error) = &result {
+            self.get_state().record_error(error);
+        }
+
+        result
+    }
+}
+
+impl<T> LayeredResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    async fn load_data(&self, allow_stale: bool) -> Result<DataResult<Arc<T>>, ResourceError> {
+        if !self.get_state().is_marked_stale()? {
+            if let Some((data, true, _)) = self.get_state().get_internal_data()? {
+                self.get_state().record_hit();
+                return Ok(DataResult::Fresh(data));
+            }
+        }
+
+        self.get_state().record_miss();
+
+        let loaded = join_all(
+            self.layers
+                .iter()
+                .map(|layer| layer.get_data_or_error(true)),
+        )
+        .await;
+
+        let mut merged = self.defaults.clone();
+        let mut first_error = None;
+        let mut fresh = true;
+        for result in loaded {
+            match result {
+                Ok(DataResult::Fresh(layer)) => {
+                    deep_merge(&mut merged, (*layer).clone(), self.arrays)
+                }
+                Ok(DataResult::Stale(layer)) => {
+                    fresh = false;
+                    deep_merge(&mut merged, (*layer).clone(), self.arrays);
+                }
+                // a deliberately empty layer contributes nothing
+                Ok(DataResult::Absent) => {}
+                Err(error) => {
+                    fresh = false;
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+
+        if !fresh && !allow_stale {
+            return Err(first_error.unwrap_or(ResourceError::UnableToFreshData));
+        }
+
+        let data = serde_json::from_value::<T>(merged)
+            .map(Arc::new)
+            .map_err(|_| ResourceError::deserialization("JSON"))?;
+
+        if !fresh {
+            if let Some(error) = &first_error {
+                self.get_state().record_error(error);
+            }
+            return Ok(DataResult::Stale(data));
+        }
+
+        self.get_state().set_internal_cache(Arc::clone(&data))?;
+
+        Ok(DataResult::Fresh(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::ResourceProps;
+    use crate::test_support::block_on;
+    use crate::testing::MockResourceReader;
+    use crate::traits::ResourceFileType;
+    use reqwest::Url;
+    use serde_json::json;
+
+    #[test]
+    fn test_deep_merge_array_modes() {
+        let base = json!({ "db": { "host": "localhost", "port": 5432 }, "tags": [{ "a": 1 }, 2] });
+        let overlay = json!({ "db": { "host": "db.internal" }, "tags": [{ "b": 3 }] });
+
+        let mut replaced = base.clone();
+        deep_merge(&mut replaced, overlay.clone(), ArrayMerge::Replace);
+        assert_eq!(
+            replaced,
+            json!({ "db": { "host": "db.internal", "port": 5432 }, "tags": [{ "b": 3 }] })
+        );
+
+        let mut appended = base.clone();
+        deep_merge(&mut appended, overlay.clone(), ArrayMerge::Append);
+        assert_eq!(appended["tags"], json!([{ "a": 1 }, 2, { "b": 3 }]));
+
+        let mut by_index = base;
+        deep_merge(&mut by_index, overlay, ArrayMerge::ByIndex);
+        assert_eq!(by_index["tags"], json!([{ "a": 1, "b": 3 }, 2]));
+    }
+
+    #[test]
+    fn test_layers_override_defaults_and_survive_failures() {
+        let local = Arc::new(MockResourceReader::<Value>::new("local"));
+        local.push_fresh(json!({ "port": 8080, "features": { "beta": false } }));
+        let remote = Arc::new(MockResourceReader::<Value>::new("remote"));
+        remote
+            .push_fresh(json!({ "features": { "beta": true } }))
+            .push_error(ResourceError::Offline)
+            .push_error(ResourceError::Offline);
+
+        let reader = LayeredResourceReader::<Value>::new(ResourceState::new(ResourceProps::new(
+            "config",
+            ResourceFileType::Json,
+            Url::parse("layered:config").unwrap(),
+            std::env::temp_dir().join("resourcely-layered-unused"),
+            None,
+        )))
+        .with_defaults(&json!({ "port": 80, "host": "0.0.0.0" }))
+        .with_layer(Arc::clone(&local))
+        .with_layer(Arc::clone(&remote));
+
+        let expected = json!({ "port": 8080, "host": "0.0.0.0", "features": { "beta": true } });
+        assert!(matches!(
+            block_on(reader.get_data_or_error(false)),
+            Ok(DataResult::Fresh(data)) if *data == expected
+        ));
+
+        // the remote layer is left out while it fails
+        reader.mark_as_stale().unwrap();
+        assert!(matches!(
+            block_on(reader.get_data_or_error(false)),
+            Err(ResourceError::Offline)
+        ));
+        assert!(matches!(
+            block_on(reader.get_data_or_error(true)),
+            Ok(DataResult::Stale(data)) if data["features"]["beta"] == json!(false)
+        ));
+    }
+}
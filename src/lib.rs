@@ -1,15 +1,101 @@
+// lets `#[derive(Resource)]` output (`::resourcely::...`) compile inside this crate
+#[cfg(test)]
+extern crate self as resourcely;
+
+#[cfg(feature = "aws")]
+mod aws;
 mod base;
+mod boxed;
+mod builder;
+mod clock;
+mod command;
+mod composite;
+mod config;
+#[cfg(feature = "consul")]
+mod consul;
+#[cfg(feature = "parquet")]
+mod dataset;
+mod edit;
+#[cfg(feature = "encryption")]
+mod encryption;
 mod error;
+mod events;
+mod ext;
+mod file_system;
+mod formats;
+mod index;
+mod keyed;
+mod patch;
 mod traits;
 mod utilities;
 
+mod env;
+mod layered;
 mod local;
+mod macros;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod registry;
 mod remote;
+mod schedule;
+mod template;
+#[cfg(test)]
+mod test_support;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+#[cfg(feature = "vault")]
+mod vault;
+mod view;
+mod write_behind;
 
+pub use boxed::BoxedReader;
+pub use builder::{Profile, Resource, ResourceBuilder};
+pub use config::{global_config, set_global_config, ResourcelyConfig};
+pub use error::{CacheLookup, ResourceError, SourceFailures};
+pub use ext::{MapErr, OrDefaultValue, ResourceReaderExt, WithFallback, WithTimeout};
+pub use registry::{Janitor, RefreshOutcome, Registry, ReloadHandle, ReloadReport, Snapshot};
+#[cfg(feature = "derive")]
+pub use resourcely_derive::Resource;
 pub use traits::*;
 
+#[doc(hidden)]
+pub mod __macro_support {
+    pub use crate::macros::parse_duration;
+}
+
 pub mod state_manager {
+    #[cfg(feature = "aws")]
+    pub use crate::aws::{AwsResourceReader as Aws, AwsSecretSource};
     pub use crate::base::*;
+    pub use crate::clock::{Clock, ManualClock, SystemClock};
+    pub use crate::command::CommandResourceReader as Command;
+    pub use crate::composite::{CompositeParts, CompositeResource};
+    #[cfg(feature = "consul")]
+    pub use crate::consul::ConsulResourceReader as Consul;
+    #[cfg(feature = "parquet")]
+    pub use crate::dataset::DatasetResource;
+    pub use crate::edit::Edit;
+    #[cfg(feature = "encryption")]
+    pub use crate::encryption::{EncryptionKey, EnvKeyProvider, KeyProvider, StaticKeys};
+    pub use crate::env::EnvResourceReader as Env;
+    pub use crate::events::*;
+    pub use crate::file_system::{FileSystem, MemoryFileSystem, RealFileSystem};
+    pub use crate::keyed::KeyedResource;
+    pub use crate::layered::{ArrayMerge, LayeredResourceReader as Layered};
     pub use crate::local::DefaultLocalResourceReader as Local;
+    #[cfg(feature = "mqtt")]
+    pub use crate::mqtt::MqttResourceReader as Mqtt;
+    pub use crate::patch::{apply_patch, diff, PatchOperation};
     pub use crate::remote::DefaultRemoteResourceReader as Remote;
+    pub use crate::remote::{
+        is_global_offline, set_global_offline, AutoOfflinePolicy, FetchMetadata, LookupPolicy,
+        MirrorStats, OriginHealth, ParseBackoff, DEFAULT_USER_AGENT,
+    };
+    pub use crate::schedule::CronSchedule;
+    pub use crate::template::TemplatedResourceReader as Templated;
+    #[cfg(feature = "vault")]
+    pub use crate::vault::{KvVersion, VaultAuth, VaultResourceReader as Vault};
+    pub use crate::view::View;
+    pub use reqwest::cookie::Jar as CookieJar;
+    pub use reqwest::Url;
 }
@@ -1,4 +1,6 @@
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use serde::{de::DeserializeOwned, Serialize};
 
@@ -6,16 +8,26 @@ use crate::{
     base::ResourceState,
     error::ResourceError,
     traits::{DataResult, ResourceReader},
-    utilities::{get_files_starts_with, parse_file},
+    utilities::{get_files_starts_with, parse_timestamp_from_path},
 };
 
 pub struct DefaultLocalResourceReader<T> {
-    state: ResourceState<T>,
+    state: Arc<ResourceState<T>>,
 }
 
 impl<T> DefaultLocalResourceReader<T> {
-    pub fn new(state: ResourceState<T>) -> Self {
-        Self { state }
+    pub fn new(state: impl Into<Arc<ResourceState<T>>>) -> Self {
+        Self {
+            state: state.into(),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for DefaultLocalResourceReader<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DefaultLocalResourceReader")
+            .field("state", &self.state)
+            .finish()
     }
 }
 
@@ -24,7 +36,7 @@ impl<T> ResourceReader<T> for DefaultLocalResourceReader<T>
 where
     T: Send + Sync + DeserializeOwned + Serialize + Default,
 {
-    fn get_state(&self) -> &ResourceState<T> {
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
         &self.state
     }
 
@@ -32,6 +44,68 @@ where
         &self,
         allow_stale: bool,
     ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        let result = self
+            .load_data(allow_stale)
+            .and_then(|data| self.get_state().limit_staleness(data));
+
+        if let Err(error) = &result {
+            self.get_state().record_error(error);
+        }
+
+        result
+    }
+}
+
+impl<T> DefaultLocalResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    /// Whether the file holds this resource: its exact name, e.g. `app.json`, or a
+    /// timestamped one. Other resources sharing the prefix (`app-beta.json`), metadata
+    /// sidecars and the cache index are not.
+    fn is_resource_file(&self, file_path: &Path) -> bool {
+        let state = self.get_state();
+        let Some(name) = file_path.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+
+        let exact = name
+            .strip_prefix(state.get_file_name())
+            .and_then(|rest| rest.strip_prefix('.'))
+            .is_some_and(|extension| state.get_file_type().extensions().contains(&extension));
+
+        exact
+            || parse_timestamp_from_path(
+                file_path,
+                state.get_file_name(),
+                state.get_file_name_format(),
+            )
+            .is_ok()
+    }
+
+    /// Files of this resource ordered newest first, by the timestamp embedded in the
+    /// name or, for the file without one, by modification time.
+    fn newest_first(&self, file_paths: Vec<PathBuf>) -> Vec<PathBuf> {
+        let state = self.get_state();
+        let timestamp = |file_path: &Path| {
+            parse_timestamp_from_path(
+                file_path,
+                state.get_file_name(),
+                state.get_file_name_format(),
+            )
+            .or_else(|_| state.file_system().modified(file_path))
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+        };
+
+        let mut file_paths: Vec<PathBuf> = file_paths
+            .into_iter()
+            .filter(|file_path| self.is_resource_file(file_path))
+            .collect();
+        file_paths.sort_by_cached_key(|file_path| std::cmp::Reverse(timestamp(file_path)));
+        file_paths
+    }
+
+    fn load_data(&self, allow_stale: bool) -> Result<DataResult<Arc<T>>, ResourceError> {
         let mut stale_internal_data: Option<Arc<T>> = None;
 
         if !self.get_state().is_marked_stale()? {
@@ -42,6 +116,7 @@ where
             if let Some((data, fresh, _)) = self.get_state().get_internal_data()? {
                 if fresh {
                     // timestamp based
+                    self.get_state().record_hit();
                     return Ok(DataResult::Fresh(data));
                 }
                 stale_internal_data = Some(data);
@@ -52,19 +127,39 @@ where
         // 2. Data member is either stale or not available; refreshing //
         /////////////////////////////////////////////////////////////////
 
-        let fresh_data_from_drive = match get_files_starts_with(
+        self.get_state().record_miss();
+
+        let disk_files = match get_files_starts_with(
+            self.get_state().file_system(),
             self.get_state().get_file_name(),
             self.get_state().get_storage_directory(),
-        )
-        .first()
-        {
-            Some(file_path) => match parse_file::<T>(file_path, self.get_state().get_file_type()) {
-                Ok(data) => Some(Arc::new(data)),
-                Err(_) => None,
-            },
-            None => None,
+        ) {
+            Ok(disk_files) => disk_files,
+            Err(error) => {
+                return match stale_internal_data {
+                    Some(data) if allow_stale => Ok(DataResult::Stale(data)),
+                    _ => Err(error),
+                };
+            }
         };
 
+        let mut fresh_data_from_drive = None;
+        for file_path in self.newest_first(disk_files) {
+            let Ok(content) = self.get_state().file_system().read_to_string(&file_path) else {
+                continue;
+            };
+
+            match self.get_state().parse(&content) {
+                Ok(data) => {
+                    fresh_data_from_drive = Some(Arc::new(data));
+                    break;
+                }
+                // a hand-edited file rejected by the schema mode is not skipped silently
+                Err(error @ ResourceError::UnknownFields(_)) => return Err(error),
+                Err(_) => continue,
+            }
+        }
+
         if fresh_data_from_drive.is_none() && allow_stale {
             if let Some(data) = stale_internal_data {
                 return Ok(DataResult::Stale(data));
@@ -80,3 +175,210 @@ where
         Ok(DataResult::Fresh(fresh_data))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::ResourceProps;
+    use crate::file_system::MemoryFileSystem;
+    use crate::ResourceFileType;
+    use reqwest::Url;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    #[test]
+    fn test_newest_local_file_is_read() {
+        let file_system = Arc::new(MemoryFileSystem::new());
+        file_system.insert("/config/app-2000.json", "[2]");
+        file_system.insert("/config/app-3000.json", "[3]");
+        file_system.insert("/config/app.json", "[1]");
+        file_system.set_modified(
+            Path::new("/config/app.json"),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(2500),
+        );
+
+        let reader = DefaultLocalResourceReader::<Vec<u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "app",
+                ResourceFileType::Json,
+                Url::parse("file:///config/app.json").unwrap(),
+                "/config",
+                None,
+            )
+            .with_file_system(file_system.clone()),
+        ));
+        assert!(matches!(reader.load_data(false), Ok(DataResult::Fresh(data)) if *data == [3]));
+
+        // a hand-edited file without a timestamp counts by its modification time
+        file_system.set_modified(
+            Path::new("/config/app.json"),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(4000),
+        );
+        reader.mark_as_stale().unwrap();
+        assert!(matches!(reader.load_data(false), Ok(DataResult::Fresh(data)) if *data == [1]));
+    }
+
+    #[test]
+    fn test_other_resources_and_sidecars_are_not_read() {
+        let file_system = Arc::new(MemoryFileSystem::new());
+        file_system.insert("/config/app-2000.json", r#"{"app": 2}"#);
+        file_system.insert("/config/app-beta.json", r#"{"beta": 1}"#);
+        file_system.insert("/config/app-2000.json.meta.json", r#"{"fetched_at": 3000}"#);
+        for name in ["/config/app-beta.json", "/config/app-2000.json.meta.json"] {
+            file_system.set_modified(
+                Path::new(name),
+                SystemTime::UNIX_EPOCH + Duration::from_secs(5000),
+            );
+        }
+
+        let reader = DefaultLocalResourceReader::<HashMap<String, u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "app",
+                ResourceFileType::Json,
+                Url::parse("file:///config/app.json").unwrap(),
+                "/config",
+                None,
+            )
+            .with_file_system(file_system.clone()),
+        ));
+        assert!(matches!(
+            reader.load_data(false),
+            Ok(DataResult::Fresh(data)) if *data == HashMap::from([("app".to_string(), 2)])
+        ));
+    }
+
+    #[test]
+    fn test_strict_schema_rejects_unknown_keys_in_local_files() {
+        #[derive(serde::Deserialize, Serialize, Default)]
+        struct Limits {
+            rate: u32,
+        }
+
+        let file_system = Arc::new(MemoryFileSystem::new());
+        file_system.insert("/config/limits-1000.json", r#"{"rate": 5}"#);
+        file_system.insert("/config/limits-2000.json", r#"{"rate": 10, "rtae": 20}"#);
+        let reader = DefaultLocalResourceReader::<Limits>::new(ResourceState::new(
+            ResourceProps::new(
+                "limits",
+                ResourceFileType::Json,
+                Url::parse("file:///config/limits.json").unwrap(),
+                "/config",
+                None,
+            )
+            .with_file_system(file_system.clone())
+            .with_schema_mode(crate::base::SchemaMode::Strict),
+        ));
+
+        assert!(matches!(
+            reader.load_data(false),
+            Err(ResourceError::UnknownFields(fields)) if fields == ["rtae"]
+        ));
+
+        file_system.insert("/config/limits-3000.json", r#"{"rate": 30}"#);
+        assert!(matches!(reader.load_data(false), Ok(DataResult::Fresh(data)) if data.rate == 30));
+    }
+
+    #[test]
+    fn test_local_files_report_schema_warnings() {
+        #[derive(serde::Deserialize, Serialize, Default)]
+        struct Limits {
+            rate: u32,
+        }
+
+        let file_system = Arc::new(MemoryFileSystem::new());
+        file_system.insert("/config/limits.json", r#"{"rate": 10, "rtae": 20}"#);
+        let reader = DefaultLocalResourceReader::<Limits>::new(ResourceState::new(
+            ResourceProps::new(
+                "limits",
+                ResourceFileType::Json,
+                Url::parse("file:///config/limits.json").unwrap(),
+                "/config",
+                None,
+            )
+            .with_file_system(file_system)
+            .with_schema_mode(crate::base::SchemaMode::Warn),
+        ));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let (data, warnings) = runtime
+            .block_on(reader.get_data_with_warnings(false))
+            .unwrap();
+        assert!(matches!(data, DataResult::Fresh(data) if data.rate == 10));
+        assert_eq!(
+            warnings,
+            vec![crate::base::SchemaWarning::UnknownField("rtae".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_generation_tracks_newer_data() {
+        let file_system = Arc::new(MemoryFileSystem::new());
+        file_system.insert("/config/app-1000.json", "[1]");
+        let reader = DefaultLocalResourceReader::<Vec<u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "app",
+                ResourceFileType::Json,
+                Url::parse("file:///config/app.json").unwrap(),
+                "/config",
+                None,
+            )
+            .with_file_system(file_system.clone()),
+        ));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let (_, generation) = runtime
+            .block_on(reader.get_data_with_generation(false))
+            .unwrap();
+        assert!(reader.is_current(generation).unwrap());
+        let (_, again) = runtime
+            .block_on(reader.get_data_with_generation(false))
+            .unwrap();
+        assert_eq!(again, generation);
+
+        file_system.insert("/config/app-2000.json", "[2]");
+        reader.mark_as_stale().unwrap();
+        let (data, newer) = runtime
+            .block_on(reader.get_data_with_generation(false))
+            .unwrap();
+        assert!(matches!(data, DataResult::Fresh(data) if *data == [2]));
+        assert!(newer > generation);
+        assert!(!reader.is_current(generation).unwrap());
+        assert!(reader.is_current(newer).unwrap());
+    }
+
+    #[test]
+    fn test_readers_share_one_state() {
+        let server = crate::testing::StubServer::start().unwrap();
+        server.fixture("/flags.json", r#"{"enabled": 1}"#);
+        let state = Arc::new(ResourceState::new(
+            ResourceProps::<std::collections::HashMap<String, u32>>::new(
+                "flags",
+                ResourceFileType::Json,
+                server.url("/flags.json"),
+                "/cache",
+                Some(Duration::from_secs(60)),
+            )
+            .with_file_system(Arc::new(MemoryFileSystem::new())),
+        ));
+        let remote = crate::remote::DefaultRemoteResourceReader::new(Arc::clone(&state));
+        let local = DefaultLocalResourceReader::new(remote.shared_state());
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        assert!(runtime.block_on(remote.get_data_or_error(false)).is_ok());
+        // the local reader serves what the remote one fetched
+        let data = runtime.block_on(local.get_data_or_error(false));
+        assert!(matches!(data, Ok(DataResult::Fresh(data)) if data["enabled"] == 1));
+        assert!(Arc::ptr_eq(&state, &local.shared_state()));
+
+        local.mark_as_stale().unwrap();
+        assert!(remote.get_state().is_marked_stale().unwrap());
+        server.assert_fetches("/flags.json", 1);
+    }
+}
@@ -0,0 +1,160 @@
+//! Support of the [`resource!`](crate::resource) macro.
+
+use std::time::Duration;
+
+/// Parse the `ttl` option of [`resource!`](crate::resource): a number with a `ms`, `s`,
+/// `m`, `h` or `d` suffix, or plain seconds.
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount.parse().ok()?;
+
+    match unit {
+        "ms" => Some(Duration::from_millis(amount)),
+        "" | "s" => Some(Duration::from_secs(amount)),
+        "m" => Some(Duration::from_secs(amount.checked_mul(60)?)),
+        "h" => Some(Duration::from_secs(amount.checked_mul(3_600)?)),
+        "d" => Some(Duration::from_secs(amount.checked_mul(86_400)?)),
+        _ => None,
+    }
+}
+
+/// Declare lazily initialized global readers.
+///
+/// ```ignore
+/// resource! {
+///     pub FLAGS: FeatureFlags = remote("https://example.com/flags.json", ttl = 300s, cache = "/var/cache/app");
+///     LIMITS: Limits = local("config/limits.json", ttl = 1m);
+/// }
+///
+/// let flags = FLAGS.get_data_or_default_with(StalePolicy::IfError).await;
+/// ```
+///
+/// `remote(url, ...)` yields a [`Remote`](crate::state_manager::Remote) reader and
+/// `local(path, ...)` a [`Local`](crate::state_manager::Local) one. Options:
+/// `ttl` (e.g. `500ms`, `300s`, `5m`, `1h`), `cache` (cache directory, remote only),
+/// `file_name` (defaults to the lowercased static name, remote only) and `namespace`.
+///
+/// The reader is built on first access; an invalid declaration panics there.
+#[macro_export]
+macro_rules! resource {
+    ($(
+        $(#[$meta:meta])*
+        $vis:vis $name:ident : $ty:ty = $kind:ident ( $source:expr $(, $key:ident = $value:tt)* $(,)? )
+    );* $(;)?) => {$(
+        $(#[$meta])*
+        $vis static $name: ::std::sync::LazyLock<$crate::__resource_reader!($kind, $ty)> =
+            ::std::sync::LazyLock::new(|| {
+                let builder = $crate::__resource_source!(
+                    $kind,
+                    $crate::ResourceBuilder::<$ty>::new()
+                        .file_name(stringify!($name).to_lowercase()),
+                    $source
+                );
+                $( let builder = $crate::__resource_option!(builder, $key = $value); )*
+
+                $crate::__resource_build!($kind, builder)
+                    .unwrap_or_else(|e| panic!("Invalid resource {}: {}", stringify!($name), e))
+            });
+    )*};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __resource_reader {
+    (remote, $ty:ty) => { $crate::state_manager::Remote<$ty> };
+    (local, $ty:ty) => { $crate::state_manager::Local<$ty> };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __resource_source {
+    (remote, $builder:expr, $url:expr) => {
+        $builder.url($url)
+    };
+    (local, $builder:expr, $path:expr) => {{
+        let path = ::std::path::Path::new($path);
+        $builder
+            .file_name(
+                path.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            )
+            .cache_directory(path.parent().unwrap_or(::std::path::Path::new(".")))
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __resource_option {
+    ($builder:ident, ttl = $value:tt) => {
+        $builder.timeout(
+            $crate::__macro_support::parse_duration(stringify!($value))
+                .unwrap_or_else(|| panic!("Invalid ttl '{}'", stringify!($value))),
+        )
+    };
+    ($builder:ident, cache = $value:tt) => {
+        $builder.cache_directory($value)
+    };
+    ($builder:ident, file_name = $value:tt) => {
+        $builder.file_name($value)
+    };
+    ($builder:ident, namespace = $value:tt) => {
+        $builder.namespace($value)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __resource_build {
+    (remote, $builder:ident) => {
+        $builder.build_remote()
+    };
+    (local, $builder:ident) => {
+        $builder.build_local()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::{ResourceReader, StalePolicy};
+    use std::collections::HashMap;
+
+    crate::resource! {
+        INLINE_FLAGS: HashMap<String, bool> = remote(
+            "data:application/json,{\"enabled\":true}",
+            ttl = 300s,
+            cache = (std::env::temp_dir().join("resourcely-macros")),
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("300s"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("300"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("5m"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("2h"), Some(Duration::from_secs(7_200)));
+        assert_eq!(parse_duration("1d"), Some(Duration::from_secs(86_400)));
+        assert_eq!(parse_duration("5 minutes"), None);
+        assert_eq!(parse_duration("s"), None);
+    }
+
+    #[test]
+    fn test_declared_resource_is_built_lazily() {
+        assert_eq!(INLINE_FLAGS.get_state().get_file_name(), "inline_flags");
+
+        let data = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(INLINE_FLAGS.get_data_or_default_with(StalePolicy::Never));
+        assert_eq!(data.get("enabled"), Some(&true));
+
+        let _ = std::fs::remove_dir_all(std::env::temp_dir().join("resourcely-macros"));
+    }
+}
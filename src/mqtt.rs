@@ -0,0 +1,577 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::Mutex,
+};
+
+use crate::{
+    base::{CacheScope, ResourceState},
+    error::ResourceError,
+    traits::{DataResult, ResourceReader},
+};
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const PUBACK: u8 = 0x40;
+const SUBSCRIBE: u8 = 0x82;
+const SUBACK: u8 = 0x90;
+const PINGREQ: u8 = 0xC0;
+const PINGRESP: u8 = 0xD0;
+const DISCONNECT: u8 = 0xE0;
+
+/// Reader backed by an MQTT topic (MQTT 3.1.1 over plain TCP).
+///
+/// The resource URL is the broker address (e.g. `mqtt://broker:1883`); other schemes,
+/// `mqtts://` included, are rejected as TLS is not supported. A read that misses the
+/// cache subscribes to the topic and takes the retained message as the value; the
+/// connection is kept for the next miss while the broker's keep alive allows it.
+/// [`watch`](Self::watch) stays subscribed so every message published to the topic is
+/// stored and published to the resource subscribers (see
+/// [`ResourceState::subscribe`]).
+pub struct MqttResourceReader<T> {
+    state: Arc<ResourceState<T>>,
+    topic: String,
+    client_id: String,
+    credentials: Option<(String, String)>,
+    cleartext_credentials: bool,
+    keep_alive: Duration,
+    retained_wait: Duration,
+    /// Connection of the last read, reused by the next one.
+    idle_session: Mutex<Option<Session>>,
+}
+
+impl<T> MqttResourceReader<T> {
+    pub fn new(state: impl Into<Arc<ResourceState<T>>>, topic: impl Into<String>) -> Self {
+        Self {
+            state: state.into(),
+            topic: topic.into(),
+            client_id: format!("resourcely-{}", std::process::id()),
+            credentials: None,
+            cleartext_credentials: false,
+            keep_alive: Duration::from_secs(60),
+            retained_wait: Duration::from_secs(5),
+            idle_session: Mutex::new(None),
+        }
+    }
+
+    /// Identify as `client_id` to the broker (defaults to `resourcely-<pid>`); it must be
+    /// unique per broker, as a second connection with the same id drops the first.
+    pub fn with_client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = client_id.into();
+        self
+    }
+
+    /// Authenticate with a user name and password.
+    ///
+    /// The connection is not encrypted, so reads fail with [`ResourceError::Mqtt`]
+    /// unless sending them in cleartext is allowed with
+    /// [`allow_cleartext_credentials`](Self::allow_cleartext_credentials).
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Send the credentials over the unencrypted connection, e.g. to a broker on a
+    /// trusted local network.
+    pub fn allow_cleartext_credentials(mut self) -> Self {
+        self.cleartext_credentials = true;
+        self
+    }
+
+    /// Interval of keep alive pings while watching (defaults to 60 seconds).
+    pub fn with_keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// How long a read waits for the retained message (defaults to 5 seconds).
+    pub fn with_retained_wait(mut self, wait: Duration) -> Self {
+        self.retained_wait = wait;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ResourceReader<T> for MqttResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
+        &self.state
+    }
+
+    async fn get_data_or_error(
+        &self,
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        let fetch = async { self.fetch().await.map(|data| (data, None)) };
+
+        self.get_state()
+            .load_or_refresh(allow_stale, CacheScope::MemoryAndDisk, fetch)
+            .await
+    }
+}
+
+impl<T> MqttResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    /// Stay subscribed to the topic and store every message until an error occurs
+    /// (including the broker closing the connection).
+    ///
+    /// Meant to run in a spawned task next to the readers of the resource; restart it
+    /// after an error, possibly with a delay.
+    pub async fn watch(&self) -> Result<(), ResourceError> {
+        let result = self.receive_updates().await;
+        if let Err(error) = &result {
+            self.get_state().record_error(error);
+        }
+        result
+    }
+
+    async fn receive_updates(&self) -> Result<(), ResourceError> {
+        let mut session = self.subscribe().await?;
+
+        loop {
+            let payload = match tokio::time::timeout(self.keep_alive, session.next_message()).await
+            {
+                Ok(payload) => payload?,
+                Err(_) => {
+                    session.send(PINGREQ, &[]).await?;
+                    continue;
+                }
+            };
+
+            // an empty retained message clears the topic; keep the last value
+            if payload.is_empty() {
+                continue;
+            }
+
+            let data = self.parse(&payload).map(Arc::new)?;
+            self.get_state().save_to_disk(&data)?;
+            self.get_state().set_internal_cache(data)?;
+        }
+    }
+
+    /// Close the connection kept between reads, if any.
+    pub async fn disconnect(&self) {
+        if let Some(session) = self.idle_session.lock().await.take() {
+            session.disconnect().await;
+        }
+    }
+
+    /// Connect and subscribe to the topic.
+    async fn subscribe(&self) -> Result<Session, ResourceError> {
+        let url = self.get_state().get_url();
+        if url.scheme() != "mqtt" {
+            return Err(ResourceError::Mqtt(format!(
+                "unsupported scheme '{}', only mqtt:// is supported",
+                url.scheme()
+            )));
+        }
+        if self.credentials.is_some() && !self.cleartext_credentials {
+            return Err(ResourceError::Mqtt(
+                "refusing to send credentials over an unencrypted connection".to_string(),
+            ));
+        }
+
+        let host = url
+            .host_str()
+            .ok_or_else(|| ResourceError::Mqtt(format!("invalid broker address '{}'", url)))?;
+        let stream = TcpStream::connect((host, url.port().unwrap_or(1883)))
+            .await
+            .map_err(ResourceError::Io)?;
+        let mut session = Session {
+            stream,
+            last_sent: Instant::now(),
+        };
+
+        let mut flags = 0x02; // clean session
+        let mut payload = encode_string(&self.client_id);
+        if let Some((username, password)) = &self.credentials {
+            flags |= 0xC0;
+            payload.extend(encode_string(username));
+            payload.extend(encode_string(password));
+        }
+
+        let mut connect = encode_string("MQTT");
+        connect.extend([4, flags]);
+        connect.extend((self.keep_alive.as_secs().min(u64::from(u16::MAX)) as u16).to_be_bytes());
+        connect.extend(payload);
+        session.send(CONNECT, &connect).await?;
+
+        match session.receive().await? {
+            (CONNACK, body) if body.get(1) == Some(&0) => {}
+            (CONNACK, body) => {
+                return Err(ResourceError::Mqtt(format!(
+                    "connection refused with code {}",
+                    body.get(1).copied().unwrap_or_default()
+                )))
+            }
+            (kind, _) => return Err(unexpected(kind)),
+        }
+
+        self.send_subscribe(&mut session).await?;
+        Ok(session)
+    }
+
+    /// Subscribe to the topic on a connected session; subscribing again makes the
+    /// broker resend the retained message.
+    async fn send_subscribe(&self, session: &mut Session) -> Result<(), ResourceError> {
+        // packet id 1, QoS 0
+        let mut subscribe = vec![0, 1];
+        subscribe.extend(encode_string(&self.topic));
+        subscribe.push(0);
+        session.send(SUBSCRIBE, &subscribe).await?;
+
+        loop {
+            match session.receive().await? {
+                (SUBACK, body) if body.get(2) != Some(&0x80) => return Ok(()),
+                (SUBACK, _) => {
+                    return Err(ResourceError::Mqtt(format!(
+                        "subscription to '{}' refused",
+                        self.topic
+                    )))
+                }
+                // live messages of the previous subscription, superseded by the retained one
+                (kind, _) if kind & 0xF0 == PUBLISH || kind == PINGRESP => continue,
+                (kind, _) => return Err(unexpected(kind)),
+            }
+        }
+    }
+
+    /// The retained message of the topic, through the idle session when the broker
+    /// still keeps it open.
+    async fn fetch(&self) -> Result<Arc<T>, ResourceError> {
+        let mut idle_session = self.idle_session.lock().await;
+
+        let reused = match idle_session.take() {
+            // the broker drops a client silent for 1.5 times the keep alive
+            Some(mut session) if session.last_sent.elapsed() < self.keep_alive => {
+                match self.send_subscribe(&mut session).await {
+                    Ok(()) => Some(session),
+                    Err(_) => None,
+                }
+            }
+            Some(session) => {
+                session.disconnect().await;
+                None
+            }
+            None => None,
+        };
+        let mut session = match reused {
+            Some(session) => session,
+            None => self.subscribe().await?,
+        };
+
+        let payload = match tokio::time::timeout(self.retained_wait, session.next_message()).await {
+            Ok(Ok(payload)) => payload,
+            Ok(Err(error)) => return Err(error),
+            Err(_) => {
+                session.disconnect().await;
+                return Err(ResourceError::Mqtt(format!(
+                    "no retained message on '{}'",
+                    self.topic
+                )));
+            }
+        };
+        *idle_session = Some(session);
+
+        self.parse(&payload).map(Arc::new)
+    }
+
+    fn parse(&self, payload: &[u8]) -> Result<T, ResourceError> {
+        let content = std::str::from_utf8(payload)
+            .map_err(|_| ResourceError::Mqtt(format!("non UTF-8 message on '{}'", self.topic)))?;
+        self.get_state().parse(content)
+    }
+}
+
+/// Connection to the broker.
+struct Session {
+    stream: TcpStream,
+    /// Moment of the last packet sent, which the broker's keep alive is measured from.
+    last_sent: Instant,
+}
+
+impl Session {
+    async fn send(&mut self, kind: u8, body: &[u8]) -> Result<(), ResourceError> {
+        let mut packet = vec![kind];
+        packet.extend(encode_length(body.len()));
+        packet.extend_from_slice(body);
+        self.stream
+            .write_all(&packet)
+            .await
+            .map_err(ResourceError::Io)?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+
+    /// Close the connection cleanly; errors are ignored as it is dropped anyway.
+    async fn disconnect(mut self) {
+        let _ = self.send(DISCONNECT, &[]).await;
+        let _ = self.stream.shutdown().await;
+    }
+
+    /// The next packet: its type (with flags) and body.
+    async fn receive(&mut self) -> Result<(u8, Vec<u8>), ResourceError> {
+        let closed = |error: std::io::Error| match error.kind() {
+            std::io::ErrorKind::UnexpectedEof => {
+                ResourceError::Mqtt("connection closed by the broker".to_string())
+            }
+            _ => ResourceError::Io(error),
+        };
+
+        let kind = self.stream.read_u8().await.map_err(closed)?;
+        let mut length = 0;
+        for shift in (0..28).step_by(7) {
+            let byte = self.stream.read_u8().await.map_err(closed)?;
+            length |= usize::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+
+        let mut body = vec![0; length];
+        self.stream.read_exact(&mut body).await.map_err(closed)?;
+        Ok((kind, body))
+    }
+
+    /// Payload of the next published message, acknowledging it when required.
+    async fn next_message(&mut self) -> Result<Vec<u8>, ResourceError> {
+        loop {
+            let (kind, body) = self.receive().await?;
+            match kind & 0xF0 {
+                PUBLISH => {
+                    let malformed = || ResourceError::Mqtt("malformed PUBLISH packet".to_string());
+                    let topic_length = usize::from(u16::from_be_bytes([
+                        *body.first().ok_or_else(malformed)?,
+                        *body.get(1).ok_or_else(malformed)?,
+                    ]));
+                    let mut offset = 2 + topic_length;
+
+                    // QoS 1 and 2 messages carry a packet id, acknowledged for QoS 1
+                    if kind & 0x06 != 0 {
+                        let id = body.get(offset..offset + 2).ok_or_else(malformed)?.to_vec();
+                        if kind & 0x06 == 0x02 {
+                            self.send(PUBACK, &id).await?;
+                        }
+                        offset += 2;
+                    }
+
+                    return Ok(body.get(offset..).ok_or_else(malformed)?.to_vec());
+                }
+                PINGRESP => continue,
+                _ => return Err(unexpected(kind)),
+            }
+        }
+    }
+}
+
+fn unexpected(kind: u8) -> ResourceError {
+    ResourceError::Mqtt(format!("unexpected packet type {:#04x}", kind))
+}
+
+/// Remaining length of a packet as an MQTT variable byte integer.
+fn encode_length(mut length: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        bytes.push(byte);
+        if length == 0 {
+            return bytes;
+        }
+    }
+}
+
+fn encode_string(text: &str) -> Vec<u8> {
+    let mut bytes = (text.len() as u16).to_be_bytes().to_vec();
+    bytes.extend_from_slice(text.as_bytes());
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::ResourceProps;
+    use crate::test_support::{block_on, test_directory};
+    use crate::traits::{ResourceFileType, StalePolicy};
+    use reqwest::Url;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_encode_length() {
+        assert_eq!(encode_length(0), vec![0x00]);
+        assert_eq!(encode_length(127), vec![0x7F]);
+        assert_eq!(encode_length(128), vec![0x80, 0x01]);
+        assert_eq!(encode_length(16_383), vec![0xFF, 0x7F]);
+        assert_eq!(encode_length(2_097_152), vec![0x80, 0x80, 0x80, 0x01]);
+    }
+
+    fn read_packet(stream: &mut std::net::TcpStream) -> (u8, Vec<u8>) {
+        let mut header = [0; 2];
+        stream.read_exact(&mut header).unwrap();
+        let mut body = vec![0; usize::from(header[1])];
+        stream.read_exact(&mut body).unwrap();
+        (header[0], body)
+    }
+
+    fn write_publish(stream: &mut std::net::TcpStream, kind: u8, message: &str) {
+        let mut body = encode_string("devices/config");
+        body.extend_from_slice(message.as_bytes());
+        stream.write_all(&[kind, body.len() as u8]).unwrap();
+        stream.write_all(&body).unwrap();
+    }
+
+    /// Broker accepting a client per session, sending the session's messages on
+    /// `devices/config` and hanging up; returns the CONNECT packets.
+    fn broker(sessions: Vec<Vec<&'static str>>) -> (Url, std::thread::JoinHandle<Vec<Vec<u8>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("mqtt://{}", listener.local_addr().unwrap())).unwrap();
+
+        let handle = std::thread::spawn(move || {
+            let packet = read_packet;
+            let mut connects = Vec::new();
+
+            for messages in sessions {
+                let (mut stream, _) = listener.accept().unwrap();
+                let (kind, connect) = packet(&mut stream);
+                assert_eq!(kind, CONNECT);
+                connects.push(connect);
+                stream.write_all(&[CONNACK, 2, 0, 0]).unwrap();
+
+                let (kind, subscribe) = packet(&mut stream);
+                assert_eq!(kind, SUBSCRIBE);
+                assert_eq!(&subscribe[4..subscribe.len() - 1], b"devices/config");
+                stream.write_all(&[SUBACK, 3, 0, 1, 0]).unwrap();
+
+                for (index, message) in messages.iter().enumerate() {
+                    // the first message is the retained one
+                    let kind = if index == 0 { PUBLISH | 0x01 } else { PUBLISH };
+                    write_publish(&mut stream, kind, message);
+                }
+            }
+
+            connects
+        });
+
+        (url, handle)
+    }
+
+    fn reader(url: Url) -> MqttResourceReader<Vec<u32>> {
+        let directory = test_directory("mqtt");
+        let _ = std::fs::remove_dir_all(&directory);
+        let state = ResourceState::new(ResourceProps::new(
+            "config",
+            ResourceFileType::Json,
+            url,
+            directory,
+            Some(Duration::from_secs(60)),
+        ));
+
+        MqttResourceReader::new(state, "devices/config")
+            .with_client_id("gateway-7")
+            .with_credentials("device", "secret")
+            .allow_cleartext_credentials()
+    }
+
+    #[test]
+    fn test_retained_message_and_watch() {
+        let (url, broker_thread) = broker(vec![vec!["[1, 2]"], vec!["[1, 2]", "[3]"]]);
+        let reader = reader(url);
+        assert_eq!(
+            block_on(reader.get_data_or_none_with(StalePolicy::Never)),
+            Some(Arc::new(vec![1, 2]))
+        );
+
+        // the broker hangs up after the second message
+        let Err(ResourceError::Mqtt(reason)) = block_on(reader.watch()) else {
+            panic!("expected the watch to end with the connection");
+        };
+        assert_eq!(reason, "connection closed by the broker");
+        assert_eq!(
+            block_on(reader.get_data_or_none_with(StalePolicy::Never)),
+            Some(Arc::new(vec![3]))
+        );
+
+        let connects = broker_thread.join().unwrap();
+        assert_eq!(&connects[0][..7], b"\0\x04MQTT\x04");
+        assert_eq!(connects[0][7], 0xC2);
+        assert!(connects[0].ends_with(b"\0\x06secret"));
+    }
+
+    #[test]
+    fn test_reads_reuse_the_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("mqtt://{}", listener.local_addr().unwrap())).unwrap();
+        let broker_thread = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            assert_eq!(read_packet(&mut stream).0, CONNECT);
+            stream.write_all(&[CONNACK, 2, 0, 0]).unwrap();
+
+            for retained in ["[1]", "[2]"] {
+                assert_eq!(read_packet(&mut stream).0, SUBSCRIBE);
+                stream.write_all(&[SUBACK, 3, 0, 1, 0]).unwrap();
+                write_publish(&mut stream, PUBLISH | 0x01, retained);
+            }
+
+            read_packet(&mut stream).0
+        });
+
+        let reader = reader(url);
+        // the connection belongs to the runtime it was opened on
+        block_on(async {
+            assert_eq!(
+                reader.get_data_or_none_with(StalePolicy::Never).await,
+                Some(Arc::new(vec![1]))
+            );
+            reader.mark_as_stale().unwrap();
+            assert_eq!(
+                reader.get_data_or_none_with(StalePolicy::Never).await,
+                Some(Arc::new(vec![2]))
+            );
+            reader.disconnect().await;
+        });
+
+        assert_eq!(broker_thread.join().unwrap(), DISCONNECT);
+    }
+
+    #[test]
+    fn test_rejects_tls_schemes_and_cleartext_credentials() {
+        let tls = reader(Url::parse("mqtts://127.0.0.1:8883").unwrap());
+        assert!(matches!(
+            block_on(tls.get_data_or_error(false)),
+            Err(ResourceError::Mqtt(reason)) if reason.contains("'mqtts'")
+        ));
+
+        let state = ResourceState::new(ResourceProps::new(
+            "config",
+            ResourceFileType::Json,
+            Url::parse("mqtt://127.0.0.1:1883").unwrap(),
+            std::env::temp_dir(),
+            None,
+        ));
+        let cleartext = MqttResourceReader::<Vec<u32>>::new(state, "devices/config")
+            .with_credentials("device", "secret");
+        assert!(matches!(
+            block_on(cleartext.get_data_or_error(false)),
+            Err(ResourceError::Mqtt(reason)) if reason.contains("unencrypted")
+        ));
+    }
+}
@@ -0,0 +1,334 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::ResourceError;
+
+/// A single JSON Patch (RFC 6902) operation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/// Apply the operations in order; the document is left untouched when any fails.
+pub fn apply_patch(
+    document: &Value,
+    operations: &[PatchOperation],
+) -> Result<Value, ResourceError> {
+    let mut patched = document.clone();
+
+    for operation in operations {
+        match operation {
+            PatchOperation::Add { path, value } => add(&mut patched, path, value.clone())?,
+            PatchOperation::Remove { path } => {
+                remove(&mut patched, path)?;
+            }
+            PatchOperation::Replace { path, value } => {
+                *pointer_mut(&mut patched, path)? = value.clone();
+            }
+            PatchOperation::Move { from, path } => {
+                if path.starts_with(&format!("{}/", from)) {
+                    return Err(invalid(format!("cannot move '{}' into itself", from)));
+                }
+                let value = remove(&mut patched, from)?;
+                add(&mut patched, path, value)?;
+            }
+            PatchOperation::Copy { from, path } => {
+                let value = pointer(&patched, from)?.clone();
+                add(&mut patched, path, value)?;
+            }
+            PatchOperation::Test { path, value } => {
+                if pointer(&patched, path)? != value {
+                    return Err(invalid(format!("test failed at '{}'", path)));
+                }
+            }
+        }
+    }
+
+    Ok(patched)
+}
+
+/// Operations turning `old` into `new`, e.g. to log what changed in a refreshed
+/// document. Applying them to `old` with [`apply_patch`] yields `new`.
+pub fn diff(old: &Value, new: &Value) -> Vec<PatchOperation> {
+    let mut operations = Vec::new();
+    diff_at(String::new(), old, new, &mut operations);
+    operations
+}
+
+fn diff_at(path: String, old: &Value, new: &Value, operations: &mut Vec<PatchOperation>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                let child = format!("{}/{}", path, escape(key));
+                match new.get(key) {
+                    Some(new_value) => diff_at(child, old_value, new_value, operations),
+                    None => operations.push(PatchOperation::Remove { path: child }),
+                }
+            }
+            for (key, new_value) in new {
+                if !old.contains_key(key) {
+                    operations.push(PatchOperation::Add {
+                        path: format!("{}/{}", path, escape(key)),
+                        value: new_value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for (index, (old_value, new_value)) in old.iter().zip(new).enumerate() {
+                diff_at(
+                    format!("{}/{}", path, index),
+                    old_value,
+                    new_value,
+                    operations,
+                );
+            }
+            // surplus items are removed from the end so the indices stay valid
+            for index in (new.len()..old.len()).rev() {
+                operations.push(PatchOperation::Remove {
+                    path: format!("{}/{}", path, index),
+                });
+            }
+            for (index, value) in new.iter().enumerate().skip(old.len()) {
+                operations.push(PatchOperation::Add {
+                    path: format!("{}/{}", path, index),
+                    value: value.clone(),
+                });
+            }
+        }
+        (old, new) if old != new => operations.push(PatchOperation::Replace {
+            path,
+            value: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Escape a key as a JSON Pointer (RFC 6901) reference token.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn invalid(reason: String) -> ResourceError {
+    ResourceError::InvalidPatch(reason)
+}
+
+/// Split a JSON Pointer (RFC 6901) into its unescaped reference tokens.
+fn tokens(path: &str) -> Result<Vec<String>, ResourceError> {
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let rest = path
+        .strip_prefix('/')
+        .ok_or_else(|| invalid(format!("invalid JSON pointer '{}'", path)))?;
+
+    Ok(rest
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn array_index(token: &str, len: usize, path: &str) -> Result<usize, ResourceError> {
+    let valid = !token.is_empty()
+        && token.bytes().all(|byte| byte.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+
+    token
+        .parse::<usize>()
+        .ok()
+        .filter(|index| valid && *index < len)
+        .ok_or_else(|| invalid(format!("invalid array index in '{}'", path)))
+}
+
+fn pointer<'a>(document: &'a Value, path: &str) -> Result<&'a Value, ResourceError> {
+    tokens(path)?
+        .iter()
+        .try_fold(document, |value, token| match value {
+            Value::Object(map) => map.get(token),
+            Value::Array(items) => array_index(token, items.len(), path)
+                .ok()
+                .map(|index| &items[index]),
+            _ => None,
+        })
+        .ok_or_else(|| invalid(format!("path '{}' does not exist", path)))
+}
+
+fn pointer_mut<'a>(document: &'a mut Value, path: &str) -> Result<&'a mut Value, ResourceError> {
+    let mut value = document;
+
+    for token in tokens(path)? {
+        value = match value {
+            Value::Object(map) => map.get_mut(&token),
+            Value::Array(items) => {
+                let index = array_index(&token, items.len(), path)?;
+                items.get_mut(index)
+            }
+            _ => None,
+        }
+        .ok_or_else(|| invalid(format!("path '{}' does not exist", path)))?;
+    }
+
+    Ok(value)
+}
+
+/// Resolve the parent container of `path` and the last reference token.
+fn parent_mut<'a>(
+    document: &'a mut Value,
+    path: &str,
+) -> Result<(&'a mut Value, String), ResourceError> {
+    let mut tokens = tokens(path)?;
+    let last = tokens
+        .pop()
+        .ok_or_else(|| invalid("the document root has no parent".to_string()))?;
+
+    let parent_path: String = tokens
+        .iter()
+        .map(|token| format!("/{}", token.replace('~', "~0").replace('/', "~1")))
+        .collect();
+
+    Ok((pointer_mut(document, &parent_path)?, last))
+}
+
+fn add(document: &mut Value, path: &str, value: Value) -> Result<(), ResourceError> {
+    if path.is_empty() {
+        *document = value;
+        return Ok(());
+    }
+
+    match parent_mut(document, path)? {
+        (Value::Object(map), key) => {
+            map.insert(key, value);
+            Ok(())
+        }
+        (Value::Array(items), token) if token == "-" => {
+            items.push(value);
+            Ok(())
+        }
+        (Value::Array(items), token) => {
+            // inserting right after the last element is allowed
+            let index = array_index(&token, items.len() + 1, path)?;
+            items.insert(index, value);
+            Ok(())
+        }
+        _ => Err(invalid(format!("cannot add at '{}'", path))),
+    }
+}
+
+fn remove(document: &mut Value, path: &str) -> Result<Value, ResourceError> {
+    match parent_mut(document, path)? {
+        (Value::Object(map), key) => map
+            .remove(&key)
+            .ok_or_else(|| invalid(format!("path '{}' does not exist", path))),
+        (Value::Array(items), token) => {
+            let index = array_index(&token, items.len(), path)?;
+            Ok(items.remove(index))
+        }
+        _ => Err(invalid(format!("cannot remove '{}'", path))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn patch(document: Value, operations: Value) -> Result<Value, ResourceError> {
+        let operations: Vec<PatchOperation> = serde_json::from_value(operations).unwrap();
+        apply_patch(&document, &operations)
+    }
+
+    #[test]
+    fn test_apply_all_operations() {
+        let patched = patch(
+            json!({ "a": { "b": 1 }, "list": [1, 2], "x/y": "slash" }),
+            json!([
+                { "op": "test", "path": "/a/b", "value": 1 },
+                { "op": "add", "path": "/a/c", "value": 2 },
+                { "op": "replace", "path": "/a/b", "value": 10 },
+                { "op": "add", "path": "/list/1", "value": 5 },
+                { "op": "add", "path": "/list/-", "value": 9 },
+                { "op": "remove", "path": "/list/0" },
+                { "op": "copy", "from": "/a", "path": "/copied" },
+                { "op": "move", "from": "/x~1y", "path": "/moved" }
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            patched,
+            json!({
+                "a": { "b": 10, "c": 2 },
+                "copied": { "b": 10, "c": 2 },
+                "list": [5, 2, 9],
+                "moved": "slash"
+            })
+        );
+    }
+
+    #[test]
+    fn test_diff_roundtrips_through_apply() {
+        let old = json!({ "a/b": 1, "list": [1, 2, 3], "gone": true, "same": { "x": 1 } });
+        let new = json!({ "a/b": 2, "list": [1, 5], "added": null, "same": { "x": 1 } });
+
+        let operations = diff(&old, &new);
+        assert_eq!(
+            operations,
+            vec![
+                PatchOperation::Replace {
+                    path: "/a~1b".to_string(),
+                    value: json!(2)
+                },
+                PatchOperation::Remove {
+                    path: "/gone".to_string()
+                },
+                PatchOperation::Replace {
+                    path: "/list/1".to_string(),
+                    value: json!(5)
+                },
+                PatchOperation::Remove {
+                    path: "/list/2".to_string()
+                },
+                PatchOperation::Add {
+                    path: "/added".to_string(),
+                    value: json!(null)
+                },
+            ]
+        );
+        assert_eq!(apply_patch(&old, &operations).unwrap(), new);
+        assert!(diff(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_failed_operations_are_rejected() {
+        let document = json!({ "a": [1] });
+
+        assert!(patch(document.clone(), json!([{ "op": "remove", "path": "/b" }])).is_err());
+        assert!(patch(
+            document.clone(),
+            json!([{ "op": "add", "path": "/a/5", "value": 1 }])
+        )
+        .is_err());
+        assert!(patch(
+            document.clone(),
+            json!([{ "op": "test", "path": "/a/0", "value": 2 }])
+        )
+        .is_err());
+        assert!(patch(
+            document.clone(),
+            json!([{ "op": "move", "from": "/a", "path": "/a/0" }])
+        )
+        .is_err());
+        assert!(patch(
+            document,
+            json!([{ "op": "replace", "path": "/a/01", "value": 2 }])
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,1347 @@
+use std::any::Any;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::marker::PhantomData;
+use std::sync::{mpsc, Arc, PoisonError, RwLock, Weak};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use futures_util::future::join_all;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::Semaphore;
+
+use crate::{
+    base::{DiskCacheCleanup, RefreshPriority, ResourceStats},
+    error::ResourceError,
+    traits::ResourceReader,
+};
+
+/// Longest sleep of [`Registry::run_schedules`] before it looks for new schedules.
+const SCHEDULE_RECHECK: Duration = Duration::from_secs(60);
+
+/// Attempts of [`Registry::snapshot`] to read the resources without a refresh in between.
+const SNAPSHOT_ATTEMPTS: usize = 16;
+
+type AnyData = Arc<dyn Any + Send + Sync>;
+
+/// Type-erased view of a registered reader, so resources of different payload
+/// types can live in one registry.
+#[async_trait::async_trait]
+trait RegisteredResource: Send + Sync {
+    fn stats(&self) -> Result<ResourceStats, ResourceError>;
+
+    fn mark_as_stale(&self) -> Result<(), ResourceError>;
+
+    fn last_refreshed_at(&self) -> Result<Option<SystemTime>, ResourceError>;
+
+    fn priority(&self) -> RefreshPriority;
+
+    /// Next time the resource's refresh schedule comes due after `time`.
+    fn next_refresh_after(&self, time: SystemTime) -> Option<SystemTime>;
+
+    fn remove_files_older_than(&self, max_age: Duration)
+        -> Result<DiskCacheCleanup, ResourceError>;
+
+    fn memory_weight(&self) -> Result<usize, ResourceError>;
+
+    /// Drop the data held in memory, returning the weight freed.
+    fn evict_from_memory(&self) -> Result<usize, ResourceError>;
+
+    fn generation(&self) -> Result<u64, ResourceError>;
+
+    /// Newest cached data, from memory or disk, without refreshing.
+    fn cached_data(&self) -> Result<Option<AnyData>, ResourceError>;
+
+    /// Load fresh data, discarding it; the reader keeps it cached.
+    async fn refresh(&self) -> Result<(), ResourceError>;
+}
+
+type SharedEntry = Arc<dyn RegisteredResource>;
+
+/// Time a refresh schedule comes due, with the named entries due then.
+type DueEntries = (SystemTime, Vec<(String, SharedEntry)>);
+
+struct Entry<T, R> {
+    reader: Arc<R>,
+    _payload: PhantomData<fn() -> T>,
+}
+
+#[async_trait::async_trait]
+impl<T, R> RegisteredResource for Entry<T, R>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default + 'static,
+    R: ResourceReader<T> + Send + Sync + 'static,
+{
+    fn stats(&self) -> Result<ResourceStats, ResourceError> {
+        self.reader.stats()
+    }
+
+    fn mark_as_stale(&self) -> Result<(), ResourceError> {
+        self.reader.mark_as_stale()
+    }
+
+    fn last_refreshed_at(&self) -> Result<Option<SystemTime>, ResourceError> {
+        self.reader.last_refreshed_at()
+    }
+
+    fn priority(&self) -> RefreshPriority {
+        self.reader.get_state().get_priority()
+    }
+
+    fn next_refresh_after(&self, time: SystemTime) -> Option<SystemTime> {
+        self.reader
+            .get_state()
+            .get_refresh_schedule()?
+            .next_after(time)
+    }
+
+    fn remove_files_older_than(
+        &self,
+        max_age: Duration,
+    ) -> Result<DiskCacheCleanup, ResourceError> {
+        self.reader.get_state().remove_files_older_than(max_age)
+    }
+
+    fn memory_weight(&self) -> Result<usize, ResourceError> {
+        self.reader.get_state().memory_weight()
+    }
+
+    fn evict_from_memory(&self) -> Result<usize, ResourceError> {
+        self.reader.get_state().evict_from_memory()
+    }
+
+    fn generation(&self) -> Result<u64, ResourceError> {
+        self.reader.get_state().generation()
+    }
+
+    fn cached_data(&self) -> Result<Option<AnyData>, ResourceError> {
+        Ok(self
+            .reader
+            .get_state()
+            .get_cached_data()?
+            .map(|(data, _)| data as AnyData))
+    }
+
+    async fn refresh(&self) -> Result<(), ResourceError> {
+        self.reader.get_data_or_error(false).await.map(|_| ())
+    }
+}
+
+/// Named collection of resource readers of any payload type.
+///
+/// The registry keeps shared handles to the readers; callers keep using their own
+/// `Arc` clones to read the data while the registry offers operations across all
+/// registered resources.
+///
+/// Resources may depend on each other (e.g. pricing on currency rates, see
+/// [`add_dependency`](Self::add_dependency)); marking a resource stale or refreshing it
+/// cascades to its dependents.
+#[derive(Default)]
+pub struct Registry {
+    entries: RwLock<HashMap<String, SharedEntry>>,
+    /// Dependents of each resource.
+    dependents: RwLock<HashMap<String, BTreeSet<String>>>,
+    /// Registered name each alias stands for.
+    aliases: RwLock<HashMap<String, String>>,
+    memory_budget: Option<usize>,
+}
+
+impl Registry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bound the memory held by all registered resources together, see
+    /// [`enforce_memory_budget`](Self::enforce_memory_budget). The weight of each
+    /// payload comes from its [weigher](crate::state_manager::ResourceProps::with_weigher).
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = Some(bytes);
+        self
+    }
+
+    /// Approximate size of the data held in memory by all registered resources.
+    pub fn memory_usage(&self) -> Result<usize, ResourceError> {
+        self.entries()?
+            .into_iter()
+            .map(|(_, entry)| entry.memory_weight())
+            .sum()
+    }
+
+    /// Drop resources from memory until the registry fits its memory budget, lowest
+    /// priority first and, within a priority, the least recently refreshed first.
+    /// Evicted resources are read from their disk cache on the next access. Returns
+    /// the names of the evicted resources.
+    ///
+    /// Runs after every refresh through the registry; call it after reading through
+    /// the readers directly to bound the memory in between.
+    pub fn enforce_memory_budget(&self) -> Result<Vec<String>, ResourceError> {
+        let Some(budget) = self.memory_budget else {
+            return Ok(Vec::new());
+        };
+
+        let mut held = Vec::new();
+        for (name, entry) in self.entries()? {
+            let weight = entry.memory_weight()?;
+            if weight > 0 {
+                held.push((name, entry.last_refreshed_at()?, weight, entry));
+            }
+        }
+
+        let mut usage: usize = held.iter().map(|(_, _, weight, _)| weight).sum();
+        held.sort_by(|a, b| {
+            (a.3.priority().cmp(&b.3.priority()))
+                .then(a.1.cmp(&b.1))
+                .then(a.0.cmp(&b.0))
+        });
+
+        let mut evicted = Vec::new();
+        for (name, _, _, entry) in held {
+            if usage <= budget {
+                break;
+            }
+            usage = usage.saturating_sub(entry.evict_from_memory()?);
+            evicted.push(name);
+        }
+
+        Ok(evicted)
+    }
+
+    /// Register a reader under the given name, replacing any previous registration.
+    pub fn register<T, R>(
+        &self,
+        name: impl Into<String>,
+        reader: Arc<R>,
+    ) -> Result<(), ResourceError>
+    where
+        T: Send + Sync + DeserializeOwned + Serialize + Default + 'static,
+        R: ResourceReader<T> + Send + Sync + 'static,
+    {
+        let entry: SharedEntry = Arc::new(Entry {
+            reader,
+            _payload: PhantomData,
+        });
+
+        self.entries
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(name.into(), entry);
+
+        Ok(())
+    }
+
+    /// Make `alias` another name of the resource registered as `name`, e.g. while
+    /// modules migrate between naming conventions: both names resolve to the one
+    /// reader and its cache. Registered names take precedence over aliases; an alias
+    /// of an alias stands for the name it resolves to now.
+    pub fn alias(
+        &self,
+        alias: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Result<(), ResourceError> {
+        let name = self.resolve(&name.into())?;
+
+        self.aliases
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(alias.into(), name);
+
+        Ok(())
+    }
+
+    /// Registered name `name` stands for: the name itself or, for an alias, the name
+    /// it was created for.
+    pub fn resolve(&self, name: &str) -> Result<String, ResourceError> {
+        if self
+            .entries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(name)
+        {
+            return Ok(name.to_string());
+        }
+
+        Ok(self
+            .aliases
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string()))
+    }
+
+    /// Registered entry of the name or alias.
+    fn entry(&self, name: &str) -> Result<Option<SharedEntry>, ResourceError> {
+        let name = self.resolve(name)?;
+
+        Ok(self
+            .entries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&name)
+            .cloned())
+    }
+
+    /// Remove a registration with its dependencies and aliases, or a single alias,
+    /// returning whether it existed.
+    pub fn unregister(&self, name: &str) -> Result<bool, ResourceError> {
+        let mut aliases = self.aliases.write().unwrap_or_else(PoisonError::into_inner);
+        let registered = self
+            .entries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(name);
+        if !registered {
+            return Ok(aliases.remove(name).is_some());
+        }
+        aliases.retain(|_, target| target != name);
+        drop(aliases);
+
+        let mut dependents = self
+            .dependents
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        dependents.remove(name);
+        for names in dependents.values_mut() {
+            names.remove(name);
+        }
+        drop(dependents);
+
+        Ok(self
+            .entries
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(name)
+            .is_some())
+    }
+
+    /// Declare that `dependent` is derived from `dependency`, so it is invalidated and
+    /// refreshed after it. The resources need not be registered yet.
+    pub fn add_dependency(
+        &self,
+        dependent: impl Into<String>,
+        dependency: impl Into<String>,
+    ) -> Result<(), ResourceError> {
+        let dependent = self.resolve(&dependent.into())?;
+        let dependency = self.resolve(&dependency.into())?;
+        let mut dependents = self
+            .dependents
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // the new edge closes a cycle when the dependency is reachable from the dependent
+        if let Some(path) = dependency_path(&dependents, &dependent, &dependency) {
+            return Err(ResourceError::DependencyCycle(format!(
+                "{} -> {}",
+                path.join(" -> "),
+                dependent
+            )));
+        }
+
+        dependents.entry(dependency).or_default().insert(dependent);
+
+        Ok(())
+    }
+
+    /// Resources depending on `name`, directly or transitively, in refresh order.
+    pub fn dependents_of(&self, name: &str) -> Result<Vec<String>, ResourceError> {
+        let name = &self.resolve(name)?;
+        let dependents = self
+            .dependents
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut order = topological_order(&dependents, name);
+        order.remove(0);
+
+        Ok(order)
+    }
+
+    /// Mark the resource and everything depending on it stale, returning the names of
+    /// the registered resources marked.
+    pub fn mark_stale(&self, name: &str) -> Result<Vec<String>, ResourceError> {
+        let mut marked = Vec::new();
+
+        for (name, entry) in self.cascade(name)? {
+            entry.mark_as_stale()?;
+            marked.push(name);
+        }
+
+        Ok(marked)
+    }
+
+    /// Refresh the resource, then its dependents in topological order, so each one is
+    /// loaded after everything it depends on.
+    ///
+    /// Stops at the first failure; the resources not refreshed yet stay marked stale.
+    /// Returns the names of the refreshed resources.
+    pub async fn refresh(&self, name: &str) -> Result<Vec<String>, ResourceError> {
+        let cascade = self.cascade(name)?;
+        for (_, entry) in &cascade {
+            entry.mark_as_stale()?;
+        }
+
+        let mut refreshed = Vec::new();
+        for (name, entry) in cascade {
+            entry.refresh().await?;
+            refreshed.push(name);
+        }
+        self.enforce_memory_budget()?;
+
+        Ok(refreshed)
+    }
+
+    /// Mark every registered resource stale and refresh them in parallel, at most
+    /// `max_concurrency` at a time. Returns an outcome per resource, sorted by name.
+    ///
+    /// Resources of higher [`RefreshPriority`] get a slot first; resources of equal
+    /// priority are started in name order.
+    ///
+    /// Dependencies are not ordered here; use [`refresh`](Self::refresh) for a cascade.
+    pub async fn refresh_all(
+        &self,
+        max_concurrency: usize,
+    ) -> Result<Vec<RefreshOutcome>, ResourceError> {
+        let mut entries = self.entries()?;
+        // the semaphore hands out slots in the order they are requested
+        entries.sort_by(|a, b| b.1.priority().cmp(&a.1.priority()).then(a.0.cmp(&b.0)));
+        let permits = Semaphore::new(max_concurrency.max(1));
+
+        let mut outcomes = join_all(entries.into_iter().map(|(name, entry)| {
+            let permits = &permits;
+            async move {
+                let started = Instant::now();
+                // the semaphore is never closed
+                let _permit = permits.acquire().await;
+                let result = match entry.mark_as_stale() {
+                    Ok(()) => entry.refresh().await,
+                    Err(error) => Err(error),
+                };
+
+                RefreshOutcome {
+                    name,
+                    result,
+                    duration: started.elapsed(),
+                    refreshed_at: entry.last_refreshed_at().ok().flatten(),
+                }
+            }
+        }))
+        .await;
+        outcomes.sort_by(|a, b| a.name.cmp(&b.name));
+        self.enforce_memory_budget()?;
+
+        Ok(outcomes)
+    }
+
+    /// Refresh every resource with a
+    /// [refresh schedule](crate::state_manager::ResourceProps::with_refresh_schedule)
+    /// each time it comes due, by the wall clock; never returns.
+    ///
+    /// Meant to run in a spawned task next to the readers. Resources registered later
+    /// are picked up within a minute. Resources due at the same time are refreshed
+    /// together, in priority order; a failed refresh is recorded in the resource's
+    /// stats and its cached data stays in place.
+    pub async fn run_schedules(&self) {
+        let mut checked = SystemTime::now();
+
+        loop {
+            let now = SystemTime::now();
+            let next = self.scheduled_after(checked).ok().flatten();
+
+            match next {
+                Some((due, entries)) if due <= now => {
+                    join_all(entries.iter().map(|(_, entry)| async move {
+                        if entry.mark_as_stale().is_ok() {
+                            let _ = entry.refresh().await;
+                        }
+                    }))
+                    .await;
+                    let _ = self.enforce_memory_budget();
+                    checked = due;
+                }
+                next => {
+                    let wait = next
+                        .and_then(|(due, _)| due.duration_since(now).ok())
+                        .unwrap_or(SCHEDULE_RECHECK)
+                        .min(SCHEDULE_RECHECK);
+                    tokio::time::sleep(wait).await;
+                }
+            }
+        }
+    }
+
+    /// The earliest time a refresh schedule comes due after `time`, with the entries
+    /// due then, highest priority first.
+    fn scheduled_after(&self, time: SystemTime) -> Result<Option<DueEntries>, ResourceError> {
+        let mut due: Option<DueEntries> = None;
+
+        for (name, entry) in self.entries()? {
+            let Some(next) = entry.next_refresh_after(time) else {
+                continue;
+            };
+            match &mut due {
+                Some((earliest, entries)) if next == *earliest => entries.push((name, entry)),
+                Some((earliest, _)) if next > *earliest => {}
+                _ => due = Some((next, vec![(name, entry)])),
+            }
+        }
+
+        if let Some((_, entries)) = &mut due {
+            entries.sort_by(|a, b| b.1.priority().cmp(&a.1.priority()).then(a.0.cmp(&b.0)));
+        }
+
+        Ok(due)
+    }
+
+    /// Registered entries of the resource and its dependents, in topological order.
+    fn cascade(&self, name: &str) -> Result<Vec<(String, SharedEntry)>, ResourceError> {
+        let name = &self.resolve(name)?;
+        let dependents = self
+            .dependents
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let order = topological_order(&dependents, name);
+        drop(dependents);
+
+        let entries = self.entries.read().unwrap_or_else(PoisonError::into_inner);
+
+        Ok(order
+            .into_iter()
+            .filter_map(|name| {
+                let entry = Arc::clone(entries.get(&name)?);
+                Some((name, entry))
+            })
+            .collect())
+    }
+
+    /// Names of all registered resources, sorted.
+    pub fn names(&self) -> Result<Vec<String>, ResourceError> {
+        let mut names: Vec<String> = self
+            .entries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .cloned()
+            .collect();
+        names.sort();
+
+        Ok(names)
+    }
+
+    /// Cached data of several resources as of one moment: the resources are read again
+    /// until no refresh stored new data in between, so values derived from them never
+    /// mix old and new halves. Nothing is refreshed; load the resources first.
+    ///
+    /// Names not registered at this point are ignored. Fails with
+    /// [`ResourceError::SnapshotConflict`] when refreshes keep interleaving.
+    pub fn snapshot(&self, names: &[&str]) -> Result<Snapshot, ResourceError> {
+        let mut selected: Vec<(String, SharedEntry)> = Vec::new();
+        for name in names {
+            if let Some(entry) = self.entry(name)? {
+                selected.push((name.to_string(), entry));
+            }
+        }
+        let generations = || -> Result<Vec<u64>, ResourceError> {
+            selected
+                .iter()
+                .map(|(_, entry)| entry.generation())
+                .collect()
+        };
+
+        let mut changed = Vec::new();
+        for _ in 0..SNAPSHOT_ATTEMPTS {
+            let before = generations()?;
+            let mut values = HashMap::new();
+            for (name, entry) in &selected {
+                if let Some(data) = entry.cached_data()? {
+                    values.insert(name.clone(), data);
+                }
+            }
+            let after = generations()?;
+
+            if before == after {
+                let generations = selected
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .zip(before)
+                    .collect();
+                return Ok(Snapshot {
+                    values,
+                    generations,
+                });
+            }
+            changed = selected
+                .iter()
+                .zip(before.iter().zip(&after))
+                .filter(|(_, (before, after))| before != after)
+                .map(|((name, _), _)| name.clone())
+                .collect();
+        }
+
+        Err(ResourceError::SnapshotConflict(changed))
+    }
+
+    /// Statistics of every registered resource, keyed by name.
+    pub fn stats(&self) -> Result<BTreeMap<String, ResourceStats>, ResourceError> {
+        self.entries()?
+            .into_iter()
+            .map(|(name, entry)| Ok((name, entry.stats()?)))
+            .collect()
+    }
+
+    /// Statistics of a single registered resource.
+    pub fn stats_for(&self, name: &str) -> Result<Option<ResourceStats>, ResourceError> {
+        self.entry(name)?.map(|entry| entry.stats()).transpose()
+    }
+
+    /// Handle reloading the named resources, e.g. from a `SIGHUP` handler.
+    ///
+    /// Names not registered at this point are ignored.
+    pub fn reload_handle(&self, names: &[&str]) -> Result<ReloadHandle, ResourceError> {
+        let mut resources = Vec::new();
+        for name in names {
+            if let Some(entry) = self.entry(name)? {
+                resources.push((name.to_string(), entry));
+            }
+        }
+
+        Ok(ReloadHandle { resources })
+    }
+
+    /// Remove the disk cache files older than `max_age` of every registered resource,
+    /// keeping each resource's newest file. Returns the total removed.
+    pub fn remove_aged_files(&self, max_age: Duration) -> Result<DiskCacheCleanup, ResourceError> {
+        let mut total = DiskCacheCleanup::default();
+
+        for (_, entry) in self.entries()? {
+            let cleanup = entry.remove_files_older_than(max_age)?;
+            total.files += cleanup.files;
+            total.bytes += cleanup.bytes;
+        }
+
+        Ok(total)
+    }
+
+    /// Run [`remove_aged_files`](Self::remove_aged_files) on a background thread every
+    /// `interval`, until the returned [`Janitor`] or the registry is dropped.
+    ///
+    /// Each sweep that deletes files of a resource reports the reclaimed bytes to its
+    /// event listener as [`ResourceEvent::CacheFilesRemoved`](crate::state_manager::ResourceEvent::CacheFilesRemoved).
+    pub fn spawn_janitor(self: &Arc<Self>, interval: Duration, max_age: Duration) -> Janitor {
+        let registry = Arc::downgrade(self);
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        let thread = thread::Builder::new()
+            .name("resourcely-janitor".to_string())
+            .spawn(move || sweep_until_stopped(registry, stopped, interval, max_age))
+            .expect("Unable to start the janitor thread");
+
+        Janitor {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// Clone the current entries so no lock is held while calling into readers.
+    fn entries(&self) -> Result<Vec<(String, SharedEntry)>, ResourceError> {
+        Ok(self
+            .entries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(name, entry)| (name.clone(), Arc::clone(entry)))
+            .collect())
+    }
+}
+
+/// Data of several resources taken together by [`Registry::snapshot`].
+pub struct Snapshot {
+    values: HashMap<String, AnyData>,
+    generations: HashMap<String, u64>,
+}
+
+impl Snapshot {
+    /// Data of the named resource; `None` when nothing was cached, the name is not part
+    /// of the snapshot or the payload type is not `T`.
+    pub fn get<T: Send + Sync + 'static>(&self, name: &str) -> Option<Arc<T>> {
+        Arc::clone(self.values.get(name)?).downcast::<T>().ok()
+    }
+
+    /// Cache generation of the named resource when the snapshot was taken, see
+    /// [`ResourceReader::is_current`].
+    pub fn generation(&self, name: &str) -> Option<u64> {
+        self.generations.get(name).copied()
+    }
+}
+
+/// Outcome of refreshing one resource in [`Registry::refresh_all`].
+#[derive(Debug)]
+pub struct RefreshOutcome {
+    /// Name the resource is registered under.
+    pub name: String,
+    /// Whether it was loaded fresh; on failure it stays marked stale.
+    pub result: Result<(), ResourceError>,
+    /// Time spent waiting for a slot and refreshing.
+    pub duration: Duration,
+    /// When its newest cached data was stored, after the refresh.
+    pub refreshed_at: Option<SystemTime>,
+}
+
+/// Outcome of [`ReloadHandle::reload`].
+#[derive(Debug, Default)]
+pub struct ReloadReport {
+    /// Resources loaded fresh.
+    pub refreshed: Vec<String>,
+    /// Resources that failed to refresh, with the reason; they stay marked stale.
+    pub failed: Vec<(String, ResourceError)>,
+}
+
+impl ReloadReport {
+    /// Whether every resource was refreshed.
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Trigger marking a set of resources stale and refreshing them at once.
+///
+/// Cheap to clone, so it can be moved into a signal handler task:
+///
+/// ```ignore
+/// let handle = registry.reload_handle(&["flags", "pricing"])?;
+/// tokio::spawn(async move {
+///     let mut hangups = signal(SignalKind::hangup())?;
+///     while hangups.recv().await.is_some() {
+///         let report = handle.reload().await;
+///     }
+/// });
+/// ```
+#[derive(Clone, Default)]
+pub struct ReloadHandle {
+    resources: Vec<(String, SharedEntry)>,
+}
+
+impl ReloadHandle {
+    /// Create a handle without resources.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a reader to the reloaded set.
+    pub fn with_resource<T, R>(mut self, name: impl Into<String>, reader: Arc<R>) -> Self
+    where
+        T: Send + Sync + DeserializeOwned + Serialize + Default + 'static,
+        R: ResourceReader<T> + Send + Sync + 'static,
+    {
+        self.resources.push((
+            name.into(),
+            Arc::new(Entry {
+                reader,
+                _payload: PhantomData,
+            }),
+        ));
+        self
+    }
+
+    /// Names of the reloaded resources.
+    pub fn names(&self) -> Vec<&str> {
+        self.resources
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Mark all resources stale without refreshing them; the next reads reload them.
+    pub fn mark_stale(&self) -> Result<(), ResourceError> {
+        for (_, entry) in &self.resources {
+            entry.mark_as_stale()?;
+        }
+        Ok(())
+    }
+
+    /// Mark all resources stale and refresh them concurrently.
+    pub async fn reload(&self) -> ReloadReport {
+        let mut report = ReloadReport::default();
+
+        let results = join_all(self.resources.iter().map(|(_, entry)| async move {
+            entry.mark_as_stale()?;
+            entry.refresh().await
+        }))
+        .await;
+
+        for ((name, _), result) in self.resources.iter().zip(results) {
+            match result {
+                Ok(()) => report.refreshed.push(name.clone()),
+                Err(error) => report.failed.push((name.clone(), error)),
+            }
+        }
+
+        report
+    }
+}
+
+/// Background cleanup started by [`Registry::spawn_janitor`]; stops when dropped.
+pub struct Janitor {
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Janitor {
+    /// Stop the cleanup and wait for a running sweep to finish.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Janitor {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn sweep_until_stopped(
+    registry: Weak<Registry>,
+    stopped: mpsc::Receiver<()>,
+    interval: Duration,
+    max_age: Duration,
+) {
+    while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+        let Some(registry) = registry.upgrade() else {
+            return;
+        };
+        // a failed sweep is retried on the next tick
+        let _ = registry.remove_aged_files(max_age);
+    }
+}
+
+/// Path of dependents leading from `from` to `to`, if `to` is reachable.
+fn dependency_path(
+    dependents: &HashMap<String, BTreeSet<String>>,
+    from: &str,
+    to: &str,
+) -> Option<Vec<String>> {
+    if from == to {
+        return Some(vec![from.to_string()]);
+    }
+
+    dependents.get(from)?.iter().find_map(|next| {
+        let mut path = dependency_path(dependents, next, to)?;
+        path.insert(0, from.to_string());
+        Some(path)
+    })
+}
+
+/// `root` followed by everything depending on it, each name after all of its
+/// dependencies within that set (Kahn's algorithm, ties broken by name).
+fn topological_order(dependents: &HashMap<String, BTreeSet<String>>, root: &str) -> Vec<String> {
+    let mut reachable = BTreeSet::from([root.to_string()]);
+    let mut pending = vec![root.to_string()];
+    while let Some(name) = pending.pop() {
+        for dependent in dependents.get(&name).into_iter().flatten() {
+            if reachable.insert(dependent.clone()) {
+                pending.push(dependent.clone());
+            }
+        }
+    }
+
+    let mut in_degree: BTreeMap<&String, usize> = reachable.iter().map(|n| (n, 0)).collect();
+    for name in &reachable {
+        for dependent in dependents.get(name).into_iter().flatten() {
+            *in_degree.entry(dependent).or_default() += 1;
+        }
+    }
+
+    let mut order = Vec::new();
+    let mut ready: BTreeSet<&String> = in_degree
+        .iter()
+        .filter(|(_, degree)| **degree == 0)
+        .map(|(name, _)| *name)
+        .collect();
+    while let Some(name) = ready.pop_first() {
+        order.push(name.clone());
+        for dependent in dependents.get(name).into_iter().flatten() {
+            if let Some(degree) = in_degree.get_mut(dependent) {
+                *degree -= 1;
+                if *degree == 0 {
+                    ready.insert(dependent);
+                }
+            }
+        }
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{ResourceProps, ResourceState};
+    use crate::clock::ManualClock;
+    use crate::events::ResourceEvent;
+    use crate::file_system::MemoryFileSystem;
+    use crate::local::DefaultLocalResourceReader;
+    use crate::testing::MockResourceReader;
+    use crate::ResourceFileType;
+    use reqwest::Url;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::SystemTime;
+
+    fn local_reader(name: &str) -> Arc<DefaultLocalResourceReader<serde_json::Value>> {
+        Arc::new(DefaultLocalResourceReader::new(ResourceState::new(
+            ResourceProps::new(
+                name,
+                ResourceFileType::Json,
+                Url::parse("file:///tmp").unwrap(),
+                std::env::temp_dir().join("resourcely-registry-missing"),
+                None,
+            ),
+        )))
+    }
+
+    #[test]
+    fn test_register_and_collect_stats() {
+        let registry = Registry::new();
+        let flags = local_reader("flags");
+        registry.register("flags", Arc::clone(&flags)).unwrap();
+        registry
+            .register("pricing", local_reader("pricing"))
+            .unwrap();
+
+        flags.get_state().record_hit();
+        flags.get_state().record_miss();
+
+        assert_eq!(registry.names().unwrap(), vec!["flags", "pricing"]);
+
+        let stats = registry.stats().unwrap();
+        assert_eq!(stats["flags"].hits, 1);
+        assert_eq!(stats["flags"].misses, 1);
+        assert_eq!(stats["pricing"].hits, 0);
+
+        assert!(registry.unregister("pricing").unwrap());
+        assert!(registry.stats_for("pricing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_janitor_removes_aged_files() {
+        let file_system = Arc::new(MemoryFileSystem::new());
+        for timestamp in [1_000, 2_000, 9_000] {
+            file_system.insert(format!("/cache/flags-{}.json", timestamp), "{}");
+        }
+        file_system.insert("/cache/rates-1000.json", "{}");
+
+        let removed = Arc::new(AtomicU64::new(0));
+        let reclaimed = Arc::clone(&removed);
+        let props = |name: &str| {
+            ResourceProps::<serde_json::Value>::new(
+                name,
+                ResourceFileType::Json,
+                Url::parse("file:///tmp").unwrap(),
+                "/cache",
+                None,
+            )
+            .with_file_system(file_system.clone())
+            .with_clock(Arc::new(ManualClock::new(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(10_000),
+            )))
+        };
+        let flags = props("flags").with_event_listener(Arc::new(move |event| {
+            if let ResourceEvent::CacheFilesRemoved { bytes, .. } = event {
+                reclaimed.fetch_add(*bytes, Ordering::Relaxed);
+            }
+        }));
+
+        let registry = Arc::new(Registry::new());
+        registry
+            .register(
+                "flags",
+                Arc::new(DefaultLocalResourceReader::new(ResourceState::new(flags))),
+            )
+            .unwrap();
+        registry
+            .register(
+                "rates",
+                Arc::new(DefaultLocalResourceReader::new(ResourceState::new(props(
+                    "rates",
+                )))),
+            )
+            .unwrap();
+
+        let janitor = registry.spawn_janitor(Duration::from_millis(5), Duration::from_secs(3_600));
+        for _ in 0..200 {
+            if file_system.paths().len() == 2 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        janitor.stop();
+
+        // the newest file of each resource is kept as the stale fallback
+        assert_eq!(
+            file_system.paths(),
+            vec![
+                PathBuf::from("/cache/flags-9000.json"),
+                PathBuf::from("/cache/rates-1000.json")
+            ]
+        );
+        assert_eq!(removed.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn test_dependencies_cascade_in_topological_order() {
+        let registry = Registry::new();
+        for name in ["rates", "pricing", "invoices", "catalog"] {
+            registry.register(name, local_reader(name)).unwrap();
+        }
+        registry.add_dependency("pricing", "rates").unwrap();
+        registry.add_dependency("invoices", "pricing").unwrap();
+        registry.add_dependency("invoices", "rates").unwrap();
+        registry.add_dependency("catalog", "pricing").unwrap();
+
+        assert_eq!(
+            registry.dependents_of("rates").unwrap(),
+            vec!["pricing", "catalog", "invoices"]
+        );
+        assert!(matches!(
+            registry.add_dependency("rates", "invoices"),
+            Err(ResourceError::DependencyCycle(cycle)) if cycle == "rates -> invoices -> rates"
+        ));
+
+        assert_eq!(
+            registry.mark_stale("pricing").unwrap(),
+            vec!["pricing", "catalog", "invoices"]
+        );
+        assert!(registry.mark_stale("rates").unwrap().len() == 4);
+
+        registry.unregister("pricing").unwrap();
+        assert_eq!(registry.dependents_of("rates").unwrap(), vec!["invoices"]);
+    }
+
+    /// Reader counting how many reads run at once.
+    struct Concurrent {
+        reader: MockResourceReader<u32>,
+        active: Arc<AtomicU64>,
+        peak: Arc<AtomicU64>,
+    }
+
+    #[async_trait::async_trait]
+    impl ResourceReader<u32> for Concurrent {
+        fn get_state(&self) -> &Arc<ResourceState<u32>> {
+            self.reader.get_state()
+        }
+
+        async fn get_data_or_error(
+            &self,
+            allow_stale: bool,
+        ) -> Result<crate::DataResult<Arc<u32>>, ResourceError> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(active, Ordering::SeqCst);
+            tokio::task::yield_now().await;
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            self.reader.get_data_or_error(allow_stale).await
+        }
+    }
+
+    #[test]
+    fn test_refresh_all_bounds_concurrency() {
+        let (active, peak) = (Arc::new(AtomicU64::new(0)), Arc::new(AtomicU64::new(0)));
+        let registry = Registry::new();
+        for name in ["a", "b", "c", "d", "e"] {
+            let reader = MockResourceReader::<u32>::new(name);
+            if name == "c" {
+                reader.push_error(ResourceError::Offline);
+            } else {
+                reader.push_fresh(1);
+                reader.get_state().set_internal_cache(1).unwrap();
+            }
+            let reader = Concurrent {
+                reader,
+                active: Arc::clone(&active),
+                peak: Arc::clone(&peak),
+            };
+            registry.register(name, Arc::new(reader)).unwrap();
+        }
+
+        let outcomes = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(registry.refresh_all(2))
+            .unwrap();
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        let names: Vec<&str> = outcomes.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b", "c", "d", "e"]);
+        assert!(matches!(outcomes[2].result, Err(ResourceError::Offline)));
+        assert!(outcomes[2].refreshed_at.is_none());
+        assert!(outcomes[0].result.is_ok() && outcomes[0].refreshed_at.is_some());
+    }
+
+    /// Reader recording the order in which reads start.
+    struct Ordered {
+        state: Arc<ResourceState<u32>>,
+        name: &'static str,
+        order: Arc<std::sync::Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ResourceReader<u32> for Ordered {
+        fn get_state(&self) -> &Arc<ResourceState<u32>> {
+            &self.state
+        }
+
+        async fn get_data_or_error(
+            &self,
+            _allow_stale: bool,
+        ) -> Result<crate::DataResult<Arc<u32>>, ResourceError> {
+            self.order.lock().unwrap().push(self.name);
+            Ok(crate::DataResult::Fresh(Arc::new(1)))
+        }
+    }
+
+    #[test]
+    fn test_refresh_all_starts_critical_resources_first() {
+        let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let registry = Registry::new();
+        for (name, priority) in [
+            ("catalog", RefreshPriority::Low),
+            ("kill-switch", RefreshPriority::Critical),
+            ("pricing", RefreshPriority::Normal),
+        ] {
+            let reader = Ordered {
+                state: ResourceState::new(
+                    ResourceProps::new(
+                        name,
+                        ResourceFileType::Json,
+                        Url::parse("file:///tmp").unwrap(),
+                        std::env::temp_dir().join("resourcely-registry-missing"),
+                        None,
+                    )
+                    .with_priority(priority),
+                )
+                .into(),
+                name,
+                order: Arc::clone(&order),
+            };
+            registry.register(name, Arc::new(reader)).unwrap();
+        }
+
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(registry.refresh_all(1))
+            .unwrap();
+
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec!["kill-switch", "pricing", "catalog"]
+        );
+    }
+
+    #[test]
+    fn test_schedules_pick_the_earliest_due_resources() {
+        let registry = Registry::new();
+        for (name, schedule, priority) in [
+            ("catalog", None, RefreshPriority::Low),
+            ("pricing", Some("5 0 * * *"), RefreshPriority::Normal),
+            ("rates", Some("*/30 * * * *"), RefreshPriority::Normal),
+            ("switches", Some("0 * * * *"), RefreshPriority::Critical),
+        ] {
+            let mut props = ResourceProps::new(
+                name,
+                ResourceFileType::Json,
+                Url::parse("file:///tmp").unwrap(),
+                std::env::temp_dir().join("resourcely-registry-missing"),
+                None,
+            )
+            .with_priority(priority);
+            if let Some(schedule) = schedule {
+                props = props.with_refresh_schedule(schedule.parse().unwrap());
+            }
+            let reader = Ordered {
+                state: Arc::new(ResourceState::new(props)),
+                name,
+                order: Arc::default(),
+            };
+            registry.register(name, Arc::new(reader)).unwrap();
+        }
+
+        let due_after = |seconds: u64| {
+            let time = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+            let (due, entries) = registry.scheduled_after(time).unwrap().unwrap();
+            let names: Vec<String> = entries.into_iter().map(|(name, _)| name).collect();
+            (
+                due.duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs(),
+                names,
+            )
+        };
+
+        // 2024-03-15 23:50 UTC: midnight is due for the hourly and half-hourly resources
+        assert_eq!(
+            due_after(1_710_546_600),
+            (
+                1_710_547_200,
+                vec!["switches".to_string(), "rates".to_string()]
+            )
+        );
+        assert_eq!(
+            due_after(1_710_547_200),
+            (1_710_547_500, vec!["pricing".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_reload_handle_reports_each_resource() {
+        let flags = Arc::new(MockResourceReader::<Vec<u32>>::new("flags"));
+        flags.push_fresh(vec![1]).push_fresh(vec![2]);
+        let pricing = Arc::new(MockResourceReader::<Vec<u32>>::new("pricing"));
+        pricing.push_error(ResourceError::Offline);
+
+        let registry = Registry::new();
+        registry.register("flags", Arc::clone(&flags)).unwrap();
+        registry.register("pricing", Arc::clone(&pricing)).unwrap();
+        let handle = registry
+            .reload_handle(&["flags", "pricing", "unknown"])
+            .unwrap();
+        assert_eq!(handle.names(), vec!["flags", "pricing"]);
+
+        let report = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(handle.reload());
+
+        assert!(!report.is_success());
+        assert_eq!(report.refreshed, vec!["flags"]);
+        assert!(matches!(
+            report.failed.as_slice(),
+            [(name, ResourceError::Offline)] if name == "pricing"
+        ));
+        assert_eq!((flags.calls(), pricing.calls()), (1, 1));
+    }
+
+    #[test]
+    fn test_memory_budget_evicts_low_priority_and_old_entries_first() {
+        let clock = Arc::new(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let reader = |name: &str, priority: RefreshPriority| {
+            Arc::new(DefaultLocalResourceReader::new(ResourceState::new(
+                ResourceProps::<serde_json::Value>::new(
+                    name,
+                    ResourceFileType::Json,
+                    Url::parse("file:///tmp").unwrap(),
+                    "/cache",
+                    None,
+                )
+                .with_file_system(Arc::new(MemoryFileSystem::new()))
+                .with_clock(clock.clone())
+                .with_weigher(|_| 100)
+                .with_priority(priority),
+            )))
+        };
+
+        let registry = Registry::new().with_memory_budget(150);
+        let readers = [
+            ("flags", reader("flags", RefreshPriority::Critical)),
+            ("rates", reader("rates", RefreshPriority::Normal)),
+            ("banners", reader("banners", RefreshPriority::Low)),
+            ("pricing", reader("pricing", RefreshPriority::Normal)),
+        ];
+        for (name, reader) in &readers {
+            registry.register(*name, Arc::clone(reader)).unwrap();
+            reader
+                .get_state()
+                .set_internal_cache(serde_json::json!({}))
+                .unwrap();
+            clock.advance(Duration::from_secs(1));
+        }
+        assert_eq!(registry.memory_usage().unwrap(), 400);
+
+        assert_eq!(
+            registry.enforce_memory_budget().unwrap(),
+            vec!["banners", "rates", "pricing"]
+        );
+        assert_eq!(registry.memory_usage().unwrap(), 100);
+        assert!(registry.enforce_memory_budget().unwrap().is_empty());
+        assert!(Registry::new().enforce_memory_budget().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_reads_typed_values_together() {
+        fn props<T>(name: &str) -> ResourceProps<T> {
+            ResourceProps::new(
+                name,
+                ResourceFileType::Json,
+                Url::parse("file:///tmp").unwrap(),
+                "/cache",
+                None,
+            )
+            .with_file_system(Arc::new(MemoryFileSystem::new()))
+        }
+        let rates = Arc::new(DefaultLocalResourceReader::<Vec<u32>>::new(
+            ResourceState::new(props("rates")),
+        ));
+        let pricing = Arc::new(DefaultLocalResourceReader::<serde_json::Value>::new(
+            ResourceState::new(props("pricing")),
+        ));
+        let registry = Registry::new();
+        registry.register("rates", Arc::clone(&rates)).unwrap();
+        registry.register("pricing", Arc::clone(&pricing)).unwrap();
+        registry.register("empty", local_reader("empty")).unwrap();
+
+        rates.get_state().set_internal_cache(vec![1, 2]).unwrap();
+        pricing
+            .get_state()
+            .set_internal_cache(serde_json::json!({"base": 10}))
+            .unwrap();
+
+        let snapshot = registry
+            .snapshot(&["rates", "pricing", "empty", "unknown"])
+            .unwrap();
+        assert_eq!(*snapshot.get::<Vec<u32>>("rates").unwrap(), vec![1, 2]);
+        assert_eq!(
+            snapshot.get::<serde_json::Value>("pricing").unwrap()["base"],
+            10
+        );
+        assert!(snapshot.get::<String>("rates").is_none());
+        assert!(snapshot.get::<serde_json::Value>("empty").is_none());
+        assert_eq!(snapshot.generation("empty"), Some(0));
+        assert_eq!(snapshot.generation("unknown"), None);
+
+        let generation = snapshot.generation("rates").unwrap();
+        assert!(rates.is_current(generation).unwrap());
+        rates.get_state().set_internal_cache(vec![3]).unwrap();
+        assert!(!rates.is_current(generation).unwrap());
+    }
+
+    #[test]
+    fn test_aliases_share_one_registration() {
+        let registry = Registry::new();
+        let pricing = local_reader("pricing");
+        registry.register("pricing", Arc::clone(&pricing)).unwrap();
+        registry.alias("prices", "pricing").unwrap();
+        registry.alias("legacy_prices", "prices").unwrap();
+
+        assert_eq!(registry.names().unwrap(), vec!["pricing"]);
+        assert_eq!(registry.resolve("legacy_prices").unwrap(), "pricing");
+        pricing.get_state().record_hit();
+        assert_eq!(registry.stats_for("prices").unwrap().unwrap().hits, 1);
+
+        registry.add_dependency("checkout", "prices").unwrap();
+        assert_eq!(registry.dependents_of("pricing").unwrap(), vec!["checkout"]);
+        assert_eq!(
+            registry.mark_stale("legacy_prices").unwrap(),
+            vec!["pricing"]
+        );
+        assert!(pricing.is_marked_stale().unwrap());
+
+        assert!(registry.unregister("prices").unwrap());
+        assert!(registry.stats_for("prices").unwrap().is_none());
+        assert!(registry.unregister("pricing").unwrap());
+        assert_eq!(registry.resolve("legacy_prices").unwrap(), "legacy_prices");
+    }
+}
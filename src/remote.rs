@@ -1,20 +1,588 @@
 use crate::{
-    base::ResourceState,
-    error::ResourceError,
-    traits::{DataResult, ResourceFileType, ResourceReader},
-    utilities::save_to_disk_override,
+    base::{ResourceState, TimestampFormat},
+    error::{CacheLookup, ResourceError, SourceFailures},
+    events::ResourceEvent,
+    patch::{apply_patch, PatchOperation},
+    traits::{DataResult, ResourceReader},
+    utilities::{decode_data_url, format_timestamp, parse_content, parse_http_date},
 };
 
+#[cfg(any(test, feature = "testing"))]
+use crate::testing::Fault;
+use futures_util::future::join_all;
+use reqwest::{header, Method, RequestBuilder, Response, StatusCode, Url};
 use serde::{de::DeserializeOwned, Serialize};
-use std::{sync::Arc, time::SystemTime};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+/// `User-Agent` sent by remote readers unless configured otherwise.
+pub const DEFAULT_USER_AGENT: &str = concat!("resourcely/", env!("CARGO_PKG_VERSION"));
+
+/// How long a mirror probe may take before the mirror counts as unhealthy.
+const MIRROR_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time a conditional request of [`DefaultRemoteResourceReader::watch`] may take beyond
+/// the long poll wait before it times out.
+const LONG_POLL_GRACE: Duration = Duration::from_secs(10);
+
+/// First and longest pause of [`DefaultRemoteResourceReader::watch`] after a transient
+/// error; the pause doubles with every consecutive failure.
+const WATCH_RETRY_INITIAL: Duration = Duration::from_secs(1);
+const WATCH_RETRY_MAX: Duration = Duration::from_secs(60);
+
+static GLOBAL_OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Switch every remote reader into (or out of) offline mode.
+///
+/// Offline readers never perform network I/O and serve only memory or disk data,
+/// accepting stale data as the best available answer.
+pub fn set_global_offline(offline: bool) {
+    GLOBAL_OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// Whether offline mode is switched on for every remote reader.
+pub fn is_global_offline() -> bool {
+    GLOBAL_OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Result of probing the origin of a remote resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginHealth {
+    /// HTTP status code returned by the origin.
+    pub status: u16,
+    /// Time until the response headers arrived.
+    pub latency: Duration,
+    /// Announced size of the resource body, if the origin reports it.
+    pub content_length: Option<u64>,
+}
+
+impl OriginHealth {
+    /// Whether the origin answered with a success status.
+    pub fn is_healthy(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Probe results of one of the configured mirrors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirrorStats {
+    pub url: Url,
+    /// Latency of the last successful probe.
+    pub latency: Option<Duration>,
+    /// Whether the last probe was answered with a success status.
+    pub healthy: bool,
+    /// Whether requests are currently sent to this mirror.
+    pub active: bool,
+}
+
+struct Mirrors {
+    stats: Vec<MirrorStats>,
+    probe_interval: Duration,
+    probed_at: Option<SystemTime>,
+}
+
+/// Automatic switch into offline-serving mode after repeated connection failures.
+///
+/// Once `failure_threshold` consecutive DNS/connect failures happen, the reader serves
+/// memory or disk data only for `cooldown`; the first call after the cooldown probes
+/// the origin again. Transitions are reported as [`ResourceEvent`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoOfflinePolicy {
+    pub failure_threshold: u32,
+    pub cooldown: Duration,
+}
+
+impl Default for AutoOfflinePolicy {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Increasing pause of requests after the origin returned content that fails to parse.
+///
+/// After the n-th consecutive parse failure no request is sent for `initial * 2^(n-1)`,
+/// at most `max`; reads serve the last good value meanwhile (when stale data is
+/// allowed). A successfully parsed response resets the backoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseBackoff {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl Default for ParseBackoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(300),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ParseFailures {
+    consecutive: u32,
+    /// No request is sent before this moment.
+    paused_until: Option<SystemTime>,
+}
+
+/// Order in which a remote reader consults its sources.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LookupPolicy {
+    /// Memory, then disk, then the network; the first fresh value wins.
+    #[default]
+    CacheFirst,
+    /// Disk, then memory, then the network, for a disk cache shared with (and updated
+    /// by) other processes.
+    DiskFirst,
+    /// The network first; cached data is served only when the request fails, as fresh
+    /// while within its timeout and stale otherwise.
+    NetworkFirst,
+    /// Only the memory cache, which is filled by other means (e.g. a watch or
+    /// [`set_internal_cache`](crate::state_manager::ResourceState::set_internal_cache));
+    /// never touches the disk or the network.
+    MemoryOnly,
+}
+
+#[derive(Clone, Copy)]
+enum CacheSource {
+    Memory,
+    Disk,
+}
+
+/// Signs the requests of a remote reader right before they are sent, e.g. with an HMAC
+/// of the method, path and a timestamp, or a custom authentication header.
+///
+/// Closures taking `&mut reqwest::Request` implement it.
+pub trait RequestSigner: Send + Sync {
+    fn sign(&self, request: &mut reqwest::Request);
+}
+
+impl<F> RequestSigner for F
+where
+    F: Fn(&mut reqwest::Request) + Send + Sync,
+{
+    fn sign(&self, request: &mut reqwest::Request) {
+        self(request)
+    }
+}
+
+/// Response headers captured by default, see [`FetchMetadata`].
+const CAPTURED_HEADERS: [&str; 3] = ["etag", "last-modified", "x-request-id"];
+
+/// Details of the last response received for a remote resource, for debugging and
+/// correlating with origin logs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchMetadata {
+    /// URL that answered, after following redirects.
+    pub url: Url,
+    /// HTTP status code of the response.
+    pub status: u16,
+    /// When the response arrived.
+    pub fetched_at: SystemTime,
+    /// Captured response headers (lowercase names) present in the response.
+    pub headers: Vec<(String, String)>,
+}
+
+impl FetchMetadata {
+    /// Whether the response came from another URL than the one requested.
+    pub fn was_redirected_from(&self, requested: &Url) -> bool {
+        self.url != *requested
+    }
+
+    /// Value of a captured header, by case-insensitive name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Freshness lifetime announced by the origin: `Cache-Control: max-age` less the
+/// `Age` the response already spent in caches, or zero for `no-cache`/`no-store`.
+/// Without `Cache-Control`, the time from `Date` (or `now`) until `Expires`.
+fn origin_lifetime(headers: &header::HeaderMap, now: SystemTime) -> Option<Duration> {
+    let mut max_age = None;
+
+    let directives = headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','));
+
+    for directive in directives {
+        let (name, value) = match directive.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+            None => (directive.trim(), None),
+        };
+
+        if name.eq_ignore_ascii_case("no-cache") || name.eq_ignore_ascii_case("no-store") {
+            return Some(Duration::ZERO);
+        }
+        if name.eq_ignore_ascii_case("max-age") {
+            max_age = value.and_then(|value| value.parse::<u64>().ok());
+        }
+    }
+
+    let age = headers
+        .get(header::AGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+
+    if let Some(max_age) = max_age {
+        return Some(Duration::from_secs(max_age.saturating_sub(age)));
+    }
+
+    let expires = headers.get(header::EXPIRES)?.to_str().ok();
+    // the origin's own `Date` keeps clock skew out of the lifetime
+    let date = headers
+        .get(header::DATE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_http_date)
+        .unwrap_or(now);
+
+    // an invalid date (e.g. `0`) means already expired
+    Some(
+        expires
+            .and_then(parse_http_date)
+            .and_then(|expires| expires.duration_since(date).ok())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// The moment allowed by `Retry-After`, given in seconds or as an HTTP date.
+fn parse_retry_after(headers: &header::HeaderMap, now: SystemTime) -> Option<SystemTime> {
+    let value = headers.get(header::RETRY_AFTER)?.to_str().ok()?.trim();
+
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(now + Duration::from_secs(seconds)),
+        Err(_) => parse_http_date(value),
+    }
+}
+
+/// Response headers identifying a version of the remote document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ResponseValidators {
+    content_length: Option<u64>,
+    last_modified: Option<String>,
+    etag: Option<String>,
+}
+
+impl ResponseValidators {
+    fn from_headers(headers: &header::HeaderMap) -> Self {
+        Self {
+            content_length: headers
+                .get(header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok()),
+            last_modified: headers
+                .get(header::LAST_MODIFIED)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+            etag: headers
+                .get(header::ETAG)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+        }
+    }
+
+    /// Unchanged when every validator known on both sides is equal, and at least one is.
+    fn matches(&self, other: &Self) -> bool {
+        fn compare<V: PartialEq>(a: &Option<V>, b: &Option<V>) -> Option<bool> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a == b),
+                _ => None,
+            }
+        }
+
+        let compared = [
+            compare(&self.content_length, &other.content_length),
+            compare(&self.last_modified, &other.last_modified),
+            compare(&self.etag, &other.etag),
+        ];
+
+        compared.iter().any(Option::is_some) && compared.iter().all(|c| c.unwrap_or(true))
+    }
+}
+
+/// Whether [`DefaultRemoteResourceReader::watch`] retries after `error`.
+fn is_transient(error: &ResourceError) -> bool {
+    matches!(
+        error,
+        ResourceError::Network(_)
+            | ResourceError::ConnectTimeout(_)
+            | ResourceError::RequestTimeout(_)
+            | ResourceError::HttpStatus(429 | 502 | 503 | 504)
+    )
+}
+
+/// Parse a `Content-Range: bytes <start>-<end>/<total>` header into the start offset
+/// and the complete length (unknown for `*`).
+fn parse_content_range(headers: &header::HeaderMap) -> Option<(u64, Option<u64>)> {
+    let value = headers.get(header::CONTENT_RANGE)?.to_str().ok()?;
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _end) = range.split_once('-')?;
+
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()))
+}
+
+#[derive(Default)]
+struct Connectivity {
+    consecutive_failures: u32,
+    /// Set while automatically offline; kept after the cooldown until a probe succeeds.
+    offline_until: Option<SystemTime>,
+}
 
 pub struct DefaultRemoteResourceReader<T> {
-    state: ResourceState<T>,
+    state: Arc<ResourceState<T>>,
+    client: reqwest::Client,
+    offline: AtomicBool,
+    auto_offline: Option<AutoOfflinePolicy>,
+    connectivity: Mutex<Connectivity>,
+    parse_backoff: Option<ParseBackoff>,
+    parse_failures: Mutex<ParseFailures>,
+    head_check: bool,
+    delta_endpoint: Option<String>,
+    /// The last downloaded document as sent by the origin, the base of delta patches.
+    raw_document: Mutex<Option<serde_json::Value>>,
+    resumable: bool,
+    /// `If-Range` validator of the partially downloaded body.
+    partial_validator: Mutex<Option<String>>,
+    validators: Mutex<Option<ResponseValidators>>,
+    mirrors: Option<Mutex<Mirrors>>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    /// Bounds of the origin-announced lifetime, `None` when it is ignored.
+    cache_control: Option<(Duration, Duration)>,
+    /// No request is sent before this moment (`Retry-After`).
+    retry_after: Mutex<Option<SystemTime>>,
+    absent_on_not_found: bool,
+    /// Lowercase names of the response headers kept in [`FetchMetadata`].
+    captured_headers: Vec<String>,
+    last_fetch: Mutex<Option<FetchMetadata>>,
+    lookup_policy: LookupPolicy,
+    /// Longest time a conditional request of [`watch`](Self::watch) may be held open.
+    long_poll_wait: Duration,
+}
+
+impl<T> DefaultRemoteResourceReader<T> {
+    pub fn new(state: impl Into<Arc<ResourceState<T>>>) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(DEFAULT_USER_AGENT)
+            .build()
+            .unwrap_or_default();
+
+        Self::with_client(state, client)
+    }
+
+    /// Create a reader performing its requests through the given HTTP client.
+    pub fn with_client(state: impl Into<Arc<ResourceState<T>>>, client: reqwest::Client) -> Self {
+        Self {
+            state: state.into(),
+            client,
+            offline: AtomicBool::new(false),
+            auto_offline: None,
+            connectivity: Mutex::new(Connectivity::default()),
+            parse_backoff: None,
+            parse_failures: Mutex::new(ParseFailures::default()),
+            head_check: false,
+            delta_endpoint: None,
+            raw_document: Mutex::new(None),
+            resumable: false,
+            partial_validator: Mutex::new(None),
+            validators: Mutex::new(None),
+            mirrors: None,
+            signer: None,
+            cache_control: None,
+            retry_after: Mutex::new(None),
+            absent_on_not_found: false,
+            captured_headers: CAPTURED_HEADERS.map(str::to_string).to_vec(),
+            last_fetch: Mutex::new(None),
+            lookup_policy: LookupPolicy::default(),
+            long_poll_wait: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Describes the reader's state and connectivity, never the cached data, cookies or
+/// captured header values.
+impl<T> std::fmt::Debug for DefaultRemoteResourceReader<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let connectivity = self.connectivity.try_lock().ok();
+        let last_fetch = self.last_fetch.try_lock().ok();
+        let last_fetch = last_fetch.as_deref().and_then(Option::as_ref);
+
+        f.debug_struct("DefaultRemoteResourceReader")
+            .field("state", &self.state)
+            .field("offline", &self.offline.load(Ordering::Relaxed))
+            .field(
+                "consecutive_failures",
+                &connectivity.map(|connectivity| connectivity.consecutive_failures),
+            )
+            .field("lookup_policy", &self.lookup_policy)
+            .field(
+                "mirrors",
+                &self
+                    .mirrors
+                    .as_ref()
+                    .and_then(|mirrors| mirrors.try_lock().ok())
+                    .map(|mirrors| mirrors.stats.len()),
+            )
+            .field("last_status", &last_fetch.map(|fetch| fetch.status))
+            .field(
+                "last_fetched_at",
+                &last_fetch
+                    .map(|fetch| format_timestamp(fetch.fetched_at, &TimestampFormat::Iso8601)),
+            )
+            .finish_non_exhaustive()
+    }
 }
 
 impl<T> DefaultRemoteResourceReader<T> {
-    pub fn new(state: ResourceState<T>) -> Self {
-        Self { state }
+    /// Longest time the origin may hold a conditional request of [`watch`](Self::watch)
+    /// open (defaults to 60 seconds); also the polling interval for origins answering
+    /// at once.
+    ///
+    /// Conditional requests time out 10 seconds after the wait instead of after the
+    /// client's request timeout, so a shorter request timeout does not cut them off.
+    pub fn with_long_poll(mut self, wait: Duration) -> Self {
+        self.long_poll_wait = wait;
+        self
+    }
+
+    /// Set the order in which memory, disk and network are consulted.
+    pub fn with_lookup_policy(mut self, policy: LookupPolicy) -> Self {
+        self.lookup_policy = policy;
+        self
+    }
+
+    /// Issue a cheap `HEAD` request before downloading stale data again.
+    ///
+    /// When `Content-Length` and `Last-Modified` match the last download, the cached
+    /// data is renewed without transferring the body. Useful for very large
+    /// resources on weak links.
+    pub fn with_head_check(mut self, enabled: bool) -> Self {
+        self.head_check = enabled;
+        self
+    }
+
+    /// Fetch JSON Patch (RFC 6902) deltas instead of the full document when possible.
+    ///
+    /// The template is the patch endpoint with a `{base_version}` placeholder, which is
+    /// replaced by the `ETag` of the cached version (without quotes), e.g.
+    /// `https://example.com/config/patch?since={base_version}`. The origin answers with
+    /// a JSON Patch array and the `ETag` of the patched version, or `304 Not Modified`.
+    ///
+    /// The patch is applied to the last document downloaded by this reader, which is
+    /// then parsed like a download (schema mode, transforms, redaction). The first
+    /// refresh after a start downloads the full document, and any failure falls back
+    /// to it as well.
+    pub fn with_delta_endpoint(mut self, template: impl Into<String>) -> Self {
+        self.delta_endpoint = Some(template.into());
+        self
+    }
+
+    /// Keep the body received by an interrupted download in a partial file next to the
+    /// disk cache and resume it with a `Range` request on the next attempt. The partial
+    /// file is written through the resource's file system, with the file mode and
+    /// encryption of the disk cache.
+    ///
+    /// Resuming is guarded by `If-Range` with the `ETag` (or `Last-Modified`) of the
+    /// interrupted response, so a document changed in between is downloaded from the
+    /// start. The complete body is checked against the announced length before it is
+    /// parsed and stored in the cache.
+    pub fn with_resumable_downloads(mut self, enabled: bool) -> Self {
+        self.resumable = enabled;
+        self
+    }
+
+    /// Derive the memory cache lifetime from the response's `Cache-Control: max-age`
+    /// (less its `Age`) or, without `Cache-Control`, its `Expires` header, clamped to
+    /// `min..=max`; `no-cache` and `no-store` count as a zero lifetime. Responses
+    /// without either keep the resource timeout.
+    pub fn with_cache_control(mut self, min: Duration, max: Duration) -> Self {
+        self.cache_control = Some((min, max));
+        self
+    }
+
+    /// Answer [`DataResult::Absent`] when the origin responds `404 Not Found` or
+    /// `410 Gone`, instead of failing (or serving stale data), so a deliberately empty
+    /// resource is distinguishable from an outage. Absence is not cached.
+    pub fn with_absent_on_not_found(mut self, enabled: bool) -> Self {
+        self.absent_on_not_found = enabled;
+        self
+    }
+
+    /// Keep these response headers in [`last_fetch`](Self::last_fetch), on top of
+    /// `ETag`, `Last-Modified` and `X-Request-Id`.
+    pub fn with_captured_headers<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for name in names {
+            let name = name.as_ref().to_ascii_lowercase();
+            if !self.captured_headers.contains(&name) {
+                self.captured_headers.push(name);
+            }
+        }
+        self
+    }
+
+    /// Status and captured headers of the last response to a download, successful or
+    /// not.
+    pub fn last_fetch(&self) -> Option<FetchMetadata> {
+        self.last_fetch
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Sign every request (downloads, probes and `HEAD` checks) with the given signer.
+    pub fn with_request_signer(mut self, signer: Arc<dyn RequestSigner>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Enable the automatic offline mode with the given policy.
+    pub fn with_auto_offline(mut self, policy: AutoOfflinePolicy) -> Self {
+        self.auto_offline = Some(policy);
+        self
+    }
+
+    /// Back off from the origin while its content fails to parse, see [`ParseBackoff`].
+    pub fn with_parse_backoff(mut self, backoff: ParseBackoff) -> Self {
+        self.parse_backoff = Some(backoff);
+        self
+    }
+
+    /// Switch this reader into (or out of) offline mode, see [`set_global_offline`].
+    pub fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::Relaxed);
+    }
+
+    /// Whether this reader is offline, either on its own, through the global switch
+    /// or because the automatic offline mode is cooling down.
+    pub fn is_offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed) || is_global_offline() || self.is_auto_offline()
+    }
+
+    fn is_auto_offline(&self) -> bool {
+        self.connectivity
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .offline_until
+            .is_some_and(|until| self.state.now() < until)
     }
 }
 
@@ -23,7 +591,7 @@ impl<T> ResourceReader<T> for DefaultRemoteResourceReader<T>
 where
     T: Send + Sync + DeserializeOwned + Serialize + Default,
 {
-    fn get_state(&self) -> &ResourceState<T> {
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
         &self.state
     }
 
@@ -31,98 +599,2150 @@ where
         &self,
         allow_stale: bool,
     ) -> Result<DataResult<Arc<T>>, ResourceError> {
-        let mut stale_internal_data: Option<Arc<T>> = None;
-        let mut stale_internal_data_timestamp: Option<SystemTime> = None;
-        let mut stale_disk_cached_data: Option<Arc<T>> = None;
-        let mut stale_disk_cached_data_timestamp: Option<SystemTime> = None;
+        let result = self
+            .load_data(allow_stale, None)
+            .await
+            .and_then(|data| self.get_state().limit_staleness(data));
 
-        if !self.get_state().is_marked_stale()? {
-            ///////////////////////////////////////////
-            // 1. Check current internal state first //
-            ///////////////////////////////////////////
+        if let Err(error) = &result {
+            self.get_state().record_error(error);
+        }
 
-            if let Some((data, fresh, timestamp)) = self.get_state().get_internal_data()? {
-                if fresh {
-                    // timestamp based
-                    return Ok(DataResult::Fresh(data));
-                }
-                stale_internal_data = Some(data);
-                stale_internal_data_timestamp = Some(timestamp);
-            }
+        result
+    }
+}
 
-            ///////////////////////////////////
-            // 2. Check on disk cached state //
-            ///////////////////////////////////
+impl<T> DefaultRemoteResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    /// Serve the resource from the lowest-latency healthy URL among the resource URL
+    /// and the given mirrors.
+    ///
+    /// The URLs are probed concurrently on the first fetch and again once
+    /// `probe_interval` has elapsed or a download failed; a probe not answered within
+    /// 5 seconds counts as unhealthy. Every probe is reported as
+    /// [`ResourceEvent::MirrorProbed`] and switching to another mirror as
+    /// [`ResourceEvent::MirrorSelected`]; the latest results are also available through
+    /// [`mirror_stats`](Self::mirror_stats).
+    pub fn with_mirrors(mut self, mirrors: Vec<Url>, probe_interval: Duration) -> Self {
+        let stats = std::iter::once(self.state.get_url().to_owned())
+            .chain(mirrors)
+            .enumerate()
+            .map(|(index, url)| MirrorStats {
+                url,
+                latency: None,
+                healthy: false,
+                active: index == 0,
+            })
+            .collect();
 
-            if let Some((data, fresh, timestamp)) = self.get_state().get_disk_cached_data()? {
-                if fresh {
-                    // timestamp based
-                    return Ok(DataResult::Fresh(data));
-                }
-                stale_disk_cached_data = Some(data);
-                stale_disk_cached_data_timestamp = Some(timestamp);
-            }
+        self.mirrors = Some(Mutex::new(Mirrors {
+            stats,
+            probe_interval,
+            probed_at: None,
+        }));
+        self
+    }
+
+    /// Probe results of the configured mirrors, empty when none are configured.
+    pub fn mirror_stats(&self) -> Vec<MirrorStats> {
+        self.mirrors
+            .as_ref()
+            .map(|mirrors| {
+                mirrors
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .stats
+                    .clone()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Until when requests are held back because the origin answered 429 or 503 with
+    /// `Retry-After`, `None` when requests are allowed.
+    pub fn retry_after(&self) -> Option<SystemTime> {
+        self.retry_after
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .filter(|until| self.get_state().now() < *until)
+    }
+
+    /// Until when requests are paused after repeated parse failures, `None` when
+    /// requests are allowed.
+    pub fn parse_backoff_until(&self) -> Option<SystemTime> {
+        self.parse_failures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .paused_until
+            .filter(|until| self.get_state().now() < *until)
+    }
+
+    /// Count consecutive parse failures and pause requests accordingly.
+    fn track_parse_failures(&self, failed: bool) {
+        let Some(backoff) = self.parse_backoff else {
+            return;
+        };
+        let mut failures = self
+            .parse_failures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if !failed {
+            *failures = ParseFailures::default();
+            return;
         }
 
-        /////////////////////////////////////////////////////////////////
-        // 3. Data member is either stale or not available; refreshing //
-        /////////////////////////////////////////////////////////////////
+        failures.consecutive = failures.consecutive.saturating_add(1);
+        let pause = backoff
+            .initial
+            .saturating_mul(1 << (failures.consecutive - 1).min(31))
+            .min(backoff.max);
+        failures.paused_until = Some(self.get_state().now() + pause);
+    }
 
-        let fresh_data_from_server: Option<Arc<T>> =
-            match reqwest::get(self.get_state().get_url().to_owned()).await {
-                Ok(resp) => {
-                    match resp.text().await {
-                        Ok(body) => {
-                            // Try to parse as JSON or YAML depending on file_type
-                            match &self.get_state().get_file_type() {
-                                ResourceFileType::Json => {
-                                    serde_json::from_str(&body).ok().map(Arc::new)
-                                }
-                                ResourceFileType::Yaml => {
-                                    serde_yaml::from_str(&body).ok().map(Arc::new)
-                                }
-                                _ => None,
-                            }
-                        }
-                        Err(_) => None,
-                    }
-                }
-                Err(_) => None,
-            };
+    /// The URL requests are sent to: the selected mirror, or the resource URL.
+    fn active_url(&self) -> Url {
+        self.mirrors
+            .as_ref()
+            .and_then(|mirrors| {
+                let mirrors = mirrors.lock().unwrap_or_else(PoisonError::into_inner);
+                mirrors
+                    .stats
+                    .iter()
+                    .find(|mirror| mirror.active)
+                    .map(|mirror| mirror.url.clone())
+            })
+            .unwrap_or_else(|| self.state.get_url().to_owned())
+    }
+
+    /// Probe the origin with a `HEAD` request (falling back to `GET` when `HEAD` is not
+    /// allowed) and report its status, latency and content length.
+    ///
+    /// Neither the memory nor the disk cache is touched, which makes this suitable
+    /// for readiness probes.
+    pub async fn check_origin(&self) -> Result<OriginHealth, ResourceError> {
+        self.probe(self.active_url()).await
+    }
+
+    /// Like [`get_data_or_error`](ResourceReader::get_data_or_error), but a download goes
+    /// to `url` instead of the resource URL, e.g. a short-lived pre-signed URL. The data
+    /// is cached under the resource's usual identity.
+    pub async fn get_data_from(
+        &self,
+        url: &Url,
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        let result = self
+            .load_data(allow_stale, Some(url))
+            .await
+            .and_then(|data| self.get_state().limit_staleness(data));
+
+        if let Err(error) = &result {
+            self.get_state().record_error(error);
+        }
+
+        result
+    }
+
+    /// Ask the origin whether the document changed since the last download, with a
+    /// conditional `GET` (`If-None-Match` with the last `ETag`), and store the new
+    /// version when it did; returns whether it changed.
+    ///
+    /// The request carries `Prefer: wait=<seconds>` so origins supporting long polling
+    /// hold it until the document changes, up to the [long poll
+    /// wait](Self::with_long_poll), and times out 10 seconds after that wait.
+    ///
+    /// The new version is stored like a regular refresh: with the lifetime announced by
+    /// the origin (when enabled), unless newer data was stored meanwhile.
+    ///
+    /// Fails with [`ResourceError::Offline`] without a request while the reader is
+    /// [offline](Self::is_offline), and with [`ResourceError::RetryLater`] or
+    /// [`ResourceError::ParseBackoff`] while requests are held back. An inline `data:`
+    /// URL never changes, `false` is returned without a request.
+    pub async fn wait_for_change(&self) -> Result<bool, ResourceError> {
+        if self.is_inline() {
+            return Ok(false);
+        }
+
+        if self.is_offline() {
+            return Err(ResourceError::Offline);
+        }
+
+        if let Some(until) = self.retry_after() {
+            let remaining = until
+                .duration_since(self.get_state().now())
+                .unwrap_or_default();
+            return Err(ResourceError::RetryLater(remaining));
+        }
+
+        if let Some(until) = self.parse_backoff_until() {
+            let remaining = until
+                .duration_since(self.get_state().now())
+                .unwrap_or_default();
+            return Err(ResourceError::ParseBackoff(remaining));
+        }
+
+        let generation = self.get_state().generation()?;
+        let etag = self.last_etag();
+
+        let mut request = self
+            .request(Method::GET, self.active_url())
+            .header(
+                "Prefer",
+                format!("wait={}", self.long_poll_wait.as_secs().max(1)),
+            )
+            .timeout(self.long_poll_wait + LONG_POLL_GRACE);
+        if let Some(etag) = etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+
+        let response = self.send(request).await.map_err(ResourceError::network);
+        self.track_connectivity(response.as_ref().err());
+        let response = response?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(false);
+        }
+        self.record_fetch(&response);
+        if !response.status().is_success() {
+            return Err(self.reject_status(&response));
+        }
+
+        let validators = ResponseValidators::from_headers(response.headers());
+        let lifetime = origin_lifetime(response.headers(), self.get_state().now());
+        let body = response.text().await.map_err(ResourceError::network)?;
+        let parsed = self.parse_body(&body);
+        self.track_parse_failures(parsed.is_err());
+        let data = parsed?;
+
+        self.remember_validators(validators);
+        self.remember_document(&body);
+        self.get_state()
+            .store_refreshed_for(generation, data, self.bound_lifetime(lifetime))?;
+
+        Ok(true)
+    }
+
+    /// Keep the resource up to date with [conditional requests](Self::wait_for_change)
+    /// so readers see changes as soon as the origin reports them instead of when the
+    /// cache expires. Origins answering at once are polled every [long poll
+    /// wait](Self::with_long_poll).
+    ///
+    /// Network errors, timeouts and overloaded origins (`429`, `502`, `503`, `504`) are
+    /// recorded and retried after a pause starting at one second and doubling up to a
+    /// minute, or after the origin's `Retry-After`. Any other error ends the watch.
+    ///
+    /// While the reader is [offline](Self::is_offline) no requests are sent; the watch
+    /// checks again every long poll wait. After repeated parse failures it waits out
+    /// the [parse backoff](Self::with_parse_backoff). The watch of an inline `data:`
+    /// URL, which never changes, ends at once.
+    ///
+    /// Meant to run in a spawned task next to the readers of the resource.
+    pub async fn watch(&self) -> Result<(), ResourceError> {
+        if self.is_inline() {
+            return Ok(());
+        }
+
+        let mut retry_delay = WATCH_RETRY_INITIAL;
 
-        if fresh_data_from_server.is_none() && allow_stale {
-            match (stale_internal_data, stale_disk_cached_data) {
-                (Some(internal), Some(disk)) => {
-                    // Both stale sources available, return the newer one
-                    if stale_disk_cached_data_timestamp > stale_internal_data_timestamp {
-                        return Ok(DataResult::Stale(disk));
+        loop {
+            let started = Instant::now();
+
+            match self.wait_for_change().await {
+                Ok(true) => retry_delay = WATCH_RETRY_INITIAL,
+                Ok(false) => {
+                    retry_delay = WATCH_RETRY_INITIAL;
+                    let elapsed = started.elapsed();
+                    if elapsed < self.long_poll_wait {
+                        tokio::time::sleep(self.long_poll_wait - elapsed).await;
                     }
-                    return Ok(DataResult::Stale(internal));
                 }
-                (Some(internal), None) => {
-                    // Only internal cache available
-                    return Ok(DataResult::Stale(internal));
+                Err(
+                    ResourceError::RetryLater(remaining) | ResourceError::ParseBackoff(remaining),
+                ) => {
+                    tokio::time::sleep(remaining).await;
                 }
-                (None, Some(disk)) => {
-                    // Only disk cache available
-                    return Ok(DataResult::Stale(disk));
+                Err(ResourceError::Offline) => {
+                    tokio::time::sleep(self.long_poll_wait).await;
                 }
-                (None, None) => {
-                    // No stale data available, continue next ti fresh data
+                Err(error) => {
+                    self.get_state().record_error(&error);
+                    if !is_transient(&error) {
+                        return Err(error);
+                    }
+
+                    tokio::time::sleep(retry_delay).await;
+                    retry_delay = (retry_delay * 2).min(WATCH_RETRY_MAX);
                 }
             }
         }
+    }
+
+    /// Prepare a request; cookies are handled by the client's cookie provider.
+    fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    /// Send a request, signed by the request signer.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, reqwest::Error> {
+        match &self.signer {
+            Some(signer) => {
+                let mut request = request.build()?;
+                signer.sign(&mut request);
+                self.client.execute(request).await
+            }
+            None => request.send().await,
+        }
+    }
 
-        let fresh_data = fresh_data_from_server.ok_or(ResourceError::UnableToFreshData)?;
+    async fn probe(&self, url: Url) -> Result<OriginHealth, ResourceError> {
+        let started = Instant::now();
 
-        save_to_disk_override(
-            &*fresh_data,
-            self.get_state().get_file_path().as_ref(),
-            self.get_state().get_file_type(),
-        )?;
+        let mut response = self
+            .send(self.request(Method::HEAD, url.clone()))
+            .await
+            .map_err(ResourceError::network)?;
 
-        self.get_state().set_internal_cache(fresh_data.clone())?;
+        if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+            response = self
+                .send(self.request(Method::GET, url))
+                .await
+                .map_err(ResourceError::network)?;
+        }
 
-        Ok(DataResult::Fresh(fresh_data))
+        let latency = started.elapsed();
+        let content_length = response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+
+        Ok(OriginHealth {
+            status: response.status().as_u16(),
+            latency,
+            content_length,
+        })
+    }
+
+    /// Whether the payload is inlined in a `data:` URL and needs no network at all.
+    fn is_inline(&self) -> bool {
+        self.get_state().get_url().scheme() == "data"
+    }
+
+    /// Download and parse the document, along with the cache lifetime announced by
+    /// the origin (when enabled).
+    async fn fetch_from_server(
+        &self,
+        url_override: Option<&Url>,
+    ) -> Result<(Arc<T>, Option<Duration>), ResourceError> {
+        if self.is_inline() && url_override.is_none() {
+            let content = decode_data_url(self.get_state().get_url())?;
+            return self
+                .get_state()
+                .parse(&content)
+                .map(|data| (Arc::new(data), None));
+        }
+
+        if let Some(until) = self.retry_after() {
+            let remaining = until
+                .duration_since(self.get_state().now())
+                .unwrap_or_default();
+            return Err(ResourceError::RetryLater(remaining));
+        }
+
+        if let Some(until) = self.parse_backoff_until() {
+            let remaining = until
+                .duration_since(self.get_state().now())
+                .unwrap_or_default();
+            return Err(ResourceError::ParseBackoff(remaining));
+        }
+
+        #[cfg(any(test, feature = "testing"))]
+        self.get_state().inject(Fault::Network)?;
+
+        let url = url_override.cloned().unwrap_or_else(|| self.active_url());
+        let (body, validators, lifetime) = if self.resumable {
+            self.download_resumable(url).await?
+        } else {
+            let response = self
+                .send(self.request(Method::GET, url))
+                .await
+                .map_err(ResourceError::network)?;
+            self.record_fetch(&response);
+
+            if !response.status().is_success() {
+                return Err(self.reject_status(&response));
+            }
+
+            let validators = ResponseValidators::from_headers(response.headers());
+            let lifetime = origin_lifetime(response.headers(), self.get_state().now());
+            (
+                response.text().await.map_err(ResourceError::network)?,
+                validators,
+                lifetime,
+            )
+        };
+
+        let parsed = self.parse_body(&body);
+        self.track_parse_failures(parsed.is_err());
+        let data = parsed?;
+
+        self.remember_validators(validators);
+        self.remember_document(&body);
+
+        Ok((data, self.bound_lifetime(lifetime)))
+    }
+
+    /// Keep the downloaded document as the base of the next delta patch.
+    fn remember_document(&self, body: &str) {
+        if self.delta_endpoint.is_some() {
+            let document =
+                parse_content::<serde_json::Value>(body, self.get_state().get_file_type()).ok();
+            *self
+                .raw_document
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = document;
+        }
+    }
+
+    /// Clamp the origin-announced lifetime to the configured bounds, `None` when it is
+    /// ignored.
+    fn bound_lifetime(&self, lifetime: Option<Duration>) -> Option<Duration> {
+        self.cache_control
+            .and_then(|(min, max)| lifetime.map(|lifetime| lifetime.max(min).min(max)))
+    }
+
+    fn parse_body(&self, body: &str) -> Result<Arc<T>, ResourceError> {
+        #[cfg(any(test, feature = "testing"))]
+        self.get_state().inject(Fault::Parse)?;
+
+        self.get_state().parse(body).map(Arc::new)
+    }
+
+    /// Keep the status and captured headers of a download response.
+    fn record_fetch(&self, response: &Response) {
+        let headers = self
+            .captured_headers
+            .iter()
+            .filter_map(|name| {
+                let value = response.headers().get(name.as_str())?.to_str().ok()?;
+                Some((name.clone(), value.to_string()))
+            })
+            .collect();
+
+        *self
+            .last_fetch
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(FetchMetadata {
+            url: response.url().clone(),
+            status: response.status().as_u16(),
+            fetched_at: self.get_state().now(),
+            headers,
+        });
+    }
+
+    /// Error for an unsuccessful response; 429 and 503 with `Retry-After` hold back
+    /// further requests until the announced moment.
+    fn reject_status(&self, response: &Response) -> ResourceError {
+        let status = response.status();
+
+        if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
+            let until = parse_retry_after(response.headers(), self.get_state().now());
+            if let Some(until) = until {
+                *self
+                    .retry_after
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner) = Some(until);
+            }
+        }
+
+        ResourceError::HttpStatus(status.as_u16())
+    }
+
+    fn partial_download_path(&self) -> PathBuf {
+        self.get_state()
+            .get_cache_directory()
+            .join(format!(".{}.part", self.get_state().get_cache_file_name()))
+    }
+
+    /// Download the body into the partial file, continuing where the previous
+    /// attempt stopped when the origin still serves the same version.
+    async fn download_resumable(
+        &self,
+        url: Url,
+    ) -> Result<(String, ResponseValidators, Option<Duration>), ResourceError> {
+        let part_path = self.partial_download_path();
+        let validator = self
+            .partial_validator
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+
+        // read through the file system, which decrypts, to resume after the plain content
+        let partial = match &validator {
+            Some(_) => self
+                .get_state()
+                .file_system()
+                .read(&part_path)
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+        let resume_from = partial.len() as u64;
+
+        let mut request = self.request(Method::GET, url);
+        if let (Some(validator), true) = (&validator, resume_from > 0) {
+            request = request
+                .header(header::RANGE, format!("bytes={}-", resume_from))
+                .header(header::IF_RANGE, validator);
+        }
+
+        let mut response = self.send(request).await.map_err(ResourceError::network)?;
+        self.record_fetch(&response);
+
+        let mut validators = ResponseValidators::from_headers(response.headers());
+        let lifetime = origin_lifetime(response.headers(), self.get_state().now());
+        let (offset, total) = if response.status() == StatusCode::PARTIAL_CONTENT {
+            match parse_content_range(response.headers()) {
+                Some((start, total)) if start == resume_from => (start, total),
+                _ => return Err(self.discard_partial_download("unexpected Content-Range")),
+            }
+        } else if response.status().is_success() {
+            (0, validators.content_length)
+        } else {
+            if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+                let _ = self.discard_partial_download("range not satisfiable");
+            }
+            return Err(self.reject_status(&response));
+        };
+        validators.content_length = total;
+
+        // weak entity tags are not allowed in `If-Range`
+        *self
+            .partial_validator
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = validators
+            .etag
+            .clone()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or_else(|| validators.last_modified.clone());
+
+        let mut body = match offset {
+            0 => Vec::new(),
+            _ => partial,
+        };
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                Ok(None) => break,
+                Err(error) => {
+                    // an interrupted stream leaves the partial file for the next attempt
+                    self.save_partial_download(&part_path, &body)?;
+                    return Err(ResourceError::network(error));
+                }
+            }
+        }
+
+        if total.is_some_and(|total| total != body.len() as u64) {
+            return Err(self.discard_partial_download("length does not match the origin"));
+        }
+        let _ = self.discard_partial_download("");
+
+        let body = String::from_utf8(body).map_err(|error| {
+            ResourceError::io(io::Error::new(io::ErrorKind::InvalidData, error))
+        })?;
+        Ok((body, validators, lifetime))
+    }
+
+    /// Write the body received so far with the permissions of the disk cache files.
+    fn save_partial_download(&self, part_path: &Path, body: &[u8]) -> Result<(), ResourceError> {
+        let file_system = self.get_state().file_system();
+
+        if let Some(parent) = part_path.parent() {
+            file_system
+                .create_dir_all(parent)
+                .map_err(ResourceError::io)?;
+        }
+        match self.get_state().get_file_mode() {
+            Some(mode) => file_system.write_with_mode(part_path, body, mode),
+            None => file_system.write(part_path, body),
+        }
+        .map_err(ResourceError::io)
+    }
+
+    /// Remove the partial file so the next attempt starts from scratch.
+    fn discard_partial_download(&self, reason: &str) -> ResourceError {
+        let _ = self
+            .get_state()
+            .file_system()
+            .remove_file(&self.partial_download_path());
+
+        *self
+            .partial_validator
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+
+        ResourceError::io(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Incomplete download: {}", reason),
+        ))
+    }
+
+    /// Keep the validators of a download, and its `ETag` for the metadata sidecar of the
+    /// disk cache entry.
+    fn remember_validators(&self, validators: ResponseValidators) {
+        self.get_state().set_etag(validators.etag.clone());
+        *self
+            .validators
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(validators);
+    }
+
+    /// `ETag` of the last download, or after a restart the one recorded in the
+    /// metadata sidecar of the disk cache.
+    fn last_etag(&self) -> Option<String> {
+        let etag = self
+            .validators
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .and_then(|validators| validators.etag.clone());
+
+        etag.or_else(|| self.get_state().disk_metadata().ok()??.etag)
+    }
+
+    /// Compare the origin's `HEAD` response with the validators of the last download.
+    async fn is_unchanged_at_origin(&self) -> bool {
+        let Some(last_validators) = self
+            .validators
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+        else {
+            return false;
+        };
+
+        match self
+            .send(self.request(Method::HEAD, self.active_url()))
+            .await
+        {
+            Ok(response) if response.status().is_success() => {
+                last_validators.matches(&ResponseValidators::from_headers(response.headers()))
+            }
+            _ => false,
+        }
+    }
+
+    /// Bring the last downloaded document up to date by applying a patch fetched from
+    /// the delta endpoint and parse it; `None` whenever the full document has to be
+    /// downloaded instead.
+    async fn fetch_delta(&self) -> Option<(Arc<T>, Option<Duration>)> {
+        let template = self.delta_endpoint.as_ref()?;
+        if self.retry_after().is_some() || self.parse_backoff_until().is_some() {
+            // the full download reports why no request is sent
+            return None;
+        }
+
+        let document = self
+            .raw_document
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()?;
+        let base_version = self
+            .last_etag()?
+            .trim_start_matches("W/")
+            .trim_matches('"')
+            .to_string();
+
+        let encoded = percent_encoding::utf8_percent_encode(
+            &base_version,
+            percent_encoding::NON_ALPHANUMERIC,
+        );
+        let url = Url::parse(&template.replace("{base_version}", &encoded.to_string())).ok()?;
+
+        let response = self.send(self.request(Method::GET, url)).await.ok()?;
+        self.record_fetch(&response);
+        let status = response.status();
+        let validators = ResponseValidators::from_headers(response.headers());
+        let lifetime = origin_lifetime(response.headers(), self.get_state().now());
+
+        let patched = if status == StatusCode::NOT_MODIFIED {
+            document
+        } else if status.is_success() {
+            let operations: Vec<PatchOperation> = response.json().await.ok()?;
+            apply_patch(&document, &operations).ok()?
+        } else {
+            return None;
+        };
+
+        // a patched document the payload type rejects is downloaded in full, whose
+        // parse failures count
+        let data = self.get_state().parse_value(patched.clone()).ok()?;
+        self.track_parse_failures(false);
+
+        if status != StatusCode::NOT_MODIFIED {
+            // the patch response describes the patch body, only its version applies
+            self.remember_validators(ResponseValidators {
+                content_length: None,
+                last_modified: None,
+                etag: validators.etag,
+            });
+            *self
+                .raw_document
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some(patched);
+        }
+
+        Some((Arc::new(data), self.bound_lifetime(lifetime)))
+    }
+
+    /// Probe every mirror when due and switch to the lowest-latency healthy one.
+    async fn select_mirror(&self) {
+        let Some(mirrors) = &self.mirrors else {
+            return;
+        };
+
+        let urls: Vec<Url> = {
+            let now = self.get_state().now();
+            let mut mirrors = mirrors.lock().unwrap_or_else(PoisonError::into_inner);
+            if mirrors.probed_at.is_some_and(|at| {
+                now.duration_since(at).unwrap_or_default() < mirrors.probe_interval
+            }) {
+                return;
+            }
+            // claimed before probing so concurrent loads do not probe as well
+            mirrors.probed_at = Some(now);
+            mirrors
+                .stats
+                .iter()
+                .map(|mirror| mirror.url.clone())
+                .collect()
+        };
+
+        let probes = join_all(urls.into_iter().map(|url| async move {
+            tokio::time::timeout(MIRROR_PROBE_TIMEOUT, self.probe(url))
+                .await
+                .ok()
+                .and_then(Result::ok)
+        }))
+        .await;
+
+        let mut mirrors = mirrors.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let mut events = Vec::with_capacity(probes.len() + 1);
+        for (mirror, probe) in mirrors.stats.iter_mut().zip(&probes) {
+            mirror.healthy = probe.as_ref().is_some_and(OriginHealth::is_healthy);
+            mirror.latency = probe.as_ref().map(|health| health.latency);
+            events.push(ResourceEvent::MirrorProbed {
+                resource: self.get_state().get_file_name().to_string(),
+                url: mirror.url.to_string(),
+                latency: mirror.latency,
+                healthy: mirror.healthy,
+            });
+        }
+
+        let fastest = mirrors
+            .stats
+            .iter()
+            .enumerate()
+            .filter(|(_, mirror)| mirror.healthy)
+            .min_by_key(|(_, mirror)| mirror.latency)
+            .map(|(index, _)| index);
+
+        // with no healthy mirror the current choice is kept
+        if let Some(fastest) = fastest.filter(|&fastest| !mirrors.stats[fastest].active) {
+            for (index, mirror) in mirrors.stats.iter_mut().enumerate() {
+                mirror.active = index == fastest;
+            }
+
+            events.push(ResourceEvent::MirrorSelected {
+                resource: self.get_state().get_file_name().to_string(),
+                url: mirrors.stats[fastest].url.to_string(),
+                latency: mirrors.stats[fastest].latency.unwrap_or_default(),
+            });
+        }
+        drop(mirrors);
+
+        for event in events {
+            self.get_state().emit(event);
+        }
+    }
+
+    /// Probe the mirrors again on the next load.
+    fn reprobe_mirrors(&self) {
+        if let Some(Ok(mut mirrors)) = self.mirrors.as_ref().map(Mutex::lock) {
+            mirrors.probed_at = None;
+        }
+    }
+
+    /// Count consecutive connection failures and switch the automatic offline mode
+    /// on or off accordingly.
+    fn track_connectivity(&self, failure: Option<&ResourceError>) {
+        let Some(policy) = self.auto_offline else {
+            return;
+        };
+        let mut connectivity = self
+            .connectivity
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let event = match failure {
+            Some(ResourceError::Network(error) | ResourceError::ConnectTimeout(error))
+                if error.is_connect() =>
+            {
+                connectivity.consecutive_failures += 1;
+
+                if connectivity.consecutive_failures < policy.failure_threshold {
+                    None
+                } else {
+                    let was_offline = connectivity.offline_until.is_some();
+                    connectivity.offline_until = Some(self.get_state().now() + policy.cooldown);
+
+                    (!was_offline).then(|| ResourceEvent::WentOffline {
+                        resource: self.get_state().get_file_name().to_string(),
+                        consecutive_failures: connectivity.consecutive_failures,
+                        cooldown: policy.cooldown,
+                    })
+                }
+            }
+            // no request was sent
+            Some(ResourceError::RetryLater(_) | ResourceError::ParseBackoff(_)) => None,
+            _ => {
+                // the origin was reached, whatever the outcome of the request
+                connectivity.consecutive_failures = 0;
+
+                connectivity
+                    .offline_until
+                    .take()
+                    .map(|_| ResourceEvent::BackOnline {
+                        resource: self.get_state().get_file_name().to_string(),
+                    })
+            }
+        };
+
+        drop(connectivity);
+
+        if let Some(event) = event {
+            self.get_state().emit(event);
+        }
+    }
+
+    /// Serve the newest memory or disk data without touching the network.
+    fn load_offline_data(&self) -> Result<DataResult<Arc<T>>, ResourceError> {
+        let (data, fresh) = self
+            .get_state()
+            .get_cached_data()?
+            .ok_or(ResourceError::Offline)?;
+        self.get_state().record_hit();
+
+        if fresh {
+            Ok(DataResult::Fresh(data))
+        } else {
+            Ok(DataResult::Stale(data))
+        }
+    }
+
+    /// Serve the memory cache only, see [`LookupPolicy::MemoryOnly`].
+    fn load_memory_data(&self, allow_stale: bool) -> Result<DataResult<Arc<T>>, ResourceError> {
+        match self.get_state().get_internal_data()? {
+            Some((data, true, _)) if !self.get_state().is_marked_stale()? => {
+                self.get_state().record_hit();
+                Ok(DataResult::Fresh(data))
+            }
+            Some((data, _, _)) if allow_stale => {
+                self.get_state().record_hit();
+                Ok(DataResult::Stale(data))
+            }
+            _ => {
+                self.get_state().record_miss();
+                Err(ResourceError::StaleInternalNone)
+            }
+        }
+    }
+
+    /// Serve the cache or refresh it, downloading from `url_override` instead of the
+    /// resource (or mirror) URL when given.
+    async fn load_data(
+        &self,
+        allow_stale: bool,
+        url_override: Option<&Url>,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        if self.is_offline() && !self.is_inline() {
+            return self.load_offline_data();
+        }
+
+        if self.lookup_policy == LookupPolicy::MemoryOnly {
+            return self.load_memory_data(allow_stale);
+        }
+
+        // an inline payload is decoded again rather than copied to the disk cache
+        let inline = self.is_inline() && url_override.is_none();
+        let mut cached: Vec<(Arc<T>, SystemTime)> = Vec::new();
+        // fresh cached data held back until the network was tried (network first)
+        let mut fresh_fallback: Option<Arc<T>> = None;
+        let mut memory_lookup = CacheLookup::Skipped;
+        let mut disk_lookup = CacheLookup::Skipped;
+
+        if !self.get_state().is_marked_stale()? {
+            //////////////////////////////////////////////////////////
+            // 1. Check the internal and the on disk cached state,  //
+            //    in the order of the lookup policy                 //
+            //////////////////////////////////////////////////////////
+
+            let sources = match self.lookup_policy {
+                LookupPolicy::DiskFirst => [CacheSource::Disk, CacheSource::Memory],
+                _ => [CacheSource::Memory, CacheSource::Disk],
+            };
+
+            for source in sources {
+                let found = match source {
+                    CacheSource::Memory => {
+                        memory_lookup = CacheLookup::Empty;
+                        self.get_state().get_internal_data()?
+                    }
+                    CacheSource::Disk if inline => continue,
+                    CacheSource::Disk => {
+                        disk_lookup = CacheLookup::Empty;
+                        self.get_state().get_disk_cached_data()?
+                    }
+                };
+                let Some((data, fresh, timestamp)) = found else {
+                    continue;
+                };
+
+                if fresh {
+                    // timestamp based
+                    if self.lookup_policy != LookupPolicy::NetworkFirst {
+                        self.get_state().record_hit();
+                        return Ok(DataResult::Fresh(data));
+                    }
+                    fresh_fallback.get_or_insert_with(|| Arc::clone(&data));
+                }
+                cached.push((data, timestamp));
+            }
+        }
+
+        /////////////////////////////////////////////////////////////////
+        // 2. Data member is either stale or not available; refreshing //
+        /////////////////////////////////////////////////////////////////
+
+        // Both stale sources may be available, prefer the newer one (the first on a tie)
+        let newest_stale_data = cached
+            .into_iter()
+            .reduce(|newest, next| if next.1 > newest.1 { next } else { newest })
+            .map(|(data, _)| data);
+
+        self.get_state().record_miss();
+        let generation = self.get_state().generation()?;
+        if url_override.is_none() {
+            self.select_mirror().await;
+        }
+
+        // validating against the origin only makes sense for the resource URL
+        let revalidate = !inline && url_override.is_none();
+        if let Some(data) = newest_stale_data.as_ref().filter(|_| revalidate) {
+            if self.head_check && self.is_unchanged_at_origin().await {
+                // only the freshness is renewed, the body download is skipped
+                let data = self
+                    .get_state()
+                    .store_refreshed(generation, Arc::clone(data))?;
+                return Ok(DataResult::Fresh(data));
+            }
+
+            if let Some((patched, lifetime)) = self.fetch_delta().await {
+                self.track_connectivity(None);
+                let patched = self
+                    .get_state()
+                    .store_refreshed_for(generation, patched, lifetime)?;
+                return Ok(DataResult::Fresh(patched));
+            }
+        }
+
+        let fetched = self.fetch_from_server(url_override).await;
+        self.track_connectivity(fetched.as_ref().err());
+        if let Err(ResourceError::HttpStatus(404 | 410)) = &fetched {
+            if self.absent_on_not_found {
+                return Ok(DataResult::Absent);
+            }
+        }
+        if fetched.is_err() && url_override.is_none() {
+            self.reprobe_mirrors();
+        }
+
+        let (fresh_data, lifetime) = match fetched {
+            Ok(fetched) => fetched,
+            Err(network) => {
+                if let Some(data) = fresh_fallback {
+                    return Ok(DataResult::Fresh(data));
+                }
+                return match newest_stale_data {
+                    Some(data) if allow_stale => Ok(DataResult::Stale(data)),
+                    Some(_) => Err(ResourceError::UnableToFreshData),
+                    None => Err(ResourceError::AllSourcesFailed(Box::new(SourceFailures {
+                        network,
+                        disk: disk_lookup,
+                        memory: memory_lookup,
+                    }))),
+                };
+            }
+        };
+
+        // a concurrent refresh may have stored newer data while this one was fetching
+        let fresh_data = if inline {
+            if self
+                .get_state()
+                .set_internal_cache_if_generation(generation, Arc::clone(&fresh_data))?
+            {
+                fresh_data
+            } else {
+                self.get_state()
+                    .get_internal_data()?
+                    .map_or(fresh_data, |(data, _, _)| data)
+            }
+        } else {
+            self.get_state()
+                .store_refreshed_for(generation, fresh_data, lifetime)?
+        };
+
+        Ok(DataResult::Fresh(fresh_data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{ResourceProps, SchemaWarning};
+    use crate::test_support::{block_on, test_directory};
+    use crate::testing::StubServer;
+    use crate::traits::{ResourceFileType, StalePolicy};
+    use reqwest::Url;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_offline_reader_serves_cache_without_network() {
+        let directory = test_directory("remote-offline");
+        let reader = DefaultRemoteResourceReader::<HashMap<String, u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                // unroutable origin: any network access would fail the test
+                Url::parse("http://127.0.0.1:9/flags.json").unwrap(),
+                &directory,
+                Some(Duration::from_secs(60)),
+            ),
+        ));
+        reader.set_offline(true);
+
+        assert!(matches!(
+            block_on(reader.get_data_or_error(true)),
+            Err(ResourceError::Offline)
+        ));
+
+        reader
+            .get_state()
+            .save_to_disk(&HashMap::from([("enabled".to_string(), 1)]))
+            .unwrap();
+        reader.mark_as_stale().unwrap();
+
+        match block_on(reader.get_data_or_error(false)) {
+            Ok(DataResult::Stale(data)) => assert_eq!(data.get("enabled"), Some(&1)),
+            _ => panic!("Expected stale data from the disk cache"),
+        }
+        assert_eq!(reader.stats().unwrap().misses, 0);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_repeated_connect_failures_switch_to_offline() {
+        let clock = Arc::new(crate::clock::ManualClock::new(SystemTime::now()));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        let reader = DefaultRemoteResourceReader::<HashMap<String, u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                Url::parse("http://127.0.0.1:9/flags.json").unwrap(),
+                std::env::temp_dir().join("resourcely-remote-auto-offline-missing"),
+                None,
+            )
+            .with_clock(clock.clone())
+            .with_event_listener(Arc::new(move |event| {
+                recorded.lock().unwrap().push(event.clone());
+            })),
+        ))
+        .with_auto_offline(AutoOfflinePolicy {
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(60),
+        });
+
+        for _ in 0..2 {
+            assert!(matches!(
+                block_on(reader.get_data_or_error(true)),
+                Err(ResourceError::AllSourcesFailed(_))
+            ));
+        }
+
+        assert!(reader.is_offline());
+        assert!(matches!(
+            block_on(reader.get_data_or_error(true)),
+            Err(ResourceError::Offline)
+        ));
+        assert_eq!(reader.stats().unwrap().misses, 2);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![ResourceEvent::WentOffline {
+                resource: "flags".to_string(),
+                consecutive_failures: 2,
+                cooldown: Duration::from_secs(60),
+            }]
+        );
+
+        // the cooldown follows the resource's clock
+        clock.advance(Duration::from_secs(61));
+        assert!(!reader.is_offline());
+    }
+
+    #[test]
+    fn test_mirrors_keep_resource_url_without_healthy_mirror() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        let reader = DefaultRemoteResourceReader::<HashMap<String, u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                Url::parse("http://127.0.0.1:9/flags.json").unwrap(),
+                std::env::temp_dir().join("resourcely-remote-mirrors-missing"),
+                None,
+            )
+            .with_event_listener(Arc::new(move |event| {
+                recorded.lock().unwrap().push(event.clone());
+            })),
+        ))
+        .with_mirrors(
+            vec![Url::parse("http://127.0.0.1:9/mirror/flags.json").unwrap()],
+            Duration::from_secs(60),
+        );
+
+        assert!(block_on(reader.get_data_or_error(true)).is_err());
+
+        let stats = reader.mirror_stats();
+        assert_eq!(stats.len(), 2);
+        assert!(stats.iter().all(|mirror| !mirror.healthy));
+        assert!(stats[0].active && !stats[1].active);
+        assert_eq!(reader.active_url(), *reader.get_state().get_url());
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|event| matches!(
+            event,
+            ResourceEvent::MirrorProbed {
+                healthy: false,
+                latency: None,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn test_mirrors_are_probed_and_reported() {
+        let directory = test_directory("remote-mirrors");
+        let server = StubServer::start().unwrap();
+        server
+            .fixture_with_status("/flags.json", 503, "")
+            .fixture("/mirror/flags.json", r#"{"enabled": 1}"#);
+
+        let clock = Arc::new(crate::clock::ManualClock::new(SystemTime::now()));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        let reader = DefaultRemoteResourceReader::<HashMap<String, u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                server.url("/flags.json"),
+                &directory,
+                None,
+            )
+            .with_clock(clock.clone())
+            .with_event_listener(Arc::new(move |event| {
+                recorded.lock().unwrap().push(event.clone());
+            })),
+        ))
+        .with_mirrors(
+            vec![server.url("/mirror/flags.json")],
+            Duration::from_secs(60),
+        );
+
+        assert!(matches!(
+            block_on(reader.get_data_or_error(false)),
+            Ok(DataResult::Fresh(_))
+        ));
+        assert_eq!(reader.active_url(), server.url("/mirror/flags.json"));
+
+        let events = events.lock().unwrap();
+        let probed: Vec<(&str, bool)> = events
+            .iter()
+            .filter_map(|event| match event {
+                ResourceEvent::MirrorProbed { url, healthy, .. } => Some((url.as_str(), *healthy)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            probed,
+            [
+                (server.url("/flags.json").as_str(), false),
+                (server.url("/mirror/flags.json").as_str(), true)
+            ]
+        );
+        assert!(matches!(
+            events.last(),
+            Some(ResourceEvent::MirrorSelected { url, .. }) if *url == server.url("/mirror/flags.json").as_str()
+        ));
+        drop(events);
+
+        // probed again once the interval has passed on the resource's clock
+        let refresh = || {
+            reader.get_state().mark_as_stale();
+            block_on(reader.get_data_or_error(false)).unwrap();
+        };
+        refresh();
+        assert_eq!(server.requests("HEAD", "/mirror/flags.json"), 1);
+        clock.advance(Duration::from_secs(61));
+        refresh();
+        assert_eq!(server.requests("HEAD", "/mirror/flags.json"), 2);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_not_found_is_absent_when_enabled() {
+        let directory = test_directory("remote-absent");
+        let server = StubServer::start().unwrap();
+        server.fixture_with_status("/gone.json", 410, "");
+
+        let reader = server.remote::<HashMap<String, u32>>("/missing.json", &directory, None);
+        let Err(ResourceError::AllSourcesFailed(failures)) =
+            block_on(reader.get_data_or_error(false))
+        else {
+            panic!("expected every source to fail");
+        };
+        assert!(matches!(failures.network, ResourceError::HttpStatus(404)));
+        assert_eq!(failures.disk, CacheLookup::Empty);
+        assert_eq!(failures.memory, CacheLookup::Empty);
+
+        let reader = reader.with_absent_on_not_found(true);
+        assert!(matches!(
+            block_on(reader.get_data_or_error(true)),
+            Ok(DataResult::Absent)
+        ));
+        let gone = server
+            .remote::<HashMap<String, u32>>("/gone.json", &directory, None)
+            .with_absent_on_not_found(true);
+        assert!(matches!(
+            block_on(gone.get_data_or_error(false)),
+            Ok(DataResult::Absent)
+        ));
+        assert!(block_on(gone.get_data_or_none_with(StalePolicy::IfError)).is_none());
+        server.assert_fetches("/gone.json", 2);
+    }
+
+    #[test]
+    fn test_transforms_apply_before_caching() {
+        let directory = test_directory("remote-transform");
+        let server = StubServer::start().unwrap();
+        server.fixture(
+            "/hosts.json",
+            r#"["b.example.com", "A.example.com", "b.example.com"]"#,
+        );
+
+        let reader = DefaultRemoteResourceReader::new(ResourceState::new(
+            ResourceProps::<Vec<String>>::new(
+                "hosts",
+                ResourceFileType::Json,
+                server.url("/hosts.json"),
+                &directory,
+                None,
+            )
+            .with_transform(|hosts| hosts.iter_mut().for_each(|h| *h = h.to_lowercase()))
+            .with_transform(|hosts| {
+                hosts.sort();
+                hosts.dedup();
+            }),
+        ));
+
+        let expected = vec!["a.example.com".to_string(), "b.example.com".to_string()];
+        let data = block_on(reader.get_data_or_none_with(StalePolicy::Never)).unwrap();
+        assert_eq!(*data, expected);
+        let (cached, _, _) = reader.get_state().get_disk_cached_data().unwrap().unwrap();
+        assert_eq!(*cached, expected);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_delta_patches_the_downloaded_document() {
+        let directory = test_directory("remote-delta-transform");
+        let server = StubServer::start().unwrap();
+        server
+            .fixture("/flags.json", r#"{"a": 1, "b": 1}"#)
+            .fixture_header("/flags.json", "ETag", "\"v1\"");
+        server
+            .fixture(
+                "/flags.patch",
+                r#"[{"op": "replace", "path": "/a", "value": 5}]"#,
+            )
+            .fixture_header("/flags.patch", "ETag", "\"v2\"");
+        let clock = Arc::new(crate::clock::ManualClock::new(SystemTime::now()));
+        let reader = DefaultRemoteResourceReader::new(ResourceState::new(
+            ResourceProps::<HashMap<String, u32>>::new(
+                "flags",
+                ResourceFileType::Json,
+                server.url("/flags.json"),
+                &directory,
+                Some(Duration::from_secs(60)),
+            )
+            .with_clock(clock.clone())
+            .with_transform(|flags| flags.values_mut().for_each(|value| *value *= 2)),
+        ))
+        .with_delta_endpoint(format!(
+            "{}?since={{base_version}}",
+            server.url("/flags.patch")
+        ));
+
+        let data = block_on(reader.get_data_or_none_with(StalePolicy::Never)).unwrap();
+        assert_eq!((data["a"], data["b"]), (2, 2));
+
+        // the patch applies to the document as sent and the transform runs once
+        clock.advance(Duration::from_secs(120));
+        let data = block_on(reader.get_data_or_none_with(StalePolicy::Never)).unwrap();
+        assert_eq!((data["a"], data["b"]), (10, 2));
+        server.assert_fetches("/flags.json", 1);
+        server.assert_fetches("/flags.patch", 1);
+        let fetch = reader.last_fetch().unwrap();
+        assert_eq!((fetch.url.path(), fetch.status), ("/flags.patch", 200));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_lookup_policies_order_sources() {
+        let directory = test_directory("remote-lookup");
+        let server = StubServer::start().unwrap();
+        server.fixture("/flags.json", r#"{"source": 3}"#);
+        let source =
+            |result: Result<DataResult<Arc<HashMap<String, u32>>>, ResourceError>| match result {
+                Ok(DataResult::Fresh(data)) => data["source"],
+                other => panic!("unexpected result: {:?}", other.map(|_| ())),
+            };
+
+        // memory holds 1, disk holds 2, the origin serves 3
+        let reader = server.remote::<HashMap<String, u32>>("/flags.json", &directory, None);
+        reader
+            .get_state()
+            .save_to_disk(&HashMap::from([("source".to_string(), 2)]))
+            .unwrap();
+        let memory = || HashMap::from([("source".to_string(), 1)]);
+
+        reader.get_state().set_internal_cache(memory()).unwrap();
+        assert_eq!(source(block_on(reader.get_data_or_error(false))), 1);
+
+        let reader = reader.with_lookup_policy(LookupPolicy::DiskFirst);
+        assert_eq!(source(block_on(reader.get_data_or_error(false))), 2);
+        server.assert_fetches("/flags.json", 0);
+
+        let reader = reader.with_lookup_policy(LookupPolicy::NetworkFirst);
+        reader.get_state().set_internal_cache(memory()).unwrap();
+        assert_eq!(source(block_on(reader.get_data_or_error(false))), 3);
+        server.fixture_with_status("/flags.json", 500, "");
+        reader.get_state().set_internal_cache(memory()).unwrap();
+        assert_eq!(source(block_on(reader.get_data_or_error(false))), 1);
+        server.assert_fetches("/flags.json", 2);
+
+        let reader = server
+            .remote::<HashMap<String, u32>>("/flags.json", &directory, None)
+            .with_lookup_policy(LookupPolicy::MemoryOnly);
+        assert!(matches!(
+            block_on(reader.get_data_or_error(true)),
+            Err(ResourceError::StaleInternalNone)
+        ));
+        reader.get_state().set_internal_cache(memory()).unwrap();
+        assert_eq!(source(block_on(reader.get_data_or_error(false))), 1);
+        server.assert_fetches("/flags.json", 2);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_stale_policies() {
+        let directory = test_directory("remote-stale");
+        let server = StubServer::start().unwrap();
+        server.fixture_with_status("/flags.json", 500, "");
+        let clock = Arc::new(crate::clock::ManualClock::new(SystemTime::now()));
+        let reader = DefaultRemoteResourceReader::<HashMap<String, u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                server.url("/flags.json"),
+                &directory,
+                Some(Duration::from_secs(60)),
+            )
+            .with_clock(clock.clone()),
+        ));
+        reader
+            .get_state()
+            .set_internal_cache(HashMap::from([("enabled".to_string(), 1)]))
+            .unwrap();
+        clock.advance(Duration::from_secs(120));
+
+        assert!(matches!(
+            block_on(reader.get_data(StalePolicy::Always)),
+            Ok(DataResult::Stale(_))
+        ));
+        server.assert_fetches("/flags.json", 0);
+
+        assert!(matches!(
+            block_on(reader.get_data(StalePolicy::Never)),
+            Err(ResourceError::UnableToFreshData)
+        ));
+        assert!(matches!(
+            block_on(reader.get_data(StalePolicy::MaxAge(Duration::from_secs(300)))),
+            Ok(DataResult::Stale(_))
+        ));
+        assert!(matches!(
+            block_on(reader.get_data(StalePolicy::MaxAge(Duration::from_secs(100)))),
+            Err(ResourceError::StaleTooOld(age)) if age == Duration::from_secs(120)
+        ));
+        assert!(block_on(reader.get_data_or_none_with(StalePolicy::IfError)).is_some());
+        server.assert_fetches("/flags.json", 4);
+    }
+
+    #[test]
+    fn test_is_fresh_requires_fresh_unmarked_data() {
+        let directory = test_directory("remote-is-fresh");
+        let clock = Arc::new(crate::clock::ManualClock::new(SystemTime::now()));
+        let reader = DefaultRemoteResourceReader::<HashMap<String, u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                Url::parse("http://127.0.0.1:9/flags.json").unwrap(),
+                &directory,
+                Some(Duration::from_secs(60)),
+            )
+            .with_clock(clock.clone()),
+        ));
+        assert!(!reader.is_fresh().unwrap());
+
+        let flags = HashMap::from([("enabled".to_string(), 1)]);
+        reader
+            .get_state()
+            .set_internal_cache(flags.clone())
+            .unwrap();
+        assert!(reader.is_fresh().unwrap());
+
+        clock.advance(Duration::from_secs(120));
+        assert!(!reader.is_fresh().unwrap());
+
+        reader.get_state().set_internal_cache(flags).unwrap();
+        reader.mark_as_stale().unwrap();
+        assert!(!reader.is_fresh().unwrap());
+    }
+
+    #[test]
+    fn test_schema_warnings_are_returned_with_the_data() {
+        #[derive(serde::Deserialize, Serialize, Default)]
+        struct Flags {
+            enabled: u32,
+            #[serde(default)]
+            rollout: u32,
+        }
+
+        let server = StubServer::start().unwrap();
+        server.fixture("/flags.json", r#"{"enabled": 1, "extra": true}"#);
+        let reader = DefaultRemoteResourceReader::<Flags>::new(ResourceState::new(
+            ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                server.url("/flags.json"),
+                "/cache",
+                Some(Duration::from_secs(60)),
+            )
+            .with_file_system(Arc::new(crate::file_system::MemoryFileSystem::new()))
+            .with_schema_mode(crate::base::SchemaMode::Warn),
+        ));
+
+        let expected = vec![
+            SchemaWarning::UnknownField("extra".to_string()),
+            SchemaWarning::MissingField("rollout".to_string()),
+        ];
+        for _ in 0..2 {
+            let (data, warnings) = block_on(reader.get_data_with_warnings(false)).unwrap();
+            assert!(matches!(data, DataResult::Fresh(flags) if flags.enabled == 1));
+            assert_eq!(warnings, expected);
+        }
+        // the second call is served from memory with the warnings of its document
+        server.assert_fetches("/flags.json", 1);
+    }
+
+    #[test]
+    fn test_parse_failures_back_off_from_origin() {
+        let directory = test_directory("remote-parse-backoff");
+        let server = StubServer::start().unwrap();
+        server.fixture("/flags.json", "<html>maintenance</html>");
+        let clock = Arc::new(crate::clock::ManualClock::new(SystemTime::now()));
+        let reader = DefaultRemoteResourceReader::<HashMap<String, u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                server.url("/flags.json"),
+                &directory,
+                Some(Duration::from_secs(60)),
+            )
+            .with_clock(clock.clone()),
+        ))
+        .with_parse_backoff(ParseBackoff {
+            initial: Duration::from_secs(10),
+            max: Duration::from_secs(25),
+        });
+        reader
+            .get_state()
+            .set_internal_cache(HashMap::from([("enabled".to_string(), 1)]))
+            .unwrap();
+        clock.advance(Duration::from_secs(120));
+
+        let read = || block_on(reader.get_data_or_error(true));
+        assert!(matches!(read(), Ok(DataResult::Stale(_))));
+        assert!(matches!(read(), Ok(DataResult::Stale(_))));
+        server.assert_fetches("/flags.json", 1);
+
+        // the pause doubles after every failure, up to the maximum
+        for pause in [10, 20, 25, 25] {
+            clock.advance(Duration::from_secs(pause - 1));
+            assert!(matches!(read(), Ok(DataResult::Stale(_))));
+            assert!(reader.parse_backoff_until().is_some());
+            clock.advance(Duration::from_secs(1));
+            assert!(matches!(read(), Ok(DataResult::Stale(_))));
+        }
+        server.assert_fetches("/flags.json", 5);
+
+        server.fixture("/flags.json", r#"{"enabled": 2}"#);
+        clock.advance(Duration::from_secs(25));
+        assert!(matches!(read(), Ok(DataResult::Fresh(data)) if data["enabled"] == 2));
+        assert!(reader.parse_backoff_until().is_none());
+    }
+
+    #[test]
+    fn test_conditional_requests_pick_up_changes() {
+        let directory = test_directory("remote-long-poll");
+        let _ = std::fs::remove_dir_all(&directory);
+        let server = StubServer::start().unwrap();
+        server
+            .fixture("/flags.json", r#"{"enabled": 1}"#)
+            .fixture_header("/flags.json", "ETag", "\"v1\"");
+        let reader = server.remote::<HashMap<String, u32>>(
+            "/flags.json",
+            &directory,
+            Some(Duration::from_secs(3600)),
+        );
+
+        assert!(block_on(reader.wait_for_change()).unwrap());
+        // answered with 304 Not Modified
+        assert!(!block_on(reader.wait_for_change()).unwrap());
+
+        server
+            .fixture("/flags.json", r#"{"enabled": 2}"#)
+            .fixture_header("/flags.json", "ETag", "\"v2\"");
+        assert!(block_on(reader.wait_for_change()).unwrap());
+        server.assert_fetches("/flags.json", 3);
+
+        // readers get the pushed version without waiting for the cache to expire
+        assert_eq!(
+            block_on(reader.get_data_or_default_with(StalePolicy::Never))["enabled"],
+            2
+        );
+        server.assert_fetches("/flags.json", 3);
+    }
+
+    #[test]
+    fn test_conditional_requests_keep_the_origin_lifetime() {
+        let directory = test_directory("remote-long-poll-lifetime");
+        let _ = std::fs::remove_dir_all(&directory);
+        let server = StubServer::start().unwrap();
+        server
+            .fixture("/flags.json", r#"{"enabled": 1}"#)
+            .fixture_header("/flags.json", "ETag", "\"v1\"")
+            .fixture_header("/flags.json", "Cache-Control", "max-age=10");
+        let clock = Arc::new(crate::clock::ManualClock::new(SystemTime::now()));
+        let reader = DefaultRemoteResourceReader::<HashMap<String, u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                server.url("/flags.json"),
+                &directory,
+                Some(Duration::from_secs(3600)),
+            )
+            .with_clock(clock.clone()),
+        ))
+        .with_cache_control(Duration::ZERO, Duration::from_secs(3600));
+
+        let memory_is_fresh = || reader.get_state().get_internal_data().unwrap().unwrap().1;
+        assert!(block_on(reader.wait_for_change()).unwrap());
+        clock.advance(Duration::from_secs(5));
+        assert!(memory_is_fresh());
+
+        // expired after the announced ten seconds, not the resource timeout
+        clock.advance(Duration::from_secs(10));
+        assert!(!memory_is_fresh());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_watch_retries_transient_errors() {
+        let directory = test_directory("remote-watch-retry");
+        let server = StubServer::start().unwrap();
+        server.fixture_with_status("/flags.json", 502, "");
+        let reader = server
+            .remote::<HashMap<String, u32>>("/flags.json", &directory, None)
+            .with_long_poll(Duration::from_millis(50));
+
+        // retried after a second instead of ending the watch
+        let watch = block_on(async {
+            tokio::time::timeout(Duration::from_millis(1500), reader.watch()).await
+        });
+        assert!(watch.is_err());
+        server.assert_fetches("/flags.json", 2);
+        assert_eq!(
+            reader.get_state().stats().unwrap().last_error.as_deref(),
+            Some(ResourceError::HttpStatus(502).to_string().as_str())
+        );
+
+        server.fixture_with_status("/flags.json", 404, "");
+        assert!(matches!(
+            block_on(reader.watch()),
+            Err(ResourceError::HttpStatus(404))
+        ));
+        assert!(!directory.exists());
+    }
+
+    #[test]
+    fn test_offline_reader_does_not_watch_the_origin() {
+        let directory = test_directory("remote-watch-offline");
+        let server = StubServer::start().unwrap();
+        server.fixture("/flags.json", r#"{"enabled": 1}"#);
+        let reader = server
+            .remote::<HashMap<String, u32>>("/flags.json", &directory, None)
+            .with_long_poll(Duration::from_millis(50));
+        reader.set_offline(true);
+
+        assert!(matches!(
+            block_on(reader.wait_for_change()),
+            Err(ResourceError::Offline)
+        ));
+        // parked instead of ending the watch
+        let watch = block_on(async {
+            tokio::time::timeout(Duration::from_millis(300), reader.watch()).await
+        });
+        assert!(watch.is_err());
+        server.assert_fetches("/flags.json", 0);
+
+        reader.set_offline(false);
+        assert!(block_on(reader.wait_for_change()).unwrap());
+        server.assert_fetches("/flags.json", 1);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_etag_survives_restart_in_metadata_sidecar() {
+        let server = StubServer::start().unwrap();
+        server
+            .fixture("/flags.json", r#"{"enabled": 1}"#)
+            .fixture_header("/flags.json", "ETag", "\"v1\"");
+        let file_system = Arc::new(crate::file_system::MemoryFileSystem::new());
+        let build = || {
+            crate::ResourceBuilder::<HashMap<String, u32>>::new()
+                .file_name("flags")
+                .url(server.url("/flags.json").to_string())
+                .cache_directory("/cache")
+                .file_system(file_system.clone())
+                .metadata_sidecar(true)
+                .build_remote()
+                .unwrap()
+        };
+
+        assert!(block_on(build().get_data_or_error(false)).is_ok());
+        let metadata = build().get_state().disk_metadata().unwrap().unwrap();
+        assert_eq!(metadata.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(metadata.source_url, server.url("/flags.json").to_string());
+
+        // a new reader asks conditionally and gets 304 Not Modified
+        assert!(!block_on(build().wait_for_change()).unwrap());
+    }
+
+    #[test]
+    fn test_resumable_downloads_use_the_file_system() {
+        use crate::file_system::FileSystem;
+
+        let server = StubServer::start().unwrap();
+        server.fixture("/flags.json", "{\"enabled\": 1}");
+        let file_system = Arc::new(crate::file_system::MemoryFileSystem::new());
+        let reader = DefaultRemoteResourceReader::<HashMap<String, u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                server.url("/flags.json"),
+                "/cache",
+                None,
+            )
+            .with_file_system(file_system.clone()),
+        ))
+        .with_resumable_downloads(true);
+
+        // an interrupted body is kept with the mode of the disk cache files
+        let part_path = reader.partial_download_path();
+        reader.save_partial_download(&part_path, b"{\"ena").unwrap();
+        assert_eq!(file_system.read(&part_path).unwrap(), b"{\"ena");
+        assert_eq!(file_system.mode(&part_path).unwrap(), Some(0o600));
+
+        // without a validator the download starts over and removes the partial file
+        match block_on(reader.get_data_or_error(false)) {
+            Ok(DataResult::Fresh(data)) => assert_eq!(data.get("enabled"), Some(&1)),
+            _ => panic!("Expected fresh data from the origin"),
+        }
+        assert!(file_system.read(&part_path).is_err());
+        assert!(!Path::new("/cache").exists());
+    }
+
+    #[test]
+    fn test_profiles_configure_caching() {
+        let server = StubServer::start().unwrap();
+        server.fixture("/flags.json", "{\"enabled\": 1}");
+        let build = |name: &str, profile: crate::Profile| {
+            crate::ResourceBuilder::<HashMap<String, u32>>::new()
+                .file_name(name)
+                .url(server.url("/flags.json").to_string())
+                .cache_directory("/cache")
+                .file_system(Arc::new(crate::file_system::MemoryFileSystem::new()))
+                .profile(profile)
+                .build_remote()
+                .unwrap()
+        };
+
+        let aggressive = build("aggressive", crate::Profile::Aggressive);
+        for _ in 0..2 {
+            assert!(block_on(aggressive.get_data_or_error(false)).is_ok());
+        }
+        server.assert_fetches("/flags.json", 1);
+
+        let uncached = build("uncached", crate::Profile::NoCache);
+        for _ in 0..2 {
+            assert!(block_on(uncached.get_data_or_error(false)).is_ok());
+        }
+        server.assert_fetches("/flags.json", 3);
+
+        // options set before the profile win over it
+        let bounded = crate::ResourceBuilder::<HashMap<String, u32>>::new()
+            .file_name("bounded")
+            .url(server.url("/flags.json").to_string())
+            .cache_directory("/cache")
+            .file_system(Arc::new(crate::file_system::MemoryFileSystem::new()))
+            .max_stale(Duration::from_secs(3600))
+            .profile(crate::Profile::NoCache)
+            .build_remote()
+            .unwrap();
+        assert!(block_on(bounded.get_data_or_error(false)).is_ok());
+        server.assert_fetches("/flags.json", 4);
+
+        // a failing origin gets no stale answer
+        server.fixture_with_status("/flags.json", 503, "");
+        assert!(block_on(uncached.get_data_or_error(true)).is_err());
+        assert!(block_on(aggressive.get_data_or_error(true)).is_ok());
+        assert!(matches!(
+            block_on(bounded.get_data_or_error(true)),
+            Ok(DataResult::Stale(_))
+        ));
+    }
+
+    #[test]
+    fn test_profiles_configure_retries() {
+        let build = |name: &str, profile: crate::Profile| {
+            crate::ResourceBuilder::<HashMap<String, u32>>::new()
+                .file_name(name)
+                .url("http://127.0.0.1:9/flags.json")
+                .cache_directory("/cache")
+                .file_system(Arc::new(crate::file_system::MemoryFileSystem::new()))
+                .profile(profile)
+                .build_remote()
+                .unwrap()
+        };
+
+        // three refused connections switch the aggressive profile offline
+        let aggressive = build("aggressive", crate::Profile::Aggressive);
+        let uncached = build("uncached", crate::Profile::NoCache);
+        for _ in 0..3 {
+            assert!(block_on(aggressive.get_data_or_error(true)).is_err());
+            assert!(block_on(uncached.get_data_or_error(true)).is_err());
+        }
+        assert!(aggressive.is_offline());
+        assert!(!uncached.is_offline());
+    }
+
+    #[test]
+    fn test_slow_origin_fails_with_request_timeout() {
+        // accepts connections but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/flags.json", listener.local_addr().unwrap());
+        let reader = crate::ResourceBuilder::<HashMap<String, u32>>::new()
+            .file_name("flags")
+            .url(url)
+            .cache_directory(std::env::temp_dir().join("resourcely-remote-request-timeout"))
+            .connect_timeout(Duration::from_secs(5))
+            .request_timeout(Duration::from_millis(100))
+            .build_remote()
+            .unwrap();
+
+        let Err(ResourceError::AllSourcesFailed(failures)) =
+            block_on(reader.get_data_or_error(false))
+        else {
+            panic!("expected the request to time out");
+        };
+        assert!(matches!(failures.network, ResourceError::RequestTimeout(_)));
+        drop(listener);
+    }
+
+    #[test]
+    fn test_max_stale_bounds_served_data() {
+        let server = StubServer::start().unwrap();
+        server.fixture_with_status("/flags.json", 503, "");
+        let clock = Arc::new(crate::clock::ManualClock::new(SystemTime::now()));
+        let reader = DefaultRemoteResourceReader::<HashMap<String, u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                server.url("/flags.json"),
+                std::env::temp_dir().join("resourcely-remote-max-stale"),
+                Some(Duration::from_secs(60)),
+            )
+            .with_clock(clock.clone())
+            .with_max_stale(Duration::from_secs(600)),
+        ));
+        reader
+            .get_state()
+            .set_internal_cache(HashMap::from([("enabled".to_string(), 1)]))
+            .unwrap();
+
+        clock.advance(Duration::from_secs(300));
+        assert!(matches!(
+            block_on(reader.get_data_or_error(true)),
+            Ok(DataResult::Stale(_))
+        ));
+
+        clock.advance(Duration::from_secs(400));
+        assert!(matches!(
+            block_on(reader.get_data_or_error(true)),
+            Err(ResourceError::StaleTooOld(age)) if age == Duration::from_secs(700)
+        ));
+        assert!(matches!(
+            block_on(reader.get_data(StalePolicy::Always)),
+            Err(ResourceError::StaleTooOld(_))
+        ));
+        // a per-call URL is bounded the same way
+        assert!(matches!(
+            block_on(reader.get_data_from(&server.url("/flags.json"), true)),
+            Err(ResourceError::StaleTooOld(age)) if age == Duration::from_secs(700)
+        ));
+    }
+
+    #[test]
+    fn test_last_fetch_keeps_captured_headers() {
+        let directory = test_directory("remote-metadata");
+        let server = StubServer::start().unwrap();
+        server
+            .fixture("/flags.json", r#"{"enabled": 1}"#)
+            .fixture_header("/flags.json", "ETag", "\"v1\"")
+            .fixture_header("/flags.json", "X-Request-Id", "req-42")
+            .fixture_header("/flags.json", "X-Region", "eu-west-1")
+            .fixture_header("/flags.json", "X-Ignored", "yes");
+
+        let reader = server
+            .remote::<HashMap<String, u32>>("/flags.json", &directory, None)
+            .with_captured_headers(["X-Region"]);
+        assert!(reader.last_fetch().is_none());
+
+        block_on(reader.get_data_or_error(false)).unwrap();
+        let metadata = reader.last_fetch().unwrap();
+        assert_eq!(metadata.status, 200);
+        assert_eq!(metadata.header("etag"), Some("\"v1\""));
+        assert_eq!(metadata.header("X-Request-Id"), Some("req-42"));
+        assert_eq!(metadata.header("x-region"), Some("eu-west-1"));
+        assert_eq!(metadata.header("x-ignored"), None);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_redirects_are_reported_and_limited() {
+        let directory = test_directory("remote-redirect");
+        let server = StubServer::start().unwrap();
+        server.fixture("/v2/flags.json", r#"{"enabled": 1}"#);
+        server
+            .fixture_with_status("/flags.json", 301, "")
+            .fixture_header("/flags.json", "Location", "/v2/flags.json");
+
+        let build = |max_redirects| {
+            crate::ResourceBuilder::<HashMap<String, u32>>::new()
+                .url(server.url("/flags.json").as_str())
+                .file_name("flags")
+                .cache_directory(&directory)
+                .max_redirects(max_redirects)
+                .build_remote()
+                .unwrap()
+        };
+
+        let following = build(3);
+        assert!(block_on(following.get_data_or_error(false)).is_ok());
+        let metadata = following.last_fetch().unwrap();
+        assert_eq!(metadata.url, server.url("/v2/flags.json"));
+        assert!(metadata.was_redirected_from(&server.url("/flags.json")));
+
+        // without redirects the 301 fails instead of being parsed
+        let strict = build(0);
+        strict.mark_as_stale().unwrap();
+        assert!(block_on(strict.get_data_or_error(false)).is_err());
+        assert_eq!(strict.last_fetch().unwrap().status, 301);
+        server.assert_fetches("/v2/flags.json", 1);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_requests_are_signed_before_sending() {
+        let directory = test_directory("remote-signer");
+        let server = StubServer::start().unwrap();
+        server.fixture("/flags.json", r#"{"enabled": 1}"#);
+
+        let signed = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&signed);
+        let reader = server
+            .remote::<HashMap<String, u32>>("/flags.json", &directory, None)
+            .with_request_signer(Arc::new(move |request: &mut reqwest::Request| {
+                let signature = format!("{} {}", request.method(), request.url().path());
+                request.headers_mut().insert(
+                    "X-Signature",
+                    header::HeaderValue::from_str(&signature).unwrap(),
+                );
+                recorded.lock().unwrap().push(signature);
+            }));
+
+        block_on(reader.get_data_or_error(false)).unwrap();
+        block_on(reader.check_origin()).unwrap();
+        assert_eq!(
+            *signed.lock().unwrap(),
+            vec!["GET /flags.json", "HEAD /flags.json"]
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_url_override_keeps_cache_identity() {
+        let directory = test_directory("remote-override");
+        let server = StubServer::start().unwrap();
+        server.fixture("/signed/flags.json", r#"{"enabled": 1}"#);
+
+        let reader = server.remote::<HashMap<String, u32>>("/flags.json", &directory, None);
+        let data = block_on(reader.get_data_from(&server.url("/signed/flags.json"), false));
+        assert!(matches!(data, Ok(DataResult::Fresh(data)) if data["enabled"] == 1));
+
+        // the cached data is served for the resource URL, which was never requested
+        assert_eq!(
+            block_on(reader.get_data_or_default_with(StalePolicy::Never))["enabled"],
+            1
+        );
+        server.assert_fetches("/signed/flags.json", 1);
+        server.assert_fetches("/flags.json", 0);
+        assert_eq!(
+            reader
+                .get_state()
+                .get_disk_cached_data()
+                .unwrap()
+                .unwrap()
+                .0["enabled"],
+            1
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_data_url_is_served_without_network() {
+        let directory = test_directory("remote-data-url");
+        let reader = DefaultRemoteResourceReader::<HashMap<String, u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                Url::parse("data:application/json;base64,eyJlbmFibGVkIjogMX0=").unwrap(),
+                &directory,
+                Some(Duration::from_secs(60)),
+            ),
+        ));
+        reader.set_offline(true);
+
+        match block_on(reader.get_data_or_error(false)) {
+            Ok(DataResult::Fresh(data)) => assert_eq!(data.get("enabled"), Some(&1)),
+            _ => panic!("Expected fresh data decoded from the data URL"),
+        }
+        assert!(!reader.get_state().get_file_path().exists());
+        assert!(!directory.exists());
+
+        // the document never changes, watching it ends at once
+        assert!(!block_on(reader.wait_for_change()).unwrap());
+        block_on(reader.watch()).unwrap();
+    }
+
+    #[test]
+    fn test_check_origin_reports_status_and_refused_connections() {
+        let directory = test_directory("remote-origin");
+        let server = StubServer::start().unwrap();
+        server
+            .fixture("/flags.json", r#"{"enabled": 1}"#)
+            .fixture_with_status("/limits.json", 503, "");
+
+        let reader = server.remote::<HashMap<String, u32>>("/flags.json", &directory, None);
+        let health = block_on(reader.check_origin()).unwrap();
+        assert!(health.is_healthy());
+        assert_eq!(health.content_length, Some(14));
+        assert_eq!(server.requests("HEAD", "/flags.json"), 1);
+        // neither cache is touched
+        assert!(reader.get_state().get_internal_data().unwrap().is_none());
+        assert!(!directory.exists());
+
+        let reader = server.remote::<HashMap<String, u32>>("/limits.json", &directory, None);
+        let health = block_on(reader.check_origin()).unwrap();
+        assert_eq!(health.status, 503);
+        assert!(!health.is_healthy());
+
+        // a port nothing listens on once the listener is dropped
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let reader = DefaultRemoteResourceReader::<HashMap<String, u32>>::new(ResourceState::new(
+            ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                Url::parse(&format!("http://{}/flags.json", address)).unwrap(),
+                &directory,
+                None,
+            ),
+        ));
+        match block_on(reader.check_origin()) {
+            Err(ResourceError::Network(error)) => assert!(error.is_connect()),
+            other => panic!("Expected a refused connection, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        let range = |value: &str| {
+            let mut headers = header::HeaderMap::new();
+            headers.insert(header::CONTENT_RANGE, value.parse().unwrap());
+            parse_content_range(&headers)
+        };
+
+        assert_eq!(range("bytes 100-199/200"), Some((100, Some(200))));
+        assert_eq!(range("bytes 0-9/*"), Some((0, None)));
+        assert_eq!(range("bytes */200"), None);
+        assert_eq!(range("items 0-9/10"), None);
+    }
+
+    #[test]
+    fn test_response_validators_matching() {
+        let validators =
+            |content_length: Option<u64>, last_modified: Option<&str>| ResponseValidators {
+                content_length,
+                last_modified: last_modified.map(str::to_string),
+                etag: None,
+            };
+        let date = "Wed, 21 Oct 2015 07:28:00 GMT";
+
+        assert!(validators(Some(10), Some(date)).matches(&validators(Some(10), Some(date))));
+        assert!(validators(Some(10), None).matches(&validators(Some(10), Some(date))));
+        assert!(!validators(Some(10), Some(date)).matches(&validators(Some(11), Some(date))));
+        assert!(!validators(None, Some(date)).matches(&validators(Some(10), Some("other"))));
+        assert!(!validators(None, None).matches(&validators(None, None)));
+
+        let tagged = |etag: &str| ResponseValidators {
+            etag: Some(etag.to_string()),
+            ..validators(Some(10), None)
+        };
+        assert!(tagged("\"v1\"").matches(&tagged("\"v1\"")));
+        assert!(!tagged("\"v1\"").matches(&tagged("\"v2\"")));
+    }
+
+    #[test]
+    fn test_origin_lifetime() {
+        let headers = |pairs: &[(header::HeaderName, &str)]| {
+            let mut headers = header::HeaderMap::new();
+            for (name, value) in pairs {
+                headers.append(name, header::HeaderValue::from_str(value).unwrap());
+            }
+            headers
+        };
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        assert_eq!(
+            origin_lifetime(
+                &headers(&[
+                    (header::CACHE_CONTROL, "public, max-age=300"),
+                    (header::AGE, "60"),
+                ]),
+                now
+            ),
+            Some(Duration::from_secs(240))
+        );
+        assert_eq!(
+            origin_lifetime(
+                &headers(&[(header::CACHE_CONTROL, "max-age=300, no-cache")]),
+                now
+            ),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            origin_lifetime(&headers(&[(header::CACHE_CONTROL, "max-age=\"30\"")]), now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            origin_lifetime(&headers(&[(header::CACHE_CONTROL, "public")]), now),
+            None
+        );
+        assert_eq!(origin_lifetime(&headers(&[]), now), None);
+
+        // `Expires` counts from the origin's `Date`
+        assert_eq!(
+            origin_lifetime(
+                &headers(&[
+                    (header::DATE, "Sun, 06 Nov 1994 08:49:37 GMT"),
+                    (header::EXPIRES, "Sun, 06 Nov 1994 09:49:37 GMT"),
+                ]),
+                now
+            ),
+            Some(Duration::from_secs(3_600))
+        );
+        assert_eq!(
+            origin_lifetime(&headers(&[(header::EXPIRES, "0")]), now),
+            Some(Duration::ZERO)
+        );
+
+        assert_eq!(
+            parse_retry_after(&headers(&[(header::RETRY_AFTER, "120")]), now),
+            Some(now + Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after(
+                &headers(&[(header::RETRY_AFTER, "Sun, 06 Nov 1994 08:49:37 GMT")]),
+                now
+            ),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(784_111_777))
+        );
     }
 }
@@ -0,0 +1,259 @@
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use crate::{error::ResourceError, utilities::civil_from_days};
+
+/// Longest span searched for the next matching minute; expressions such as
+/// `0 0 30 2 *` never match.
+const SEARCH_DAYS: i64 = 5 * 366;
+
+/// Cron-style refresh schedule, e.g. `5 0 * * *` for 00:05 every day.
+///
+/// Five fields: minute (0-59), hour (0-23), day of month (1-31), month (1-12) and day
+/// of week (0-7, Sunday being 0 or 7). Each field is `*`, a value, a range `a-b`, a
+/// step `*/n` or `a-b/n`, or a comma separated list of those. As in cron, a day
+/// matches when either day field matches, if both are restricted. The shortcuts
+/// `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` are accepted as well.
+///
+/// Times are evaluated in UTC unless [an offset](Self::with_utc_offset) is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether the day of month and day of week fields were restricted (not `*`).
+    days_restricted: bool,
+    weekdays_restricted: bool,
+    /// Offset of the schedule's time zone from UTC, in seconds.
+    utc_offset: i64,
+}
+
+impl CronSchedule {
+    /// Parse a cron expression, see [`CronSchedule`].
+    pub fn parse(expression: &str) -> Result<Self, ResourceError> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            expression => expression,
+        };
+
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(ResourceError::InvalidSchedule(format!(
+                "expected 5 fields, found {} in '{}'",
+                fields.len(),
+                expression
+            )));
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        // Sunday is both 0 and 7
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            days_restricted: !day.starts_with('*'),
+            weekdays_restricted: !weekday.starts_with('*'),
+            utc_offset: 0,
+        })
+    }
+
+    /// Evaluate the schedule in a time zone `minutes` east of UTC (negative for west),
+    /// e.g. `60` for CET. The offset is fixed; daylight saving changes are not followed.
+    pub fn with_utc_offset(mut self, minutes: i32) -> Self {
+        self.utc_offset = i64::from(minutes) * 60;
+        self
+    }
+
+    /// The first matching minute strictly after `time`, or `None` when the schedule
+    /// matches no minute in the next five years.
+    pub fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+        let seconds = match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(elapsed) => i64::try_from(elapsed.as_secs()).ok()?,
+            Err(before) => -i64::try_from(before.duration().as_secs()).ok()? - 1,
+        };
+
+        // local time, on the next whole minute
+        let mut local = (seconds + self.utc_offset).div_euclid(60) * 60 + 60;
+        let limit = local + SEARCH_DAYS * 86_400;
+
+        while local < limit {
+            let days = local.div_euclid(86_400);
+            let (_, month, day) = civil_from_days(days);
+            // 1970-01-01 was a Thursday
+            let weekday = (days + 4).rem_euclid(7) as u64;
+
+            if !matches(self.months, month) || !self.matches_day(day, weekday) {
+                local = (days + 1) * 86_400;
+                continue;
+            }
+
+            let of_day = local - days * 86_400;
+            if !matches(self.hours, (of_day / 3_600) as u64) {
+                local = days * 86_400 + (of_day / 3_600 + 1) * 3_600;
+                continue;
+            }
+
+            if !matches(self.minutes, (of_day % 3_600 / 60) as u64) {
+                local += 60;
+                continue;
+            }
+
+            let utc = u64::try_from(local - self.utc_offset).ok()?;
+            return Some(SystemTime::UNIX_EPOCH + Duration::from_secs(utc));
+        }
+
+        None
+    }
+
+    fn matches_day(&self, day: u64, weekday: u64) -> bool {
+        let by_day = matches(self.days, day);
+        let by_weekday = matches(self.weekdays, weekday);
+
+        if self.days_restricted && self.weekdays_restricted {
+            by_day || by_weekday
+        } else {
+            by_day && by_weekday
+        }
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = ResourceError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        Self::parse(expression)
+    }
+}
+
+fn matches(mask: u64, value: u64) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Bit mask of the values a cron field allows.
+fn parse_field(field: &str, min: u64, max: u64) -> Result<u64, ResourceError> {
+    let invalid = || ResourceError::InvalidSchedule(format!("invalid field '{}'", field));
+    let number = |value: &str| -> Result<u64, ResourceError> {
+        value
+            .parse::<u64>()
+            .ok()
+            .filter(|value| (min..=max).contains(value))
+            .ok_or_else(invalid)
+    };
+
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u64>().ok().filter(|step| *step > 0)),
+            None => (part, Some(1)),
+        };
+        let step = step.ok_or_else(invalid)?;
+
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (number(start)?, number(end)?),
+            // `a/n` runs from `a` to the end of the field
+            None if part.contains('/') => (number(range)?, max),
+            None => {
+                let value = number(range)?;
+                (value, value)
+            }
+        };
+        if start > end {
+            return Err(invalid());
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+
+    Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    // 2024-03-15 (a Friday) 10:30:20 UTC
+    const FRIDAY: u64 = 1_710_498_620;
+
+    #[test]
+    fn test_next_matching_minute() {
+        let daily = CronSchedule::parse("5 0 * * *").unwrap();
+        // 2024-03-16 00:05
+        assert_eq!(daily.next_after(at(FRIDAY)), Some(at(1_710_547_500)));
+
+        let quarter = CronSchedule::parse("*/15 9-17 * * 1-5").unwrap();
+        // 10:45 the same day
+        assert_eq!(quarter.next_after(at(FRIDAY)), Some(at(1_710_499_500)));
+        // after 17:45 on Friday comes 09:00 on Monday 2024-03-18
+        assert_eq!(
+            quarter.next_after(at(1_710_524_700)),
+            Some(at(1_710_752_400))
+        );
+
+        // both day fields restricted: the 1st of the month or any Sunday
+        let either = CronSchedule::parse("0 0 1 * 7").unwrap();
+        // Sunday 2024-03-17 00:00
+        assert_eq!(either.next_after(at(FRIDAY)), Some(at(1_710_633_600)));
+
+        assert_eq!(
+            CronSchedule::parse("0 0 30 2 *")
+                .unwrap()
+                .next_after(at(FRIDAY)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_utc_offset_shifts_local_time() {
+        // midnight in UTC+2 is 22:00 UTC the previous day
+        let daily = CronSchedule::parse("@daily").unwrap().with_utc_offset(120);
+        // 2024-03-15 22:00 UTC
+        assert_eq!(daily.next_after(at(FRIDAY)), Some(at(1_710_540_000)));
+
+        let west: CronSchedule = "0 9 * * *".parse().unwrap();
+        // 09:00 in UTC-5 is 14:00 UTC
+        assert_eq!(
+            west.with_utc_offset(-300).next_after(at(FRIDAY)),
+            Some(at(1_710_511_200))
+        );
+    }
+
+    #[test]
+    fn test_rejects_malformed_expressions() {
+        for expression in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(
+                matches!(
+                    CronSchedule::parse(expression),
+                    Err(ResourceError::InvalidSchedule(_))
+                ),
+                "{}",
+                expression
+            );
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    base::{validate_namespace, ResourceProps, ResourceState},
+    error::ResourceError,
+    remote::{
+        AutoOfflinePolicy, DefaultRemoteResourceReader, LookupPolicy, ParseBackoff, RequestSigner,
+    },
+    traits::{DataResult, ResourceFileType, ResourceReader},
+    utilities::content_hash,
+};
+
+type PropsFn<T> = dyn Fn(ResourceProps<T>) -> ResourceProps<T> + Send + Sync;
+
+/// Remote resource whose URL contains `{placeholders}` filled in at call time, e.g.
+/// `https://api.example.com/tenants/{tenant}/flags.json`.
+///
+/// Every parameter set is a resource of its own, with its own memory cache and disk
+/// files named after the resource file name and a hash of the parameters (see
+/// [`cache_key`](Self::cache_key)).
+pub struct TemplatedResourceReader<T> {
+    template: String,
+    file_name: String,
+    file_type: ResourceFileType,
+    storage_directory: PathBuf,
+    timeout: Option<Duration>,
+    namespace: Option<String>,
+    client: Option<reqwest::Client>,
+    lookup_policy: LookupPolicy,
+    auto_offline: Option<AutoOfflinePolicy>,
+    parse_backoff: Option<ParseBackoff>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    configure: Option<Box<PropsFn<T>>>,
+    readers: Mutex<HashMap<String, Arc<DefaultRemoteResourceReader<T>>>>,
+}
+
+impl<T> TemplatedResourceReader<T> {
+    pub fn new(
+        template: impl Into<String>,
+        file_name: impl Into<String>,
+        file_type: ResourceFileType,
+        storage_directory: impl Into<PathBuf>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        Self {
+            template: template.into(),
+            file_name: file_name.into(),
+            file_type,
+            storage_directory: storage_directory.into(),
+            timeout,
+            namespace: None,
+            client: None,
+            lookup_policy: LookupPolicy::default(),
+            auto_offline: None,
+            parse_backoff: None,
+            signer: None,
+            configure: None,
+            readers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Perform the requests of every parameter set through the given HTTP client.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Set the order in which the reader of every parameter set consults its sources.
+    pub fn with_lookup_policy(mut self, policy: LookupPolicy) -> Self {
+        self.lookup_policy = policy;
+        self
+    }
+
+    /// Switch the reader of every parameter set into offline mode after repeated
+    /// connection failures, see [`AutoOfflinePolicy`].
+    pub fn with_auto_offline(mut self, policy: AutoOfflinePolicy) -> Self {
+        self.auto_offline = Some(policy);
+        self
+    }
+
+    /// Back off from the origin of every parameter set while its content fails to
+    /// parse, see [`ParseBackoff`].
+    pub fn with_parse_backoff(mut self, backoff: ParseBackoff) -> Self {
+        self.parse_backoff = Some(backoff);
+        self
+    }
+
+    /// Sign the requests of every parameter set with the given signer.
+    pub fn with_request_signer(mut self, signer: Arc<dyn RequestSigner>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Adjust the properties of every parameter set's resource, e.g. its file system,
+    /// transforms or redaction.
+    pub fn with_props(
+        mut self,
+        configure: impl Fn(ResourceProps<T>) -> ResourceProps<T> + Send + Sync + 'static,
+    ) -> Self {
+        self.configure = Some(Box::new(configure));
+        self
+    }
+
+    /// Nest the disk cache into a namespace subdirectory, see
+    /// [`ResourceProps::with_namespace`].
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Result<Self, ResourceError> {
+        self.namespace = Some(validate_namespace(namespace.into())?);
+        Ok(self)
+    }
+
+    /// File name identifying the cache of a parameter set, e.g. `flags_3f2a...`.
+    /// The parameter order does not matter.
+    pub fn cache_key(&self, params: &[(&str, &str)]) -> String {
+        let canonical = canonical_params(params)
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        format!(
+            "{}_{:016x}",
+            self.file_name,
+            content_hash(canonical.as_bytes())
+        )
+    }
+
+    /// The template with every placeholder replaced by its percent-encoded parameter.
+    pub fn resolve_url(&self, params: &[(&str, &str)]) -> Result<Url, ResourceError> {
+        let params = canonical_params(params);
+        let mut url = String::with_capacity(self.template.len());
+        let mut rest = self.template.as_str();
+
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| {
+                    ResourceError::InvalidUrl(format!(
+                        "unclosed placeholder in '{}'",
+                        self.template
+                    ))
+                })?;
+            let name = &rest[start + 1..end];
+            let value = params.get(name).ok_or_else(|| {
+                ResourceError::InvalidUrl(format!("no value for placeholder '{{{}}}'", name))
+            })?;
+
+            url.push_str(&rest[..start]);
+            url.extend(utf8_percent_encode(value, NON_ALPHANUMERIC));
+            rest = &rest[end + 1..];
+        }
+        url.push_str(rest);
+
+        Url::parse(&url).map_err(|e| ResourceError::InvalidUrl(format!("{}: {}", url, e)))
+    }
+}
+
+impl<T> TemplatedResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    /// Reader of the given parameter set, created on first use and kept afterwards.
+    pub fn reader(
+        &self,
+        params: &[(&str, &str)],
+    ) -> Result<Arc<DefaultRemoteResourceReader<T>>, ResourceError> {
+        let key = self.cache_key(params);
+        let mut readers = self.readers.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(reader) = readers.get(&key) {
+            return Ok(Arc::clone(reader));
+        }
+
+        let mut props = ResourceProps::new(
+            key.clone(),
+            self.file_type.clone(),
+            self.resolve_url(params)?,
+            &self.storage_directory,
+            self.timeout,
+        );
+        if let Some(configure) = &self.configure {
+            props = configure(props);
+        }
+        if let Some(namespace) = &self.namespace {
+            props = props.with_namespace(namespace.clone())?;
+        }
+
+        let state = ResourceState::new(props);
+        let mut reader = match &self.client {
+            Some(client) => DefaultRemoteResourceReader::with_client(state, client.clone()),
+            None => DefaultRemoteResourceReader::new(state),
+        }
+        .with_lookup_policy(self.lookup_policy);
+        if let Some(signer) = &self.signer {
+            reader = reader.with_request_signer(Arc::clone(signer));
+        }
+        if let Some(policy) = self.auto_offline {
+            reader = reader.with_auto_offline(policy);
+        }
+        if let Some(backoff) = self.parse_backoff {
+            reader = reader.with_parse_backoff(backoff);
+        }
+
+        let reader = Arc::new(reader);
+        readers.insert(key, Arc::clone(&reader));
+
+        Ok(reader)
+    }
+
+    /// Data of the given parameter set, see [`ResourceReader::get_data_or_error`].
+    pub async fn get_data_with_params(
+        &self,
+        params: &[(&str, &str)],
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        self.reader(params)?.get_data_or_error(allow_stale).await
+    }
+}
+
+/// Parameters sorted by name; a repeated name keeps its last value.
+fn canonical_params<'a>(params: &[(&'a str, &'a str)]) -> BTreeMap<&'a str, &'a str> {
+    params.iter().copied().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{block_on, test_directory};
+    use crate::testing::StubServer;
+
+    #[test]
+    fn test_parameter_sets_are_cached_separately() {
+        let directory = test_directory("template");
+        let server = StubServer::start().unwrap();
+        server.fixture("/tenants/acme/flags.json", r#"{"beta": true}"#);
+        server.fixture("/tenants/globex%20inc/flags.json", r#"{"beta": false}"#);
+
+        let reader = TemplatedResourceReader::<HashMap<String, bool>>::new(
+            format!("{}tenants/{{tenant}}/flags.json", server.url("/")),
+            "flags",
+            ResourceFileType::Json,
+            &directory,
+            None,
+        );
+
+        for _ in 0..2 {
+            let acme = block_on(reader.get_data_with_params(&[("tenant", "acme")], false));
+            assert!(matches!(acme, Ok(DataResult::Fresh(data)) if data["beta"]));
+        }
+        let globex = block_on(reader.get_data_with_params(&[("tenant", "globex inc")], false));
+        assert!(matches!(globex, Ok(DataResult::Fresh(data)) if !data["beta"]));
+        server.assert_fetches("/tenants/acme/flags.json", 1);
+
+        assert_ne!(
+            reader.cache_key(&[("tenant", "acme")]),
+            reader.cache_key(&[("tenant", "globex inc")])
+        );
+        assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 2);
+        assert!(matches!(
+            reader.resolve_url(&[("region", "eu")]),
+            Err(ResourceError::InvalidUrl(_))
+        ));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
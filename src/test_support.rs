@@ -0,0 +1,19 @@
+//! Helpers shared by the unit tests of the crate.
+
+use std::future::Future;
+use std::path::PathBuf;
+
+/// Run `future` to completion on a single-threaded runtime.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+/// `resourcely-<name>-<process id>` in the system temporary directory, so concurrent
+/// test runs do not share files. The directory is not created.
+pub(crate) fn test_directory(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("resourcely-{}-{}", name, std::process::id()))
+}
@@ -0,0 +1,727 @@
+//! Helpers for testing code that consumes resources, without filesystem or network.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::base::{ResourceProps, ResourceState};
+use crate::error::ResourceError;
+use crate::remote::DefaultRemoteResourceReader;
+use crate::traits::{DataResult, ResourceFileType, ResourceReader};
+
+enum Scripted<T> {
+    Fresh(Arc<T>),
+    Stale(Arc<T>),
+    Absent,
+    Err(ResourceError),
+}
+
+/// Reader answering with a scripted sequence of responses and counting its calls.
+///
+/// Responses are returned in the order they were pushed. Once the script is used up,
+/// the last data response is repeated; without any, reads fail with
+/// [`ResourceError::UnableToFreshData`].
+pub struct MockResourceReader<T> {
+    state: Arc<ResourceState<T>>,
+    script: Mutex<VecDeque<Scripted<T>>>,
+    last_data: Mutex<Option<(Arc<T>, bool)>>,
+    calls: AtomicUsize,
+    stale_allowed_calls: AtomicUsize,
+}
+
+impl<T> Default for MockResourceReader<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    fn default() -> Self {
+        Self::new("mock")
+    }
+}
+
+impl<T> MockResourceReader<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Create a reader with an empty script; `name` is reported as its file name.
+    pub fn new(name: &str) -> Self {
+        let url = Url::parse(&format!("mock:{}", name))
+            .unwrap_or_else(|_| Url::parse("mock:resource").expect("valid mock URL"));
+
+        Self {
+            state: Arc::new(ResourceState::new(ResourceProps::new(
+                name,
+                ResourceFileType::Json,
+                url,
+                PathBuf::new(),
+                None,
+            ))),
+            script: Mutex::new(VecDeque::new()),
+            last_data: Mutex::new(None),
+            calls: AtomicUsize::new(0),
+            stale_allowed_calls: AtomicUsize::new(0),
+        }
+    }
+
+    /// Answer the next read with fresh data.
+    pub fn push_fresh(&self, data: T) -> &Self {
+        self.push(Scripted::Fresh(Arc::new(data)))
+    }
+
+    /// Answer the next read with stale data; a read not allowing stale data consumes it
+    /// and fails with [`ResourceError::UnableToFreshData`].
+    pub fn push_stale(&self, data: T) -> &Self {
+        self.push(Scripted::Stale(Arc::new(data)))
+    }
+
+    /// Answer the next read with [`DataResult::Absent`].
+    pub fn push_absent(&self) -> &Self {
+        self.push(Scripted::Absent)
+    }
+
+    /// Fail the next read with the given error.
+    pub fn push_error(&self, error: ResourceError) -> &Self {
+        self.push(Scripted::Err(error))
+    }
+
+    /// Number of `get_data_or_error` calls (the other getters call it once each).
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    /// Number of calls that allowed stale data.
+    pub fn stale_allowed_calls(&self) -> usize {
+        self.stale_allowed_calls.load(Ordering::Relaxed)
+    }
+
+    /// Number of scripted responses not consumed yet.
+    pub fn remaining(&self) -> usize {
+        self.script
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    fn push(&self, response: Scripted<T>) -> &Self {
+        self.script
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push_back(response);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ResourceReader<T> for MockResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
+        &self.state
+    }
+
+    async fn get_data_or_error(
+        &self,
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        if allow_stale {
+            self.stale_allowed_calls.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let next = self
+            .script
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop_front();
+        let mut last_data = self
+            .last_data
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let result = match next {
+            Some(Scripted::Fresh(data)) => {
+                *last_data = Some((Arc::clone(&data), true));
+                Ok(DataResult::Fresh(data))
+            }
+            Some(Scripted::Stale(data)) => {
+                *last_data = Some((Arc::clone(&data), false));
+                Ok(DataResult::Stale(data))
+            }
+            Some(Scripted::Absent) => Ok(DataResult::Absent),
+            Some(Scripted::Err(error)) => Err(error),
+            None => match last_data.as_ref() {
+                Some((data, true)) => Ok(DataResult::Fresh(Arc::clone(data))),
+                Some((data, false)) => Ok(DataResult::Stale(Arc::clone(data))),
+                None => Err(ResourceError::UnableToFreshData),
+            },
+        };
+        drop(last_data);
+        let result = match result {
+            Ok(DataResult::Stale(_)) if !allow_stale => Err(ResourceError::UnableToFreshData),
+            result => result,
+        };
+
+        match &result {
+            Ok(_) => self.state.record_hit(),
+            Err(error) => self.state.record_error(error),
+        }
+
+        result
+    }
+}
+
+/// Point in the read path where a [`FaultInjector`] can make an operation fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Fault {
+    /// Fetching from the remote origin fails as if the connection broke.
+    Network,
+    /// The fetched content fails to deserialize.
+    Parse,
+    /// Writing the disk cache fails with an I/O error.
+    DiskWrite,
+}
+
+impl Fault {
+    fn error(self) -> ResourceError {
+        match self {
+            Fault::Network => ResourceError::InjectedFault("network failure".to_string()),
+            Fault::Parse => ResourceError::deserialization("injected parse failure"),
+            Fault::DiskWrite => {
+                ResourceError::Io(std::io::Error::other("injected disk write failure"))
+            }
+        }
+    }
+}
+
+/// Makes chosen operations of a resource fail with given probabilities, to verify
+/// that stale data and default handling paths work.
+///
+/// Attach it with [`ResourceProps::with_fault_injector`]. Faults are drawn from a
+/// seeded pseudo-random sequence, so a failing run can be reproduced.
+pub struct FaultInjector {
+    probabilities: Mutex<HashMap<Fault, f64>>,
+    injected: Mutex<HashMap<Fault, usize>>,
+    seed: AtomicU64,
+}
+
+impl Default for FaultInjector {
+    fn default() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.subsec_nanos())
+            .unwrap_or(0);
+
+        Self::with_seed(u64::from(nanos))
+    }
+}
+
+impl FaultInjector {
+    /// Create an injector with no faults enabled and a time-based seed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an injector with no faults enabled and a fixed seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            probabilities: Mutex::new(HashMap::new()),
+            injected: Mutex::new(HashMap::new()),
+            // xorshift gets stuck at zero
+            seed: AtomicU64::new(seed.max(1)),
+        }
+    }
+
+    /// Enable `fault` with a probability between 0 (never) and 1 (always).
+    pub fn with_fault(self, fault: Fault, probability: f64) -> Self {
+        self.set_probability(fault, probability);
+        self
+    }
+
+    /// Change the probability of `fault` while the resource is in use.
+    pub fn set_probability(&self, fault: Fault, probability: f64) {
+        self.probabilities
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(fault, probability.clamp(0.0, 1.0));
+    }
+
+    /// Number of times `fault` was injected.
+    pub fn injected(&self, fault: Fault) -> usize {
+        self.injected
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&fault)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Fail with the error of `fault` when it is drawn.
+    pub(crate) fn check(&self, fault: Fault) -> Result<(), ResourceError> {
+        let probability = self
+            .probabilities
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&fault)
+            .copied()
+            .unwrap_or(0.0);
+
+        if probability <= 0.0 || self.next_unit() >= probability {
+            return Ok(());
+        }
+
+        *self
+            .injected
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(fault)
+            .or_default() += 1;
+        Err(fault.error())
+    }
+
+    /// Next pseudo-random number in `[0, 1)` (xorshift64).
+    fn next_unit(&self) -> f64 {
+        let mut x = self.seed.load(Ordering::Relaxed);
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.seed.store(x, Ordering::Relaxed);
+
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+struct Fixture {
+    status: u16,
+    body: String,
+    headers: Vec<(String, String)>,
+}
+
+#[derive(Default)]
+struct ServerState {
+    fixtures: HashMap<String, Fixture>,
+    /// Number of requests per method and path.
+    requests: HashMap<(String, String), usize>,
+    /// Headers of the last request per path, with lowercase names.
+    request_headers: HashMap<String, Vec<(String, String)>>,
+}
+
+/// Minimal HTTP server on a local port serving fixtures, to point remote readers at.
+///
+/// Every connection gets a single response and is closed. Unknown paths answer with
+/// `404 Not Found`, requests whose `If-None-Match` equals the fixture's `ETag` header
+/// with `304 Not Modified`. The server stops when dropped.
+pub struct StubServer {
+    address: SocketAddr,
+    state: Arc<Mutex<ServerState>>,
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl StubServer {
+    /// Start a server on a free local port.
+    pub fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(ServerState::default()));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let state = Arc::clone(&state);
+            let stopped = Arc::clone(&stopped);
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let _ = respond(stream, &state);
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            address,
+            state,
+            stopped,
+            handle: Some(handle),
+        })
+    }
+
+    /// Serve `body` with status 200 at `path`, replacing a previous fixture.
+    pub fn fixture(&self, path: &str, body: impl Into<String>) -> &Self {
+        self.fixture_with_status(path, 200, body)
+    }
+
+    /// Serve `body` with the given status at `path`, replacing a previous fixture.
+    pub fn fixture_with_status(&self, path: &str, status: u16, body: impl Into<String>) -> &Self {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .fixtures
+            .insert(
+                path.to_string(),
+                Fixture {
+                    status,
+                    body: body.into(),
+                    headers: Vec::new(),
+                },
+            );
+        self
+    }
+
+    /// Add a response header to the fixture at `path` (set up beforehand).
+    pub fn fixture_header(&self, path: &str, name: &str, value: &str) -> &Self {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(fixture) = state.fixtures.get_mut(path) {
+            fixture.headers.push((name.to_string(), value.to_string()));
+        }
+        self
+    }
+
+    /// Full URL of `path` on this server.
+    pub fn url(&self, path: &str) -> Url {
+        Url::parse(&format!("http://{}", self.address))
+            .and_then(|base| base.join(path))
+            .expect("valid stub server URL")
+    }
+
+    /// Number of `GET` requests received for `path`.
+    pub fn fetches(&self, path: &str) -> usize {
+        self.requests("GET", path)
+    }
+
+    /// Number of requests with the given method received for `path`.
+    pub fn requests(&self, method: &str, path: &str) -> usize {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .requests
+            .get(&(method.to_string(), path.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Value of the header `name` in the last request received for `path`.
+    pub fn request_header(&self, path: &str, name: &str) -> Option<String> {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state
+            .request_headers
+            .get(path)?
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Panic unless `path` was fetched exactly `expected` times.
+    #[track_caller]
+    pub fn assert_fetches(&self, path: &str, expected: usize) {
+        let fetches = self.fetches(path);
+        assert_eq!(
+            fetches, expected,
+            "expected {} fetches of '{}', the server got {}",
+            expected, path, fetches
+        );
+    }
+
+    /// Remote reader for the fixture at `path`, caching on disk in `storage_directory`.
+    ///
+    /// The resource is named after the last path segment; its extension selects the
+    /// file type (JSON, YAML or TOML, text otherwise).
+    pub fn remote<T>(
+        &self,
+        path: &str,
+        storage_directory: impl AsRef<Path>,
+        timeout: Option<Duration>,
+    ) -> DefaultRemoteResourceReader<T>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let segment = path.rsplit('/').next().unwrap_or(path);
+        let (name, file_type) = match segment.rsplit_once('.') {
+            Some((name, "json")) => (name, ResourceFileType::Json),
+            Some((name, "json5")) => (name, ResourceFileType::Json5),
+            Some((name, "yaml" | "yml")) => (name, ResourceFileType::Yaml),
+            Some((name, "ini")) => (name, ResourceFileType::Ini),
+            Some((name, "properties")) => (name, ResourceFileType::Properties),
+            Some((name, "env")) => (name, ResourceFileType::Dotenv),
+            Some((name, "toml")) => (name, ResourceFileType::Toml),
+            Some((name, _)) => (name, ResourceFileType::Text),
+            None => (segment, ResourceFileType::Text),
+        };
+
+        DefaultRemoteResourceReader::new(ResourceState::new(ResourceProps::new(
+            name,
+            file_type,
+            self.url(path),
+            storage_directory.as_ref(),
+            timeout,
+        )))
+    }
+}
+
+impl Drop for StubServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        // wake up the accept loop so it notices the stop flag
+        let _ = TcpStream::connect(self.address);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Read one request from the stream, count it and answer with the matching fixture.
+fn respond(mut stream: TcpStream, state: &Mutex<ServerState>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    // drain the body, closing with unread data would reset the connection
+    let head_length = request
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map_or(request.len(), |position| position + 4);
+    let content_length = String::from_utf8_lossy(&request[..head_length])
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse::<usize>().ok())?
+        })
+        .unwrap_or(0);
+    let mut remaining = (head_length + content_length).saturating_sub(request.len());
+    while remaining > 0 {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        remaining = remaining.saturating_sub(read);
+    }
+
+    let request = String::from_utf8_lossy(&request[..head_length]);
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or_default();
+    let path = target.split('?').next().unwrap_or_default().to_string();
+    let request_headers: Vec<(String, String)> = request
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            Some((name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        })
+        .collect();
+    let if_none_match = request_headers
+        .iter()
+        .find(|(name, _)| name == "if-none-match")
+        .map(|(_, value)| value.clone());
+
+    let (status, body, headers) = {
+        let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+        *state
+            .requests
+            .entry((method.clone(), path.clone()))
+            .or_default() += 1;
+        state.request_headers.insert(path.clone(), request_headers);
+
+        match state.fixtures.get(&path) {
+            Some(fixture)
+                if if_none_match.is_some()
+                    && fixture.headers.iter().any(|(name, value)| {
+                        name.eq_ignore_ascii_case("etag") && Some(value) == if_none_match.as_ref()
+                    }) =>
+            {
+                (304, String::new(), fixture.headers.clone())
+            }
+            Some(fixture) => (
+                fixture.status,
+                fixture.body.clone(),
+                fixture.headers.clone(),
+            ),
+            None => (404, String::new(), Vec::new()),
+        }
+    };
+
+    let headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}\r\n", name, value))
+        .collect();
+    let head = format!(
+        "HTTP/1.1 {} Stub\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+        status,
+        body.len(),
+        headers
+    );
+    stream.write_all(head.as_bytes())?;
+    if method != "HEAD" {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::test_support::{block_on, test_directory};
+    use crate::traits::StalePolicy;
+
+    #[test]
+    fn test_mock_follows_script_and_counts_calls() {
+        let mock = MockResourceReader::<Vec<u32>>::new("numbers");
+        mock.push_fresh(vec![1])
+            .push_error(ResourceError::Offline)
+            .push_stale(vec![2]);
+
+        assert_eq!(
+            *block_on(mock.get_data_or_default_with(StalePolicy::Never)),
+            vec![1]
+        );
+        assert!(block_on(mock.get_data_or_none_with(StalePolicy::IfError)).is_none());
+        assert_eq!(
+            block_on(mock.get_data_or_none_with(StalePolicy::IfError)),
+            Some(Arc::new(vec![2]))
+        );
+        // the script is used up, the last data is repeated
+        assert!(matches!(
+            block_on(mock.get_data_or_error(true)),
+            Ok(DataResult::Stale(data)) if *data == vec![2]
+        ));
+
+        assert_eq!(mock.calls(), 4);
+        assert_eq!(mock.stale_allowed_calls(), 3);
+        assert_eq!(mock.remaining(), 0);
+        assert_eq!(mock.stats().unwrap().hits, 3);
+    }
+
+    #[test]
+    fn test_mock_shares_its_state() {
+        let mock = MockResourceReader::<Vec<u32>>::new("numbers");
+        let state = mock.shared_state();
+        assert!(Arc::ptr_eq(&state, mock.get_state()));
+
+        // marking the shared state stale is seen by the mock
+        state.mark_as_stale();
+        assert!(mock.is_marked_stale().unwrap());
+    }
+
+    #[test]
+    fn test_stub_server_counts_remote_fetches() {
+        let directory = test_directory("testing-stub");
+        let server = StubServer::start().unwrap();
+        server.fixture("/config/flags.json", r#"{"enabled": true}"#);
+
+        let reader = server.remote::<HashMap<String, bool>>("/config/flags.json", &directory, None);
+        assert!(matches!(
+            reader.get_state().get_file_type(),
+            ResourceFileType::Json
+        ));
+
+        for _ in 0..3 {
+            let data = block_on(reader.get_data_or_default_with(StalePolicy::Never));
+            assert_eq!(data.get("enabled"), Some(&true));
+        }
+        server.assert_fetches("/config/flags.json", 1);
+
+        server.fixture_with_status("/config/flags.json", 503, "");
+        reader.mark_as_stale().unwrap();
+        assert!(block_on(reader.get_data_or_none_with(StalePolicy::Never)).is_none());
+        server.assert_fetches("/config/flags.json", 2);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_injected_faults_exercise_stale_path() {
+        let directory = test_directory("testing-faults");
+        let server = StubServer::start().unwrap();
+        server.fixture("/flags.json", r#"{"enabled": true}"#);
+
+        let faults = Arc::new(FaultInjector::with_seed(7));
+        let clock = Arc::new(ManualClock::default());
+        let reader = DefaultRemoteResourceReader::<HashMap<String, bool>>::new(ResourceState::new(
+            ResourceProps::new(
+                "flags",
+                ResourceFileType::Json,
+                server.url("/flags.json"),
+                &directory,
+                Some(Duration::from_secs(60)),
+            )
+            .with_clock(clock.clone())
+            .with_fault_injector(faults.clone()),
+        ));
+        assert!(block_on(reader.get_data_or_none_with(StalePolicy::Never)).is_some());
+
+        faults.set_probability(Fault::Network, 1.0);
+        clock.advance(Duration::from_secs(61));
+        match block_on(reader.get_data_or_error(true)) {
+            Ok(DataResult::Stale(data)) => assert_eq!(data.get("enabled"), Some(&true)),
+            _ => panic!("Expected stale data while the network fails"),
+        }
+        assert!(block_on(reader.get_data_or_default_with(StalePolicy::Never)).is_empty());
+        assert_eq!(faults.injected(Fault::Network), 2);
+        server.assert_fetches("/flags.json", 1);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_fault_probability_is_respected() {
+        let faults = FaultInjector::with_seed(42).with_fault(Fault::DiskWrite, 0.25);
+        let failures = (0..1000)
+            .filter(|_| faults.check(Fault::DiskWrite).is_err())
+            .count();
+
+        assert!((150..350).contains(&failures), "got {} failures", failures);
+        assert_eq!(faults.injected(Fault::DiskWrite), failures);
+        assert!(faults.check(Fault::Parse).is_ok());
+    }
+
+    #[test]
+    fn test_mock_refuses_stale_data_unless_allowed() {
+        let mock = MockResourceReader::<Vec<u32>>::new("numbers");
+        mock.push_stale(vec![1]).push_stale(vec![2]);
+
+        assert!(matches!(
+            block_on(mock.get_data_or_error(false)),
+            Err(ResourceError::UnableToFreshData)
+        ));
+        assert!(matches!(
+            block_on(mock.get_data_or_error(true)),
+            Ok(DataResult::Stale(data)) if *data == vec![2]
+        ));
+        // the repeated last data is stale as well
+        assert!(matches!(
+            block_on(mock.get_data_or_error(false)),
+            Err(ResourceError::UnableToFreshData)
+        ));
+        assert_eq!(mock.stats().unwrap().hits, 1);
+    }
+
+    #[test]
+    fn test_empty_mock_fails() {
+        let mock = MockResourceReader::<Vec<u32>>::default();
+
+        assert!(matches!(
+            block_on(mock.get_data_or_error(false)),
+            Err(ResourceError::UnableToFreshData)
+        ));
+    }
+}
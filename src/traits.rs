@@ -1,13 +1,33 @@
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use serde::{de::DeserializeOwned, Serialize};
 
-use crate::{base::ResourceState, error::ResourceError};
+use crate::{
+    base::{ResourceState, ResourceStats, SchemaWarning, Subscription, Updates},
+    edit::Edit,
+    error::ResourceError,
+    view::View,
+};
 
 #[derive(Debug, Clone)]
 pub enum ResourceFileType {
     Json,
+    /// [JSON5](https://json5.org), e.g. hand-edited configuration files with comments,
+    /// trailing commas, unquoted keys and single quoted strings. Written back as plain
+    /// JSON.
+    Json5,
     Yaml,
+    /// INI sections of `key = value` pairs, see [`Properties`](Self::Properties) for how
+    /// values are typed.
+    Ini,
+    /// Java-style `.properties` files, read into a flat map. Values are strings that
+    /// are parsed into numbers or booleans where the payload type has such fields.
+    Properties,
+    /// `.env` files of `KEY=VALUE` lines, read into a flat map or, matching variable
+    /// names to fields regardless of case and with `__` separating nested structs,
+    /// into a struct (`SERVER__PORT` fills `server.port`).
+    Dotenv,
     Toml,
     Text,
 }
@@ -17,18 +37,40 @@ impl ResourceFileType {
     pub fn as_str(&self) -> &'static str {
         match self {
             ResourceFileType::Json => "json",
+            ResourceFileType::Json5 => "json5",
             ResourceFileType::Yaml => "yaml",
+            ResourceFileType::Ini => "ini",
+            ResourceFileType::Properties => "properties",
+            ResourceFileType::Dotenv => "env",
             ResourceFileType::Toml => "toml",
             ResourceFileType::Text => "text",
         }
     }
+
+    /// File extensions of an untimestamped file of this type, the usual one first.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            ResourceFileType::Yaml => &["yaml", "yml"],
+            ResourceFileType::Json => &["json"],
+            ResourceFileType::Json5 => &["json5"],
+            ResourceFileType::Ini => &["ini"],
+            ResourceFileType::Properties => &["properties"],
+            ResourceFileType::Dotenv => &["env"],
+            ResourceFileType::Toml => &["toml"],
+            ResourceFileType::Text => &["text"],
+        }
+    }
 }
 
 impl std::fmt::Display for ResourceFileType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ResourceFileType::Json => write!(f, "JSON"),
+            ResourceFileType::Json5 => write!(f, "JSON5"),
             ResourceFileType::Yaml => write!(f, "YAML"),
+            ResourceFileType::Ini => write!(f, "INI"),
+            ResourceFileType::Properties => write!(f, "Properties"),
+            ResourceFileType::Dotenv => write!(f, "Dotenv"),
             ResourceFileType::Toml => write!(f, "TOML"),
             ResourceFileType::Text => write!(f, "Text"),
         }
@@ -44,6 +86,36 @@ impl AsRef<str> for ResourceFileType {
 pub enum DataResult<T> {
     Fresh(T),
     Stale(T),
+    /// The source deliberately holds no data, e.g. a remote answering 404 or 410 with
+    /// [`with_absent_on_not_found`](crate::state_manager::Remote::with_absent_on_not_found).
+    Absent,
+}
+
+/// When stale data may be served instead of fresh data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StalePolicy {
+    /// Only fresh data.
+    Never,
+    /// Stale data when refreshing fails.
+    IfError,
+    /// Cached data without waiting for a refresh, even when stale; the source is only
+    /// consulted when nothing is cached.
+    Always,
+    /// Stale data when refreshing fails and the data is at most this old; older data
+    /// fails with [`ResourceError::StaleTooOld`].
+    MaxAge(Duration),
+}
+
+impl From<bool> for StalePolicy {
+    /// The former `allow_stale` flag: `true` is [`IfError`](Self::IfError), `false` is
+    /// [`Never`](Self::Never).
+    fn from(allow_stale: bool) -> Self {
+        if allow_stale {
+            StalePolicy::IfError
+        } else {
+            StalePolicy::Never
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -51,7 +123,13 @@ pub trait ResourceReader<T>
 where
     T: Send + Sync + DeserializeOwned + Serialize + Default,
 {
-    fn get_state(&self) -> &ResourceState<T>;
+    fn get_state(&self) -> &Arc<ResourceState<T>>;
+
+    /// The state of this reader, to share its memory and disk cache with other readers
+    /// of the same resource.
+    fn shared_state(&self) -> Arc<ResourceState<T>> {
+        Arc::clone(self.get_state())
+    }
 
     fn mark_as_stale(&self) -> Result<(), ResourceError> {
         self.get_state().mark_as_stale();
@@ -64,8 +142,65 @@ where
 
     fn is_fresh(&self) -> Result<bool, ResourceError> {
         Ok(!self.is_marked_stale()?
-            || self.get_state().is_internal_data_fresh()?
-            || self.get_state().is_disk_cached_data_fresh()?)
+            && (self.get_state().is_internal_data_fresh()?
+                || self.get_state().is_disk_cached_data_fresh()?))
+    }
+
+    fn stats(&self) -> Result<ResourceStats, ResourceError> {
+        self.get_state().stats()
+    }
+
+    /// When the newest cached data was stored; `None` when nothing is cached.
+    fn last_refreshed_at(&self) -> Result<Option<SystemTime>, ResourceError> {
+        self.get_state().last_refreshed_at()
+    }
+
+    /// Time since the newest cached data was stored, e.g. to show "updated 5 minutes
+    /// ago"; `None` when nothing is cached.
+    fn data_age(&self) -> Result<Option<Duration>, ResourceError> {
+        self.get_state().data_age()
+    }
+
+    /// Subscribe to the data stored in memory after every refresh.
+    fn subscribe(&self) -> Subscription<T> {
+        self.get_state().subscribe()
+    }
+
+    /// The data replaced by the last refresh and the current data.
+    fn last_change(&self) -> Option<(Arc<T>, Arc<T>)> {
+        self.get_state().last_change()
+    }
+
+    /// Stream of the data stored in memory after every refresh.
+    fn updates(&self) -> Updates<T>
+    where
+        T: 'static,
+    {
+        self.get_state().updates()
+    }
+
+    /// Whether nothing was stored since `generation` was returned by
+    /// [`get_data_with_generation`](Self::get_data_with_generation), e.g. to check
+    /// cheaply that a long computation still works with the current data.
+    fn is_current(&self, generation: u64) -> Result<bool, ResourceError> {
+        self.get_state().is_current(generation)
+    }
+
+    /// Copy of the newest cached data to change and commit at once, see [`Edit`].
+    fn edit(&self) -> Result<Edit<'_, T>, ResourceError>
+    where
+        T: Clone,
+    {
+        Edit::new(self.get_state())
+    }
+
+    /// Derived value recomputed only when the data changes, see [`View`].
+    fn view<U, F>(&self, project: F) -> View<'_, T, U>
+    where
+        Self: Sized + Send + Sync,
+        F: Fn(&T) -> U + Send + Sync + 'static,
+    {
+        View::new(self, project)
     }
 
     async fn get_data_or_error(
@@ -73,6 +208,93 @@ where
         allow_stale: bool,
     ) -> Result<DataResult<Arc<T>>, ResourceError>;
 
+    /// Data with the generation of the memory cache it belongs to, see
+    /// [`is_current`](Self::is_current). Generations increase with every store.
+    ///
+    /// Data not held by the memory cache (e.g. read from disk) gets the generation read
+    /// before the fetch, so a store while fetching makes it not current.
+    async fn get_data_with_generation(
+        &self,
+        allow_stale: bool,
+    ) -> Result<(DataResult<Arc<T>>, u64), ResourceError> {
+        let before = self.get_state().generation()?;
+        let result = self.get_data_or_error(allow_stale).await?;
+        let generation = match &result {
+            DataResult::Fresh(data) | DataResult::Stale(data) => {
+                self.get_state().generation_of(data)?
+            }
+            DataResult::Absent => None,
+        };
+
+        Ok((result, generation.unwrap_or(before)))
+    }
+
+    /// Data with the differences between the document it was parsed from and the
+    /// payload type, see [`SchemaMode::Warn`](crate::state_manager::SchemaMode::Warn).
+    ///
+    /// The warnings are those of the document the resource parsed last: the fetched
+    /// document, or the local file, that the data held in memory comes from. They are
+    /// empty in [`SchemaMode::Ignore`](crate::state_manager::SchemaMode::Ignore), and
+    /// for data loaded from the disk cache (e.g. after a restart) until a document is
+    /// parsed again, as the disk cache is written from the payload type itself.
+    async fn get_data_with_warnings(
+        &self,
+        allow_stale: bool,
+    ) -> Result<(DataResult<Arc<T>>, Vec<SchemaWarning>), ResourceError> {
+        let result = self.get_data_or_error(allow_stale).await?;
+
+        Ok((result, self.get_state().schema_warnings()))
+    }
+
+    /// Data according to the stale policy, see [`StalePolicy`].
+    async fn get_data(&self, policy: StalePolicy) -> Result<DataResult<Arc<T>>, ResourceError> {
+        match policy {
+            StalePolicy::Never => self.get_data_or_error(false).await,
+            StalePolicy::IfError => self.get_data_or_error(true).await,
+            StalePolicy::Always => match self.get_state().get_cached_data()? {
+                Some((data, true)) => Ok(DataResult::Fresh(data)),
+                Some((data, false)) => {
+                    match self.get_state().limit_staleness(DataResult::Stale(data)) {
+                        Ok(result) => Ok(result),
+                        // too old to serve without trying to refresh
+                        Err(_) => self.get_data_or_error(true).await,
+                    }
+                }
+                None => self.get_data_or_error(true).await,
+            },
+            StalePolicy::MaxAge(max_age) => match self.get_data_or_error(true).await? {
+                DataResult::Stale(data) => {
+                    let age = self
+                        .get_state()
+                        .cached_data_age(&data)?
+                        .ok_or(ResourceError::StaleInternalNone)?;
+                    if age > max_age {
+                        return Err(ResourceError::StaleTooOld(age));
+                    }
+                    Ok(DataResult::Stale(data))
+                }
+                result => Ok(result),
+            },
+        }
+    }
+
+    /// Data according to the stale policy, or the default value when unavailable.
+    async fn get_data_or_default_with(&self, policy: StalePolicy) -> Arc<T> {
+        self.get_data_or_none_with(policy)
+            .await
+            .unwrap_or_else(|| T::default().into())
+    }
+
+    /// Data according to the stale policy, or `None` when unavailable.
+    async fn get_data_or_none_with(&self, policy: StalePolicy) -> Option<Arc<T>> {
+        match self.get_data(policy).await {
+            Ok(DataResult::Fresh(data)) => Some(data),
+            Ok(DataResult::Stale(data)) if policy != StalePolicy::Never => Some(data),
+            _ => None,
+        }
+    }
+
+    #[deprecated(note = "use `get_data_or_default_with` with a `StalePolicy`")]
     async fn get_data_or_default(&self, allow_stale: bool) -> Arc<T> {
         match self.get_data_or_error(allow_stale).await {
             Ok(data) => match data {
@@ -84,11 +306,13 @@ where
                         T::default().into()
                     }
                 }
+                DataResult::Absent => T::default().into(),
             },
             Err(_) => T::default().into(),
         }
     }
 
+    #[deprecated(note = "use `get_data_or_none_with` with a `StalePolicy`")]
     async fn get_data_or_none(&self, allow_stale: bool) -> Option<Arc<T>> {
         match self.get_data_or_error(allow_stale).await {
             Ok(data) => match data {
@@ -100,6 +324,7 @@ where
                         None
                     }
                 }
+                DataResult::Absent => None,
             },
             Err(_) => None,
         }
@@ -1,12 +1,23 @@
 use std::{
-    fs,
+    hash::Hasher,
     path::{Path, PathBuf},
     time::{Duration, SystemTime},
 };
 
-use serde::{Deserialize, Serialize};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use fnv::FnvHasher;
+use percent_encoding::percent_decode_str;
+use reqwest::Url;
 
-use crate::{error::ResourceError, traits::ResourceFileType};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    base::{Durability, FileNameFormat, SchemaWarning, SerializationOptions, TimestampFormat},
+    error::ResourceError,
+    file_system::FileSystem,
+    formats,
+    traits::ResourceFileType,
+};
 
 fn parse_by_json_content<T: for<'a> Deserialize<'a>>(
     file_content: &str,
@@ -17,6 +28,12 @@ fn parse_by_json_content<T: for<'a> Deserialize<'a>>(
     }
 }
 
+fn parse_by_json5_content<T: for<'a> Deserialize<'a>>(
+    file_content: &str,
+) -> Result<T, ResourceError> {
+    json5::from_str(file_content).map_err(|_| ResourceError::deserialization("JSON5"))
+}
+
 fn parse_by_yaml_content<T: for<'a> Deserialize<'a>>(
     file_content: &str,
 ) -> Result<T, ResourceError> {
@@ -27,129 +44,907 @@ fn parse_by_yaml_content<T: for<'a> Deserialize<'a>>(
 }
 
 pub fn parse_file<T: for<'a> Deserialize<'a>>(
+    fs: &dyn FileSystem,
     file_path: &Path,
     file_type: &ResourceFileType,
 ) -> Result<T, ResourceError> {
     let get_file_content = || -> Result<String, ResourceError> {
-        match fs::read_to_string(file_path) {
+        match fs.read_to_string(file_path) {
             Ok(content) => Ok(content),
             Err(e) => Err(ResourceError::Io(e)),
         }
     };
 
     match file_type {
-        ResourceFileType::Json => Ok(parse_by_json_content::<T>(&get_file_content()?)?),
-        ResourceFileType::Yaml => Ok(parse_by_yaml_content::<T>(&get_file_content()?)?),
+        ResourceFileType::Json
+        | ResourceFileType::Json5
+        | ResourceFileType::Yaml
+        | ResourceFileType::Ini
+        | ResourceFileType::Properties
+        | ResourceFileType::Dotenv => parse_content::<T>(&get_file_content()?, file_type),
         _ => Err(ResourceError::unsupported_file_type(file_type.as_str())),
     }
 }
 
-/// Parse a manifest file with a filename containing a timestamp "filename-[timestamp].json"
-/// and return the deserialized manifest and the timestamp in secs as a u64.
-pub fn parse_file_with_timestamp_by_path<T: for<'a> Deserialize<'a>>(
+/// Parse a file like [`parse_file`], but report content that does not deserialize as
+/// [`ResourceError::InvalidCacheFile`] with the path and the parser's message.
+pub fn parse_file_reporting<T: for<'a> Deserialize<'a>>(
+    fs: &dyn FileSystem,
     file_path: &Path,
     file_type: &ResourceFileType,
-) -> Result<(T, SystemTime), ResourceError> {
+) -> Result<T, ResourceError> {
+    let content = fs.read_to_string(file_path).map_err(ResourceError::Io)?;
+
+    let parsed = match file_type {
+        ResourceFileType::Json => serde_json::from_str(&content).map_err(|e| e.to_string()),
+        ResourceFileType::Json5 => json5::from_str(&content).map_err(|e| e.to_string()),
+        ResourceFileType::Yaml => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+        ResourceFileType::Ini | ResourceFileType::Properties | ResourceFileType::Dotenv => {
+            parse_content(&content, file_type).map_err(|e| e.to_string())
+        }
+        _ => return Err(ResourceError::unsupported_file_type(file_type.as_str())),
+    };
+
+    parsed.map_err(|error| ResourceError::InvalidCacheFile(file_path.to_path_buf(), error))
+}
+
+/// Parse textual content (e.g. a response body) according to the file type.
+pub fn parse_content<T: for<'a> Deserialize<'a>>(
+    content: &str,
+    file_type: &ResourceFileType,
+) -> Result<T, ResourceError> {
+    match file_type {
+        ResourceFileType::Json => parse_by_json_content::<T>(content),
+        ResourceFileType::Json5 => parse_by_json5_content::<T>(content),
+        ResourceFileType::Yaml => parse_by_yaml_content::<T>(content),
+        ResourceFileType::Ini => formats::deserialize_strings(formats::parse_ini(content)?, "INI"),
+        ResourceFileType::Properties => {
+            formats::deserialize_strings(formats::parse_properties(content)?, "properties")
+        }
+        ResourceFileType::Dotenv => formats::deserialize_env(formats::parse_dotenv(content)?),
+        _ => Err(ResourceError::unsupported_file_type(file_type.as_str())),
+    }
+}
+
+/// Parse the content and report where it differs from the shape of `T`, see
+/// [`SchemaMode::Warn`](crate::state_manager::SchemaMode::Warn).
+///
+/// Unknown fields are the keys serde ignored while deserializing, so renamed, aliased
+/// and flattened fields are recognized. Missing fields are keys of `T` serialized
+/// back that the document lacks, which got their default value. Content that does
+/// not deserialize fails with [`ResourceError::SchemaMismatch`] naming the path of
+/// the failing value.
+pub fn parse_content_with_diagnostics<T: DeserializeOwned + Serialize>(
+    content: &str,
+    file_type: &ResourceFileType,
+) -> Result<(T, Vec<SchemaWarning>), ResourceError> {
+    deserialize_with_diagnostics(parse_content(content, file_type)?, file_type)
+}
+
+/// Deserialize a document decoded from the given file type, reporting where it
+/// differs from the shape of `T` like [`parse_content_with_diagnostics`].
+pub fn deserialize_with_diagnostics<T: DeserializeOwned + Serialize>(
+    document: serde_json::Value,
+    file_type: &ResourceFileType,
+) -> Result<(T, Vec<SchemaWarning>), ResourceError> {
+    let mut warnings = Vec::new();
+    let data: T = match file_type {
+        ResourceFileType::Ini | ResourceFileType::Properties => deserialize_tracked(
+            formats::Strings::new(document.clone(), false),
+            &mut warnings,
+        ),
+        ResourceFileType::Dotenv => {
+            deserialize_tracked(formats::Strings::new(document.clone(), true), &mut warnings)
+        }
+        _ => deserialize_tracked(document.clone(), &mut warnings),
+    }
+    .map_err(|error| {
+        ResourceError::SchemaMismatch(field_path(error.path()), error.inner().to_string())
+    })?;
+
+    let shape = serde_json::to_value(&data).map_err(|_| ResourceError::serialization("JSON"))?;
+    missing_fields(&document, &shape, "", &mut warnings);
+
+    Ok((data, warnings))
+}
+
+/// Deserialize `T`, collecting the keys serde ignored as unknown fields and tracking
+/// the path of a failing value.
+fn deserialize_tracked<'de, D: serde::Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+    warnings: &mut Vec<SchemaWarning>,
+) -> Result<T, serde_path_to_error::Error<D::Error>> {
+    let mut unknown =
+        |path: serde_ignored::Path| warnings.push(SchemaWarning::UnknownField(ignored_path(&path)));
+
+    serde_path_to_error::deserialize(serde_ignored::Deserializer::new(deserializer, &mut unknown))
+}
+
+/// Render a path reported by `serde_path_to_error` like `rules[2].name`.
+fn field_path(path: &serde_path_to_error::Path) -> String {
+    use serde_path_to_error::Segment;
+
+    let mut rendered = String::new();
+    for segment in path.iter() {
+        match segment {
+            Segment::Seq { index } => rendered.push_str(&format!("[{}]", index)),
+            Segment::Map { key } | Segment::Enum { variant: key } => {
+                if !rendered.is_empty() {
+                    rendered.push('.');
+                }
+                rendered.push_str(key);
+            }
+            Segment::Unknown => {}
+        }
+    }
+
+    if rendered.is_empty() {
+        ".".to_string()
+    } else {
+        rendered
+    }
+}
+
+/// Render a path reported by `serde_ignored` like `rules[2].name`.
+fn ignored_path(path: &serde_ignored::Path) -> String {
+    use serde_ignored::Path;
+
+    match path {
+        Path::Root => String::new(),
+        Path::Seq { parent, index } => format!("{}[{}]", ignored_path(parent), index),
+        Path::Map { parent, key } => match ignored_path(parent) {
+            parent if parent.is_empty() => key.clone(),
+            parent => format!("{}.{}", parent, key),
+        },
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => ignored_path(parent),
+    }
+}
+
+fn missing_fields(
+    document: &serde_json::Value,
+    shape: &serde_json::Value,
+    path: &str,
+    warnings: &mut Vec<SchemaWarning>,
+) {
+    use serde_json::Value;
+
+    match (document, shape) {
+        (Value::Object(document), Value::Object(shape)) => {
+            let field = |key: &str| match path {
+                "" => key.to_string(),
+                path => format!("{}.{}", path, key),
+            };
+            for (key, shaped) in shape {
+                match document.get(key) {
+                    Some(value) => missing_fields(value, shaped, &field(key), warnings),
+                    None => warnings.push(SchemaWarning::MissingField(field(key))),
+                }
+            }
+        }
+        (Value::Array(document), Value::Array(shape)) => {
+            for (index, (value, shaped)) in document.iter().zip(shape).enumerate() {
+                missing_fields(value, shaped, &format!("{}[{}]", path, index), warnings);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Extract the timestamp embedded in a filename "filename-[timestamp].json".
+///
+/// Parsing is anchored on the resource `file_name`, so names containing the separator
+/// (`my-config-v2`) work, and files of other resources sharing the prefix
+/// (`feature-flags-123.json` when looking for `feature`) are rejected.
+pub fn parse_timestamp_from_path(
+    file_path: &Path,
+    file_name: &str,
+    format: &FileNameFormat,
+) -> Result<SystemTime, ResourceError> {
     let filename = file_path
         .file_name()
         .ok_or(ResourceError::IncorrectTargetPathName)?
         .to_str()
         .ok_or(ResourceError::InvalidUnicodeEncoding)?;
 
-    let disk_manifest_timestamp_duration = Duration::from_secs(
-        filename
-            .split('-')
-            .next_back()
-            .ok_or(ResourceError::MissingTimestampSeparator)?
-            .split('.')
-            .next()
-            .ok_or(ResourceError::MissingTimestampExtension)?
-            .parse::<u64>()
-            .map_err(|_| ResourceError::TimestampParseError)?,
-    );
+    let timestamp_with_extension = filename
+        .strip_prefix(file_name)
+        .ok_or(ResourceError::IncorrectTargetPathName)?
+        .strip_prefix(format.separator.as_str())
+        .ok_or(ResourceError::MissingTimestampSeparator)?;
 
-    let disk_manifest_timestamp = SystemTime::UNIX_EPOCH + disk_manifest_timestamp_duration;
+    let (timestamp, _extension) = timestamp_with_extension
+        .split_once('.')
+        .filter(|(_, extension)| !extension.is_empty() && !extension.contains('.'))
+        .ok_or(ResourceError::MissingTimestampExtension)?;
 
-    Ok((parse_file(file_path, file_type)?, disk_manifest_timestamp))
+    parse_timestamp(timestamp, &format.timestamp)
 }
 
-/// Get files in a directory that start with a specific prefix.
-pub fn get_files_starts_with(file_name_prefix: &str, dir: &Path) -> Vec<PathBuf> {
-    let mut result_files = Vec::new();
-
-    match fs::read_dir(dir) {
-        Ok(dir_entries) => {
-            for entry_result in dir_entries {
-                match entry_result {
-                    Ok(entry) => {
-                        let file_path = entry.path();
-
-                        if file_path.is_file() {
-                            if let Some(filename) = file_path.file_name() {
-                                match filename.to_str() {
-                                    Some(name) => {
-                                        if name.starts_with(file_name_prefix) {
-                                            result_files.push(file_path);
-                                        }
-                                    }
-                                    None => {
-                                        // TODO: See the 'Tracing callback support' in the 'singleton-registry' crate (reson about first, if not any tracing callback - do error? trace self?)
-                                        // Implement tracing with option to replace by custom callback, so we will be consistent
-
-                                        // warn!(
-                                        //     "Failed to convert filename to string: {:?}",
-                                        //     filename
-                                        // );
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Err(_e) => {
-                        // warn!(
-                        //     "Failed to read directory entry in {}: {} (kind: {:?})",
-                        //     dir.display(),
-                        //     _e,
-                        //     _e.kind()
-                        // );
-                    }
-                }
-            }
+/// Build a filename with an embedded timestamp "filename-[timestamp].json".
+pub fn timestamped_file_name(
+    file_name: &str,
+    timestamp: SystemTime,
+    file_type: &ResourceFileType,
+    format: &FileNameFormat,
+) -> String {
+    format!(
+        "{}{}{}.{}",
+        file_name,
+        format.separator,
+        format_timestamp(timestamp, &format.timestamp),
+        file_type.as_str()
+    )
+}
+
+pub fn format_timestamp(timestamp: SystemTime, format: &TimestampFormat) -> String {
+    let since_epoch = timestamp
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+
+    match format {
+        TimestampFormat::EpochSeconds => since_epoch.as_secs().to_string(),
+        TimestampFormat::EpochMillis => since_epoch.as_millis().to_string(),
+        TimestampFormat::Iso8601 => {
+            let secs = since_epoch.as_secs();
+            let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+            let seconds_of_day = secs % 86_400;
+
+            format!(
+                "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+                year,
+                month,
+                day,
+                seconds_of_day / 3_600,
+                seconds_of_day % 3_600 / 60,
+                seconds_of_day % 60
+            )
         }
-        Err(_e) => {
-            // warn!(
-            //     "Failed to open directory {}: {} (kind: {:?})",
-            //     dir.display(),
-            //     _e,
-            //     _e.kind()
-            // );
+    }
+}
+
+fn parse_timestamp(timestamp: &str, format: &TimestampFormat) -> Result<SystemTime, ResourceError> {
+    let parse_number = |digits: &str| -> Result<u64, ResourceError> {
+        if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(ResourceError::TimestampParseError);
         }
+        digits
+            .parse::<u64>()
+            .map_err(|_| ResourceError::TimestampParseError)
+    };
+
+    let since_epoch = match format {
+        TimestampFormat::EpochSeconds => Duration::from_secs(parse_number(timestamp)?),
+        TimestampFormat::EpochMillis => Duration::from_millis(parse_number(timestamp)?),
+        TimestampFormat::Iso8601 => {
+            let digits = timestamp
+                .strip_suffix('Z')
+                .filter(|digits| digits.len() == 15 && digits.as_bytes()[8] == b'T')
+                .ok_or(ResourceError::TimestampParseError)?;
+
+            let year = parse_number(&digits[0..4])? as i64;
+            let month = parse_number(&digits[4..6])?;
+            let day = parse_number(&digits[6..8])?;
+            let hour = parse_number(&digits[9..11])?;
+            let minute = parse_number(&digits[11..13])?;
+            let second = parse_number(&digits[13..15])?;
+
+            if !(1..=12).contains(&month)
+                || !(1..=31).contains(&day)
+                || hour > 23
+                || minute > 59
+                || second > 60
+            {
+                return Err(ResourceError::TimestampParseError);
+            }
+
+            let days = u64::try_from(days_from_civil(year, month, day))
+                .map_err(|_| ResourceError::TimestampParseError)?;
+
+            Duration::from_secs(days * 86_400 + hour * 3_600 + minute * 60 + second)
+        }
+    };
+
+    Ok(SystemTime::UNIX_EPOCH + since_epoch)
+}
+
+/// Parse an HTTP date (`Sun, 06 Nov 1994 08:49:37 GMT`), also accepting the dashed
+/// day-month-year form used by cookies (`Sun, 06-Nov-1994 08:49:37 GMT`).
+pub fn parse_http_date(value: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+
+    let (_weekday, date) = value.split_once(',')?;
+    let date = date.replace('-', " ");
+    let mut parts = date.split_whitespace();
+
+    let day: u64 = parts.next()?.parse().ok()?;
+    let month = parts.next()?.to_ascii_lowercase();
+    let month = MONTHS.iter().position(|name| month.starts_with(name))? as u64 + 1;
+    let year: i64 = match parts.next()?.parse().ok()? {
+        year @ 0..=69 => year + 2000,
+        year @ 70..=99 => year + 1900,
+        year => year,
+    };
+
+    let mut time = parts
+        .next()?
+        .split(':')
+        .map(|part| part.parse::<u64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+
+    if !parts.next()?.eq_ignore_ascii_case("GMT")
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
     }
 
-    result_files
+    let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+
+    Some(
+        SystemTime::UNIX_EPOCH
+            + Duration::from_secs(days * 86_400 + hour * 3_600 + minute * 60 + second),
+    )
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u64, day: u64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian date (year, month, day) for days since 1970-01-01.
+pub fn civil_from_days(days: i64) -> (i64, u64, u64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u64;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u64;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
 }
 
-pub fn save_to_disk_override<T>(
+/// Decode the payload of a `data:[<media type>][;base64],<data>` URL (RFC 2397).
+pub fn decode_data_url(url: &Url) -> Result<String, ResourceError> {
+    let (metadata, data) = url
+        .as_str()
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(','))
+        .ok_or(ResourceError::InvalidDataUrl)?;
+
+    let data: Vec<u8> = percent_decode_str(data).collect();
+    let is_base64 = metadata
+        .rsplit(';')
+        .next()
+        .is_some_and(|parameter| parameter.eq_ignore_ascii_case("base64"));
+
+    let content = if is_base64 {
+        let data: Vec<u8> = data
+            .into_iter()
+            .filter(|byte| !byte.is_ascii_whitespace())
+            .collect();
+        STANDARD
+            .decode(data)
+            .map_err(|_| ResourceError::InvalidDataUrl)?
+    } else {
+        data
+    };
+
+    String::from_utf8(content).map_err(|_| ResourceError::InvalidUnicodeEncoding)
+}
+
+/// Stable (FNV-1a) hash of the content, used to detect unchanged payloads.
+pub fn content_hash(content: &[u8]) -> u64 {
+    let mut hasher = FnvHasher::default();
+    hasher.write(content);
+    hasher.finish()
+}
+
+/// Get files in a directory that start with a specific prefix.
+///
+/// A missing directory holds no files; any other failure to list it (e.g. missing
+/// permissions) is returned. File names that are not valid UTF-8 are skipped, as
+/// cache files are always named in UTF-8.
+pub fn get_files_starts_with(
+    fs: &dyn FileSystem,
+    file_name_prefix: &str,
+    dir: &Path,
+) -> Result<Vec<PathBuf>, ResourceError> {
+    let file_paths = match fs.list_files(dir) {
+        Ok(file_paths) => file_paths,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(ResourceError::Io(e)),
+    };
+
+    Ok(file_paths
+        .into_iter()
+        .filter(|file_path| {
+            file_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(file_name_prefix))
+        })
+        .collect())
+}
+
+/// Serialize the data into the textual representation of the given file type.
+///
+/// With `options.canonical` the data goes through `serde_json::Value` first, whose
+/// maps keep keys sorted, so equal data always yields byte-identical output.
+pub fn serialize_content<T>(
     data: &T,
-    file_path: &Path,
     file_type: &ResourceFileType,
-) -> Result<(), ResourceError>
+    options: &SerializationOptions,
+) -> Result<String, ResourceError>
 where
     T: Serialize,
 {
-    let stringified_data = match file_type {
-        ResourceFileType::Json => {
+    if options.canonical {
+        let value = serde_json::to_value(data)
+            .map_err(|_| ResourceError::serialization(file_type.as_str()))?;
+        return serialize_value(&value, file_type, options.pretty);
+    }
+
+    serialize_value(data, file_type, options.pretty)
+}
+
+fn serialize_value<T>(
+    data: &T,
+    file_type: &ResourceFileType,
+    pretty: bool,
+) -> Result<String, ResourceError>
+where
+    T: Serialize + ?Sized,
+{
+    match file_type {
+        ResourceFileType::Json | ResourceFileType::Json5 if pretty => {
+            serde_json::to_string_pretty(data).map_err(|_| ResourceError::serialization("JSON"))
+        }
+        ResourceFileType::Json | ResourceFileType::Json5 => {
             serde_json::to_string(data).map_err(|_| ResourceError::serialization("JSON"))
         }
         ResourceFileType::Yaml => {
-            serde_yaml::to_string(data).map_err(|_| ResourceError::deserialization("YAML"))
+            serde_yaml::to_string(data).map_err(|_| ResourceError::serialization("YAML"))
         }
-        _ => {
-            return Err(ResourceError::unsupported_file_type(file_type.as_str()));
+        ResourceFileType::Ini | ResourceFileType::Properties | ResourceFileType::Dotenv => {
+            let document = serde_json::to_value(data)
+                .map_err(|_| ResourceError::serialization(file_type.as_str()))?;
+            match file_type {
+                ResourceFileType::Ini => formats::render_ini(&document),
+                ResourceFileType::Dotenv => formats::render_dotenv(&document),
+                _ => formats::render_properties(&document),
+            }
         }
-    }?;
+        _ => Err(ResourceError::unsupported_file_type(file_type.as_str())),
+    }
+}
 
-    fs::write(file_path, stringified_data).map_err(ResourceError::Io)?;
+/// Write the serialized content to `file_path`, replacing `previous_file`.
+///
+/// When `previous_hash`, the content hash of `previous_file`, matches the content, the
+/// file is only renamed to `file_path` (or left alone when it is `file_path`), sparing
+/// the disk and any file watchers a full rewrite. Otherwise the content is written and
+/// `previous_file` is removed. With a `mode`, the saved file is created with these
+/// Unix permission bits.
+pub fn save_content_to_disk(
+    fs: &dyn FileSystem,
+    content: &str,
+    file_path: &Path,
+    previous_file: Option<&Path>,
+    previous_hash: Option<u64>,
+    durability: Durability,
+    mode: Option<u32>,
+) -> Result<(), ResourceError> {
+    let set_mode = |path: &Path| match mode {
+        Some(mode) => fs.set_mode(path, mode),
+        None => Ok(()),
+    };
+    let write = |path: &Path| match mode {
+        Some(mode) => fs.write_with_mode(path, content.as_bytes(), mode),
+        None => fs.write(path, content.as_bytes()),
+    };
+
+    let directory = file_path.parent().unwrap_or(Path::new(""));
+
+    if let Some(previous_file) = previous_file {
+        if previous_hash == Some(content_hash(content.as_bytes())) {
+            if previous_file != file_path {
+                fs.rename(previous_file, file_path)
+                    .map_err(ResourceError::Io)?;
+            }
+            set_mode(file_path).map_err(ResourceError::Io)?;
+
+            if durability == Durability::Synced {
+                fs.sync_directory(directory).map_err(ResourceError::Io)?;
+            }
+            return Ok(());
+        }
+    }
+    let previous_file = previous_file.filter(|previous_file| *previous_file != file_path);
+
+    // the replaced file is only removed once the new one is in place
+    let remove_previous = || {
+        if let Some(previous_file) = previous_file {
+            let _ = fs.remove_file(previous_file);
+        }
+    };
+
+    fs.create_dir_all(directory).map_err(ResourceError::Io)?;
+
+    if durability == Durability::Direct {
+        write(file_path).map_err(ResourceError::Io)?;
+        remove_previous();
+        return Ok(());
+    }
+
+    // the leading dot keeps the partial file out of the resource's cache listing
+    let temporary_path = directory.join(format!(
+        ".{}.tmp",
+        file_path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default()
+    ));
+
+    let written = write(&temporary_path)
+        .and_then(|_| match durability {
+            Durability::Synced => fs.sync_file(&temporary_path),
+            _ => Ok(()),
+        })
+        .and_then(|_| fs.rename(&temporary_path, file_path));
+
+    if let Err(error) = written {
+        let _ = fs.remove_file(&temporary_path);
+        return Err(ResourceError::Io(error));
+    }
+    remove_previous();
+
+    if durability == Durability::Synced {
+        fs.sync_directory(directory).map_err(ResourceError::Io)?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_system::{MemoryFileSystem, RealFileSystem};
+    use crate::test_support::test_directory;
+    use std::collections::HashMap;
+    use std::fs;
+
+    #[test]
+    fn test_canonical_json_is_key_ordered() {
+        let mut data = HashMap::new();
+        data.insert("zeta", 1);
+        data.insert("alpha", 2);
+        data.insert("mid", 3);
+
+        let output = serialize_content(
+            &data,
+            &ResourceFileType::Json,
+            &SerializationOptions::canonical(),
+        )
+        .unwrap();
+
+        assert_eq!(output, r#"{"alpha":2,"mid":3,"zeta":1}"#);
+    }
+
+    #[test]
+    fn test_json5_extensions_are_accepted() {
+        let content = r#"{
+            // rate limits per tenant
+            "rate": 10, /* requests
+                           per second */
+            "url": "https://example.com/a//b, ]",
+            "quote": "say \"hi\", // not a comment",
+            "tiers": [1, 2, 3,],
+            burst: 0x10,
+            region: 'eu-west',
+        }"#;
+
+        let value: serde_json::Value = parse_content(content, &ResourceFileType::Json5).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "rate": 10,
+                "url": "https://example.com/a//b, ]",
+                "quote": "say \"hi\", // not a comment",
+                "tiers": [1, 2, 3],
+                "burst": 16,
+                "region": "eu-west",
+            })
+        );
+
+        assert!(parse_content::<serde_json::Value>(content, &ResourceFileType::Json).is_err());
+        assert!(matches!(
+            parse_content::<serde_json::Value>("{\"rate\": }", &ResourceFileType::Json5),
+            Err(ResourceError::Deserialization(_))
+        ));
+    }
+
+    #[test]
+    fn test_pretty_json_is_indented() {
+        let data = serde_json::json!({ "key": "value" });
+
+        let output = serialize_content(
+            &data,
+            &ResourceFileType::Json,
+            &SerializationOptions::pretty(),
+        )
+        .unwrap();
+
+        assert_eq!(output, "{\n  \"key\": \"value\"\n}");
+    }
+
+    #[test]
+    fn test_timestamped_file_name_roundtrip() {
+        let timestamp = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let cases = [
+            (FileNameFormat::default(), "my-flags-1700000000.json"),
+            (
+                FileNameFormat::new("@", TimestampFormat::EpochMillis),
+                "my-flags@1700000000123.json",
+            ),
+            (
+                FileNameFormat::new("__", TimestampFormat::Iso8601),
+                "my-flags__20231114T221320Z.json",
+            ),
+        ];
+
+        for (format, expected) in cases {
+            let name =
+                timestamped_file_name("my-flags", timestamp, &ResourceFileType::Json, &format);
+            assert_eq!(name, expected);
+
+            let parsed = parse_timestamp_from_path(Path::new(&name), "my-flags", &format).unwrap();
+            let precision = match format.timestamp {
+                TimestampFormat::EpochMillis => Duration::from_millis(1),
+                _ => Duration::from_secs(1),
+            };
+            assert!(timestamp.duration_since(parsed).unwrap() < precision);
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamp_rejects_malformed_names() {
+        let format = FileNameFormat::default();
+        let parse = |name: &str, file_name: &str| {
+            parse_timestamp_from_path(Path::new(name), file_name, &format)
+        };
+
+        assert!(matches!(
+            parse("other-1.json", "flags"),
+            Err(ResourceError::IncorrectTargetPathName)
+        ));
+        assert!(matches!(
+            parse("flags.json", "flags"),
+            Err(ResourceError::MissingTimestampSeparator)
+        ));
+        assert!(matches!(
+            parse("flags-123", "flags"),
+            Err(ResourceError::MissingTimestampExtension)
+        ));
+        assert!(matches!(
+            parse("flags-123.json.part", "flags"),
+            Err(ResourceError::MissingTimestampExtension)
+        ));
+        assert!(matches!(
+            parse("my-config-v2.json", "my-config"),
+            Err(ResourceError::TimestampParseError)
+        ));
+        assert!(matches!(
+            parse("feature-flags-123.json", "feature"),
+            Err(ResourceError::TimestampParseError)
+        ));
+        assert!(parse("feature-flags-123.json", "feature-flags").is_ok());
+        assert!(matches!(
+            parse_timestamp_from_path(
+                Path::new("flags-20231314T000000Z.json"),
+                "flags",
+                &FileNameFormat::new("-", TimestampFormat::Iso8601)
+            ),
+            Err(ResourceError::TimestampParseError)
+        ));
+    }
+
+    #[test]
+    fn test_civil_date_conversions() {
+        for days in [-719_468, -1, 0, 59, 11_016, 19_675, 2_932_896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+    }
+
+    #[test]
+    fn test_parse_http_date() {
+        let expected = SystemTime::UNIX_EPOCH + Duration::from_secs(784_111_777);
+
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(expected)
+        );
+        assert_eq!(
+            parse_http_date("Sun, 06-Nov-94 08:49:37 GMT"),
+            Some(expected)
+        );
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 CET"), None);
+        assert_eq!(parse_http_date("06 Nov 1994 08:49:37 GMT"), None);
+    }
+
+    #[test]
+    fn test_decode_data_url() {
+        let decode = |url: &str| decode_data_url(&Url::parse(url).unwrap());
+
+        assert_eq!(
+            decode("data:application/json;base64,eyJhIjogMX0=").unwrap(),
+            r#"{"a": 1}"#
+        );
+        assert_eq!(
+            decode("data:application/json,%7B%22a%22%3A%201%7D").unwrap(),
+            r#"{"a": 1}"#
+        );
+        assert_eq!(decode("data:,plain text").unwrap(), "plain text");
+        assert!(matches!(
+            decode("data:application/json;base64"),
+            Err(ResourceError::InvalidDataUrl)
+        ));
+        assert!(matches!(
+            decode("data:;base64,not*base64"),
+            Err(ResourceError::InvalidDataUrl)
+        ));
+    }
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        assert_eq!(content_hash(b"payload"), content_hash(b"payload"));
+        assert_ne!(content_hash(b"payload"), content_hash(b"payload2"));
+    }
+
+    #[test]
+    fn test_listing_reports_unreadable_directory() {
+        let fs = MemoryFileSystem::new();
+        let directory = Path::new("/cache");
+
+        assert!(get_files_starts_with(&fs, "flags", directory)
+            .unwrap()
+            .is_empty());
+
+        fs.insert("/cache/flags-1.json", "{}");
+        fs.insert("/cache/rates-1.json", "{}");
+        assert_eq!(
+            get_files_starts_with(&fs, "flags", directory).unwrap(),
+            vec![directory.join("flags-1.json")]
+        );
+
+        fs.deny(directory);
+        assert!(matches!(
+            get_files_starts_with(&fs, "flags", directory),
+            Err(ResourceError::Io(e)) if e.kind() == std::io::ErrorKind::PermissionDenied
+        ));
+    }
+
+    #[test]
+    fn test_unchanged_content_is_renamed_not_rewritten() {
+        let dir = test_directory("utilities");
+        fs::create_dir_all(&dir).unwrap();
+        let previous = dir.join("flags-1.json");
+        let next = dir.join("flags-2.json");
+        fs::write(&previous, "{}").unwrap();
+
+        save_content_to_disk(
+            &RealFileSystem,
+            "{}",
+            &next,
+            Some(&previous),
+            Some(content_hash(b"{}")),
+            Durability::Direct,
+            None,
+        )
+        .unwrap();
+        assert!(!previous.exists());
+        assert_eq!(fs::read_to_string(&next).unwrap(), "{}");
+
+        // changed content replaces the previous file
+        save_content_to_disk(
+            &RealFileSystem,
+            "{\"a\":1}",
+            &previous,
+            Some(&next),
+            Some(content_hash(b"{}")),
+            Durability::Atomic,
+            None,
+        )
+        .unwrap();
+        assert!(!next.exists());
+        assert_eq!(fs::read_to_string(&previous).unwrap(), "{\"a\":1}");
+
+        // without a recorded hash the previous file is not read, only replaced
+        save_content_to_disk(
+            &RealFileSystem,
+            "{\"a\":1}",
+            &next,
+            Some(&previous),
+            None,
+            Durability::Direct,
+            None,
+        )
+        .unwrap();
+        assert!(!previous.exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_synced_save_replaces_through_temporary_file() {
+        let dir = test_directory("utilities-synced");
+        let file = dir.join("flags-1.json");
+
+        save_content_to_disk(
+            &RealFileSystem,
+            "{\"a\":1}",
+            &file,
+            None,
+            None,
+            Durability::Synced,
+            None,
+        )
+        .unwrap();
+        save_content_to_disk(
+            &RealFileSystem,
+            "{\"a\":2}",
+            &file,
+            None,
+            None,
+            Durability::Atomic,
+            Some(0o600),
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "{\"a\":2}");
+        #[cfg(unix)]
+        assert_eq!(RealFileSystem.mode(&file).unwrap(), Some(0o600));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_canonical_yaml_is_key_ordered() {
+        let mut data = HashMap::new();
+        data.insert("b", 1);
+        data.insert("a", 2);
+
+        let output = serialize_content(
+            &data,
+            &ResourceFileType::Yaml,
+            &SerializationOptions::canonical(),
+        )
+        .unwrap();
+
+        assert_eq!(output, "a: 2\nb: 1\n");
+    }
+}
@@ -0,0 +1,260 @@
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use reqwest::{StatusCode, Url};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{
+    base::{CacheScope, ResourceState},
+    error::ResourceError,
+    traits::{DataResult, ResourceReader},
+};
+
+/// How the reader authenticates against Vault.
+#[derive(Clone)]
+pub enum VaultAuth {
+    /// A static token, sent as `X-Vault-Token`.
+    Token(String),
+    /// AppRole login; the client token is renewed when its lease ends.
+    AppRole { role_id: String, secret_id: String },
+}
+
+/// Version of the KV secrets engine mounted at the configured path.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KvVersion {
+    V1,
+    #[default]
+    V2,
+}
+
+/// A token obtained through AppRole login.
+struct LoginToken {
+    token: String,
+    expires_at: Option<Instant>,
+}
+
+/// Reader fetching a secret from Vault's KV engine.
+///
+/// The resource URL is the Vault address (e.g. `https://vault:8200`). Secrets are
+/// kept in memory only, until the earlier of the resource timeout and the end of the
+/// secret's lease; they are never written to the disk cache.
+pub struct VaultResourceReader<T> {
+    state: Arc<ResourceState<T>>,
+    client: reqwest::Client,
+    mount: String,
+    path: String,
+    kv_version: KvVersion,
+    auth: VaultAuth,
+    login: Mutex<Option<LoginToken>>,
+}
+
+impl<T> VaultResourceReader<T> {
+    /// Create a reader for the secret at `path` of the KV engine mounted at `mount`.
+    pub fn new(
+        state: impl Into<Arc<ResourceState<T>>>,
+        mount: impl Into<String>,
+        path: impl Into<String>,
+        auth: VaultAuth,
+    ) -> Self {
+        Self {
+            state: state.into(),
+            client: reqwest::Client::new(),
+            mount: mount.into().trim_matches('/').to_string(),
+            path: path.into().trim_matches('/').to_string(),
+            kv_version: KvVersion::default(),
+            auth,
+            login: Mutex::new(None),
+        }
+    }
+
+    /// Select the KV engine version (defaults to version 2).
+    pub fn with_kv_version(mut self, kv_version: KvVersion) -> Self {
+        self.kv_version = kv_version;
+        self
+    }
+
+    /// Perform requests through the given HTTP client, e.g. one trusting a private CA.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<T> ResourceReader<T> for VaultResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    fn get_state(&self) -> &Arc<ResourceState<T>> {
+        &self.state
+    }
+
+    async fn get_data_or_error(
+        &self,
+        allow_stale: bool,
+    ) -> Result<DataResult<Arc<T>>, ResourceError> {
+        let fetch = async {
+            let (data, lease) = self.fetch_secret().await?;
+            Ok((data, lease.map(|lease| self.get_state().now() + lease)))
+        };
+
+        self.get_state()
+            .load_or_refresh(allow_stale, CacheScope::MemoryOnly, fetch)
+            .await
+    }
+}
+
+/// Extract the secret payload and its lease from a KV read response.
+fn parse_secret(response: &Value, kv_version: KvVersion) -> Option<(Value, Option<Duration>)> {
+    let data = match kv_version {
+        KvVersion::V1 => response.get("data")?,
+        KvVersion::V2 => response.get("data")?.get("data")?,
+    };
+
+    // a zero lease means the secret is not leased
+    let lease = response
+        .get("lease_duration")
+        .and_then(Value::as_u64)
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs);
+
+    Some((data.clone(), lease))
+}
+
+impl<T> VaultResourceReader<T>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    fn endpoint(&self, path: &str) -> Result<Url, ResourceError> {
+        let address = self.get_state().get_url().as_str().trim_end_matches('/');
+
+        Url::parse(&format!("{}/v1/{}", address, path))
+            .map_err(|e| ResourceError::Vault(format!("invalid address: {}", e)))
+    }
+
+    fn secret_endpoint(&self) -> Result<Url, ResourceError> {
+        match self.kv_version {
+            KvVersion::V1 => self.endpoint(&format!("{}/{}", self.mount, self.path)),
+            KvVersion::V2 => self.endpoint(&format!("{}/data/{}", self.mount, self.path)),
+        }
+    }
+
+    async fn token(&self) -> Result<String, ResourceError> {
+        let (role_id, secret_id) = match &self.auth {
+            VaultAuth::Token(token) => return Ok(token.clone()),
+            VaultAuth::AppRole { role_id, secret_id } => (role_id, secret_id),
+        };
+
+        if let Some(login) = self
+            .login
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+        {
+            if login
+                .expires_at
+                .is_none_or(|expires_at| Instant::now() < expires_at)
+            {
+                return Ok(login.token.clone());
+            }
+        }
+
+        let response = self
+            .client
+            .post(self.endpoint("auth/approle/login")?)
+            .json(&serde_json::json!({ "role_id": role_id, "secret_id": secret_id }))
+            .send()
+            .await
+            .map_err(ResourceError::network)?;
+
+        if !response.status().is_success() {
+            return Err(ResourceError::Vault(format!(
+                "AppRole login failed with status {}",
+                response.status()
+            )));
+        }
+
+        let body: Value = response.json().await.map_err(ResourceError::network)?;
+        let auth = body
+            .get("auth")
+            .ok_or_else(|| ResourceError::Vault("AppRole login returned no token".to_string()))?;
+        let token = auth
+            .get("client_token")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ResourceError::Vault("AppRole login returned no token".to_string()))?
+            .to_string();
+        let expires_at = auth
+            .get("lease_duration")
+            .and_then(Value::as_u64)
+            .filter(|seconds| *seconds > 0)
+            .map(|seconds| Instant::now() + Duration::from_secs(seconds));
+
+        *self.login.lock().unwrap_or_else(PoisonError::into_inner) = Some(LoginToken {
+            token: token.clone(),
+            expires_at,
+        });
+
+        Ok(token)
+    }
+
+    async fn fetch_secret(&self) -> Result<(Arc<T>, Option<Duration>), ResourceError> {
+        let response = self
+            .client
+            .get(self.secret_endpoint()?)
+            .header("X-Vault-Token", self.token().await?)
+            .send()
+            .await
+            .map_err(ResourceError::network)?;
+
+        if response.status() == StatusCode::FORBIDDEN {
+            // a revoked AppRole token is replaced on the next attempt
+            *self.login.lock().unwrap_or_else(PoisonError::into_inner) = None;
+        }
+
+        if !response.status().is_success() {
+            return Err(ResourceError::Vault(format!(
+                "reading '{}/{}' failed with status {}",
+                self.mount,
+                self.path,
+                response.status()
+            )));
+        }
+
+        let body: Value = response.json().await.map_err(ResourceError::network)?;
+        let (data, lease) = parse_secret(&body, self.kv_version)
+            .ok_or_else(|| ResourceError::Vault("the response holds no secret".to_string()))?;
+
+        Ok((Arc::new(self.get_state().parse_value(data)?), lease))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_secret_of_both_engine_versions() {
+        let v1 = json!({ "lease_duration": 600, "data": { "password": "s3cret" } });
+        let v2 = json!({
+            "lease_duration": 0,
+            "data": { "data": { "password": "s3cret" }, "metadata": { "version": 3 } }
+        });
+
+        assert_eq!(
+            parse_secret(&v1, KvVersion::V1),
+            Some((
+                json!({ "password": "s3cret" }),
+                Some(Duration::from_secs(600))
+            ))
+        );
+        assert_eq!(
+            parse_secret(&v2, KvVersion::V2),
+            Some((json!({ "password": "s3cret" }), None))
+        );
+        assert_eq!(parse_secret(&v1, KvVersion::V2), None);
+    }
+}
@@ -0,0 +1,133 @@
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    error::ResourceError,
+    traits::{DataResult, ResourceReader},
+};
+
+type Projection<T, U> = dyn Fn(&T) -> U + Send + Sync;
+
+/// Derived value of a resource, e.g. an index or lookup table built from it.
+///
+/// The projection runs again only when the reader returns other data than last time
+/// (a new cache generation); otherwise the previously built `Arc<U>` is returned.
+/// Created with [`ResourceReader::view`].
+pub struct View<'a, T, U> {
+    reader: Source<'a, T>,
+    project: Box<Projection<T, U>>,
+    /// Source data of the last projection, and its result. The weak reference keeps
+    /// the source allocation, so its address is never reused by newer data.
+    cached: Mutex<Option<(Weak<T>, Arc<U>)>>,
+}
+
+/// The reader a view projects, borrowed or owned by the view.
+enum Source<'a, T> {
+    Borrowed(&'a (dyn ResourceReader<T> + Send + Sync)),
+    Owned(Box<dyn ResourceReader<T> + Send + Sync + 'a>),
+}
+
+impl<'a, T, U> View<'a, T, U>
+where
+    T: Send + Sync + DeserializeOwned + Serialize + Default,
+{
+    pub fn new(
+        reader: &'a (dyn ResourceReader<T> + Send + Sync),
+        project: impl Fn(&T) -> U + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            reader: Source::Borrowed(reader),
+            project: Box::new(project),
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// View taking ownership of the reader, see
+    /// [`ResourceReaderExt::cached_view`](crate::ResourceReaderExt::cached_view).
+    pub fn owned(
+        reader: impl ResourceReader<T> + Send + Sync + 'a,
+        project: impl Fn(&T) -> U + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            reader: Source::Owned(Box::new(reader)),
+            project: Box::new(project),
+            cached: Mutex::new(None),
+        }
+    }
+
+    fn reader(&self) -> &(dyn ResourceReader<T> + Send + Sync + 'a) {
+        match &self.reader {
+            Source::Borrowed(reader) => *reader,
+            Source::Owned(reader) => reader.as_ref(),
+        }
+    }
+
+    /// The projection of the reader's data, see
+    /// [`get_data_or_error`](ResourceReader::get_data_or_error).
+    pub async fn get(&self, allow_stale: bool) -> Result<DataResult<Arc<U>>, ResourceError> {
+        Ok(match self.reader().get_data_or_error(allow_stale).await? {
+            DataResult::Fresh(data) => DataResult::Fresh(self.project(&data)?),
+            DataResult::Stale(data) => DataResult::Stale(self.project(&data)?),
+            DataResult::Absent => DataResult::Absent,
+        })
+    }
+
+    /// The projection of the reader's data, or `None` when unavailable, see
+    /// [`get_data_or_none`](ResourceReader::get_data_or_none).
+    pub async fn get_or_none(&self, allow_stale: bool) -> Option<Arc<U>> {
+        match self.get(allow_stale).await {
+            Ok(DataResult::Fresh(data)) => Some(data),
+            Ok(DataResult::Stale(data)) if allow_stale => Some(data),
+            _ => None,
+        }
+    }
+
+    fn project(&self, data: &Arc<T>) -> Result<Arc<U>, ResourceError> {
+        let mut cached = self.cached.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some((source, projected)) = cached.as_ref() {
+            if std::ptr::eq(source.as_ptr(), Arc::as_ptr(data)) {
+                return Ok(Arc::clone(projected));
+            }
+        }
+
+        let projected = Arc::new((self.project)(data));
+        *cached = Some((Arc::downgrade(data), Arc::clone(&projected)));
+
+        Ok(projected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::block_on;
+    use crate::testing::MockResourceReader;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_projection_is_rebuilt_only_for_new_data() {
+        let users = MockResourceReader::<Vec<(u32, String)>>::new("users");
+        users.push_fresh(vec![(1, "ada".to_string())]);
+
+        let builds = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&builds);
+        let by_id = users.view(move |users: &Vec<(u32, String)>| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            users.iter().cloned().collect::<HashMap<u32, String>>()
+        });
+
+        for _ in 0..3 {
+            let index = block_on(by_id.get_or_none(false)).unwrap();
+            assert_eq!(index[&1], "ada");
+        }
+        assert_eq!(builds.load(Ordering::Relaxed), 1);
+
+        users.push_fresh(vec![(1, "ada".to_string()), (2, "grace".to_string())]);
+        let index = block_on(by_id.get_or_none(false)).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(builds.load(Ordering::Relaxed), 2);
+    }
+}
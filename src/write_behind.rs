@@ -0,0 +1,39 @@
+//! Background persistence of the disk cache for write-behind resources.
+
+use std::sync::{mpsc, LazyLock};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Single thread performing the queued disk writes in submission order, so the saves
+/// of one resource never overtake each other.
+static WRITER: LazyLock<mpsc::Sender<Job>> = LazyLock::new(|| {
+    let (sender, receiver) = mpsc::channel::<Job>();
+
+    thread::Builder::new()
+        .name("resourcely-writer".to_string())
+        .spawn(move || {
+            for job in receiver {
+                job();
+            }
+        })
+        .expect("Unable to start the disk writer thread");
+
+    sender
+});
+
+/// Queue a disk write; it runs inline when the writer thread is gone.
+pub(crate) fn submit(job: impl FnOnce() + Send + 'static) {
+    if let Err(mpsc::SendError(job)) = WRITER.send(Box::new(job)) {
+        job();
+    }
+}
+
+/// Block until every write queued so far has finished.
+pub(crate) fn flush() {
+    let (done, finished) = mpsc::channel();
+    submit(move || {
+        let _ = done.send(());
+    });
+    let _ = finished.recv();
+}